@@ -0,0 +1,157 @@
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+// alias [-p] [NAME[=VALUE] ...]
+// without arguments every alias is printed, a NAME alone prints that alias
+pub fn alias(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let mut args = cmd.args.as_slice();
+    if args.first().is_some_and(|arg| arg == "-p") {
+        args = &args[1..];
+    }
+
+    if let Some(option) = args.first().filter(|arg| arg.starts_with('-') && *arg != "-") {
+        shell_state
+            .output
+            .write_stderr(format!("alias: {}: invalid option\n", option));
+        return ExitStatus::USAGE;
+    }
+
+    if args.is_empty() {
+        let definitions: String = shell_state
+            .aliases
+            .iter()
+            .map(|(name, value)| format_alias(name, value) + "\n")
+            .collect();
+        shell_state.output.write_stdout(definitions);
+
+        return ExitStatus::SUCCESS;
+    }
+
+    let mut status = ExitStatus::SUCCESS;
+    for arg in args {
+        match arg.split_once('=') {
+            Some((name, value)) if is_valid_name(name) => {
+                shell_state
+                    .aliases
+                    .insert(String::from(name), String::from(value));
+            }
+            Some((name, _)) => {
+                shell_state
+                    .output
+                    .write_stderr(format!("alias: `{}': invalid alias name\n", name));
+                status = ExitStatus::FAILURE;
+            }
+            None => match shell_state.aliases.get(arg) {
+                Some(value) => {
+                    let definition = format_alias(arg, value) + "\n";
+                    shell_state.output.write_stdout(definition);
+                }
+                None => {
+                    shell_state
+                        .output
+                        .write_stderr(format!("alias: {}: not found\n", arg));
+                    status = ExitStatus::FAILURE;
+                }
+            },
+        }
+    }
+
+    status
+}
+
+// unalias [-a] NAME [NAME ...]
+pub fn unalias(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    if cmd.args.first().is_some_and(|arg| arg == "-a") {
+        shell_state.aliases.clear();
+        return ExitStatus::SUCCESS;
+    }
+
+    if cmd.args.is_empty() || cmd.args[0].starts_with('-') {
+        shell_state
+            .output
+            .write_stderr(String::from("unalias: usage: unalias [-a] name [name ...]\n"));
+        return ExitStatus::USAGE;
+    }
+
+    let mut status = ExitStatus::SUCCESS;
+    for name in &cmd.args {
+        if shell_state.aliases.remove(name).is_none() {
+            shell_state
+                .output
+                .write_stderr(format!("unalias: {}: not found\n", name));
+            status = ExitStatus::FAILURE;
+        }
+    }
+
+    status
+}
+
+// the definition can be read back by the shell, quotes in the value are escaped
+// >> format_alias("say", "echo 'hi'")
+// "alias say='echo '\''hi'\'''"
+pub fn format_alias(name: &str, value: &str) -> String {
+    format!("alias {}='{}'", name, value.replace('\'', "'\\''"))
+}
+
+// characters that have a meaning for the shell can't be part of the name
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains(&[' ', '\t', '\n', '/', '$', '`', '\\', '\'', '"', ';', '&', '|', '(', ')', '<', '>'][..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_define_and_list() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            alias(&Command::new("alias", &["ll=ls -la", "say=echo 'hi'"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(state.aliases.get("ll"), Some(&String::from("ls -la")));
+
+        alias(&Command::new("alias", &[]), &mut state);
+        alias(&Command::new("alias", &["-p", "ll"]), &mut state);
+        assert_eq!(
+            state.output.stdout(),
+            b"alias ll='ls -la'\nalias say='echo '\\''hi'\\'''\nalias ll='ls -la'\n"
+        );
+    }
+
+    #[test]
+    fn test_alias_errors() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            alias(&Command::new("alias", &["missing", "a/b=c"]), &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(
+            state.output.stderr(),
+            b"alias: missing: not found\nalias: `a/b': invalid alias name\n"
+        );
+        assert!(state.aliases.is_empty());
+    }
+
+    #[test]
+    fn test_unalias() {
+        let mut state = ShellState::default();
+        state.define_default_aliases();
+
+        assert_eq!(
+            unalias(&Command::new("unalias", &["ll", "ll"]), &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(state.output.stderr(), b"unalias: ll: not found\n");
+        assert!(state.aliases.contains_key("la"));
+
+        assert_eq!(unalias(&Command::new("unalias", &["-a"]), &mut state), ExitStatus::SUCCESS);
+        assert!(state.aliases.is_empty());
+
+        assert_eq!(unalias(&Command::new("unalias", &[]), &mut state), ExitStatus::USAGE);
+    }
+}
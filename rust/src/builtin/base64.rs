@@ -0,0 +1,204 @@
+use crate::builtin::{read_input, write_file_error};
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const DEFAULT_WRAP: usize = 76;
+
+// base64 [-d] [-w COLS] [FILE]
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let mut decode = false;
+    let mut wrap = DEFAULT_WRAP;
+    let mut path = String::from("-");
+    let mut args = cmd.args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-d" | "--decode" => decode = true,
+            "-w" => match args.next().map(|cols| cols.parse()) {
+                Some(Ok(cols)) => wrap = cols,
+                _ => return fail("invalid wrap size", ExitStatus::USAGE, shell_state),
+            },
+            arg if arg.len() > 1 && arg.starts_with('-') => {
+                let message = format!("invalid option -- '{}'", &arg[1..]);
+                return fail(&message, ExitStatus::USAGE, shell_state);
+            }
+            arg => path = String::from(arg),
+        }
+    }
+
+    let input = match read_input(&path, shell_state) {
+        Ok(input) => input,
+        Err(error) => {
+            write_file_error("base64", &path, &error, shell_state);
+            return ExitStatus::FAILURE;
+        }
+    };
+
+    if decode {
+        match decode_bytes(&input) {
+            Some(bytes) => shell_state.output.write_stdout(bytes),
+            None => return fail("invalid input", ExitStatus::FAILURE, shell_state),
+        }
+    } else {
+        shell_state
+            .output
+            .write_stdout(wrap_lines(&encode(&input), wrap));
+    }
+
+    ExitStatus::SUCCESS
+}
+
+fn fail(message: &str, status: ExitStatus, shell_state: &mut ShellState) -> ExitStatus {
+    shell_state
+        .output
+        .write_stderr(format!("base64: {}\n", message));
+    status
+}
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut result = String::new();
+
+    for group in bytes.chunks(3) {
+        let b = [
+            group[0],
+            *group.get(1).unwrap_or(&0),
+            *group.get(2).unwrap_or(&0),
+        ];
+        let indexes = [
+            b[0] >> 2,
+            (b[0] & 0b11) << 4 | b[1] >> 4,
+            (b[1] & 0b1111) << 2 | b[2] >> 6,
+            b[2] & 0b111111,
+        ];
+
+        for (i, index) in indexes.iter().enumerate() {
+            match i <= group.len() {
+                true => result.push(ALPHABET[*index as usize] as char),
+                false => result.push('='),
+            }
+        }
+    }
+
+    result
+}
+
+// whitespace is ignored, anything else outside of the alphabet is invalid
+pub fn decode_bytes(input: &[u8]) -> Option<Vec<u8>> {
+    let mut values = Vec::new();
+    let mut padding = 0;
+
+    for byte in input.iter().filter(|byte| !byte.is_ascii_whitespace()) {
+        match byte {
+            b'=' => padding += 1,
+            _ if padding > 0 => return None,
+            byte => values.push(ALPHABET.iter().position(|c| c == byte)? as u8),
+        }
+    }
+
+    if (values.len() + padding) % 4 != 0 || padding > 2 {
+        return None;
+    }
+
+    let mut result = Vec::new();
+    for group in values.chunks(4) {
+        let v = [
+            group[0],
+            *group.get(1)?,
+            *group.get(2).unwrap_or(&0),
+            *group.get(3).unwrap_or(&0),
+        ];
+        let bytes = [
+            v[0] << 2 | v[1] >> 4,
+            v[1] << 4 | v[2] >> 2,
+            v[2] << 6 | v[3],
+        ];
+
+        result.extend_from_slice(&bytes[..group.len() - 1]);
+    }
+
+    Some(result)
+}
+
+// a wrap of 0 disables wrapping, output always ends with a newline
+fn wrap_lines(encoded: &str, wrap: usize) -> String {
+    if encoded.is_empty() {
+        return String::new();
+    }
+
+    if wrap == 0 {
+        return format!("{}\n", encoded);
+    }
+
+    encoded
+        .as_bytes()
+        .chunks(wrap)
+        .map(|line| format!("{}\n", String::from_utf8_lossy(line)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base64(args: &[&str], stdin: &[u8]) -> (ExitStatus, ShellState) {
+        let mut state = ShellState::default();
+        state.output.stdin = Some(stdin.to_vec());
+
+        let cmd = Command::new("base64", args);
+
+        (run(&cmd, &mut state), state)
+    }
+
+    #[test]
+    fn test_encode() {
+        let expected = vec![
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+
+        for (input, output) in expected {
+            assert_eq!(encode(input.as_bytes()), output);
+            assert_eq!(
+                decode_bytes(output.as_bytes()),
+                Some(input.as_bytes().to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid_input() {
+        assert_eq!(decode_bytes(b"Zm9"), None);
+        assert_eq!(decode_bytes(b"Zm9v!"), None);
+        assert_eq!(decode_bytes(b"Zg==Zg=="), None);
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let bytes = vec![0xff, 0x00, 0xfe, 0x80];
+        let encoded = base64(&[], &bytes).1.output.stdout();
+        let decoded = base64(&["-d"], &encoded).1.output.stdout();
+
+        assert_eq!(encoded, b"/wD+gA==\n");
+        assert_eq!(decoded, bytes);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let (_, state) = base64(&["-w", "4"], b"foobar");
+        assert_eq!(state.output.stdout(), b"Zm9v\nYmFy\n");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let (status, state) = base64(&["-d"], b"!");
+        assert_eq!(state.output.stderr(), b"base64: invalid input\n");
+        assert_eq!(status, ExitStatus::FAILURE);
+    }
+}
@@ -1,12 +1,12 @@
 
-use crate::shell_state::ShellState;
+use crate::exit_status::ExitStatus;
 
-pub fn false_builtin(shell_state: &mut ShellState) {
-    shell_state.output.code = Some(1);
+pub fn false_builtin() -> ExitStatus {
+    ExitStatus::FAILURE
 }
 
-pub fn true_builtin(shell_state: &mut ShellState) {
-    shell_state.output.code = Some(0);
+pub fn true_builtin() -> ExitStatus {
+    ExitStatus::SUCCESS
 }
 
 #[cfg(test)]
@@ -15,15 +15,11 @@ mod tests {
 
     #[test]
     fn test_false() {
-        let mut state = ShellState::default();
-        false_builtin(&mut state);
-        assert_eq!(state.output.code, Some(1));
+        assert_eq!(false_builtin(), ExitStatus::FAILURE);
     }
 
     #[test]
     fn test_true() {
-        let mut state = ShellState::default();
-        true_builtin(&mut state);
-        assert_eq!(state.output.code, Some(0));
+        assert_eq!(true_builtin(), ExitStatus::SUCCESS);
     }
 }
@@ -1,6 +1,76 @@
+use crate::builtin::{read_input, write_file_error};
 use crate::command::Command;
+use crate::exit_status::ExitStatus;
 use crate::shell_state::ShellState;
 
-pub fn run(_cmd: &Command, shell_state: &mut ShellState) {
-    shell_state.output.set_stderr(1, String::from("Some error"));
+// cat [FILE]...
+// concatenates files byte for byte, "-" or no files reads stdin
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let stdin = [String::from("-")];
+    let paths = match cmd.args.is_empty() {
+        true => &stdin[..],
+        false => &cmd.args[..],
+    };
+
+    let mut status = ExitStatus::SUCCESS;
+    for path in paths {
+        match read_input(path, shell_state) {
+            Ok(bytes) => shell_state.output.write_stdout(bytes),
+            Err(error) => {
+                write_file_error("cat", path, &error, shell_state);
+                status = ExitStatus::FAILURE;
+            }
+        }
+    }
+
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn cat(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("cat", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_cat_stdin() {
+        let mut state = ShellState::default();
+        state.output.stdin = Some(vec![0xff, b'\n']);
+
+        let status = cat(&[], &mut state);
+
+        assert_eq!(state.output.stdout(), vec![0xff, b'\n']);
+        assert_eq!(status, ExitStatus::SUCCESS);
+    }
+
+    #[test]
+    fn test_cat_files() {
+        let mut state = ShellState::default();
+        state.fs.write(Path::new("/a"), &[0, 1]).unwrap();
+        state.fs.write(Path::new("/b"), &[2]).unwrap();
+
+        cat(&["a", "/b"], &mut state);
+
+        assert_eq!(state.output.stdout(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_cat_missing_file() {
+        let mut state = ShellState::default();
+        state.fs.write(Path::new("/a"), b"a").unwrap();
+
+        let status = cat(&["missing", "a"], &mut state);
+
+        assert_eq!(state.output.stdout(), b"a");
+        assert_eq!(
+            state.output.stderr(),
+            b"cat: missing: No such file or directory\n"
+        );
+        assert_eq!(status, ExitStatus::FAILURE);
+    }
 }
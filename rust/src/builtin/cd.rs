@@ -0,0 +1,116 @@
+use std::io;
+
+use crate::builtin::write_file_error;
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+// cd [DIR]
+// changes the current directory, no DIR goes to HOME and "-" to the previous directory
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (target, print) = match cmd.args.as_slice() {
+        [] => match shell_state.parameter("HOME") {
+            Some(home) => (home, false),
+            None => return usage_error("HOME not set", shell_state),
+        },
+        [dir] if dir == "-" => match shell_state.parameter("OLDPWD") {
+            Some(dir) => (dir, true),
+            None => return usage_error("OLDPWD not set", shell_state),
+        },
+        [dir] => (dir.clone(), false),
+        _ => return usage_error("too many arguments", shell_state),
+    };
+
+    let path = shell_state.resolve_path(&target);
+    let error = match shell_state.fs.metadata(&path) {
+        Ok(_) if shell_state.fs.is_dir(&path) => None,
+        Ok(_) => Some(io::Error::from(io::ErrorKind::NotADirectory)),
+        Err(error) => Some(error),
+    };
+
+    if let Some(error) = error {
+        write_file_error("cd", &target, &error, shell_state);
+        return ExitStatus::FAILURE;
+    }
+
+    let previous = std::mem::replace(
+        &mut shell_state.current_dir,
+        path.to_string_lossy().to_string(),
+    );
+    shell_state.set_variable("OLDPWD", previous);
+
+    if print {
+        let current_dir = format!("{}\n", shell_state.current_dir);
+        shell_state.output.write_stdout(current_dir);
+    }
+
+    ExitStatus::SUCCESS
+}
+
+fn usage_error(message: &str, shell_state: &mut ShellState) -> ExitStatus {
+    shell_state
+        .output
+        .write_stderr(format!("cd: {}\n", message));
+    ExitStatus::FAILURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{FileSystem, MemoryFileSystem};
+    use std::path::Path;
+    use std::rc::Rc;
+
+    fn cd(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("cd", args);
+
+        run(&cmd, shell_state)
+    }
+
+    fn state() -> ShellState {
+        let fs = MemoryFileSystem::default();
+        fs.create_dir_all(Path::new("/home/me/src"));
+        fs.write(Path::new("/file"), b"").unwrap();
+
+        ShellState::new(Rc::new(fs), String::from("/"))
+    }
+
+    #[test]
+    fn test_cd_relative_and_back() {
+        let mut state = state();
+
+        assert_eq!(cd(&["home/me"], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(cd(&["./src/.."], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(state.current_dir, "/home/me");
+
+        cd(&["-"], &mut state);
+        assert_eq!(state.current_dir, "/home/me");
+        assert_eq!(state.output.stdout(), b"/home/me\n");
+    }
+
+    #[test]
+    fn test_cd_home() {
+        let mut state = state();
+
+        assert_eq!(cd(&[], &mut state), ExitStatus::FAILURE);
+        assert_eq!(state.output.stderr(), b"cd: HOME not set\n");
+
+        state
+            .set_variable("HOME", String::from("/home/me"));
+        cd(&[], &mut state);
+        assert_eq!(state.current_dir, "/home/me");
+    }
+
+    #[test]
+    fn test_cd_errors() {
+        let mut state = state();
+
+        assert_eq!(cd(&["missing"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(cd(&["file"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(
+            state.output.stderr(),
+            b"cd: missing: No such file or directory\ncd: file: Not a directory\n".to_vec()
+        );
+        assert_eq!(state.current_dir, "/");
+    }
+}
@@ -1,7 +1,9 @@
+use crate::exit_status::ExitStatus;
 use crate::shell_state::ShellState;
 
-pub fn run(shell_state: &mut ShellState) {
-    shell_state.output.set_stdout(String::from("\u{001b}c"))
+pub fn run(shell_state: &mut ShellState) -> ExitStatus {
+    shell_state.output.write_stdout("\u{001b}c");
+    ExitStatus::SUCCESS
 }
 
 #[cfg(test)]
@@ -15,6 +17,6 @@ mod tests {
 
         run(&mut state);
 
-        assert_eq!(state.output.stdout, Some(String::from("\u{001b}c")));
+        assert_eq!(state.output.stdout(), b"\x1bc");
     }
 }
@@ -0,0 +1,173 @@
+use std::str::FromStr;
+
+use crate::builtin::type_builtin::{self, Style};
+use crate::builtin::{self, BuiltinCommands};
+use crate::command::Command;
+use crate::executor;
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+// command [-v | -V] NAME [ARG...]
+// runs NAME as a builtin or file even when it is an alias, -v prints what it runs as
+// and -V describes it like `type` does
+pub fn command(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    match cmd.args.first().map(String::as_str) {
+        Some("-v") => {
+            type_builtin::describe_names("command", &cmd.args[1..], Style::Name, shell_state)
+        }
+        Some("-V") => {
+            type_builtin::describe_names("command", &cmd.args[1..], Style::Sentence, shell_state)
+        }
+        Some(option) if option.starts_with('-') && option != "-" => {
+            shell_state.output.write_stderr(format!(
+                "command: {}: invalid option\ncommand: usage: command [-vV] command [arg ...]\n",
+                option
+            ));
+            ExitStatus::USAGE
+        }
+        Some(_) => executor::execute_command(&shifted(cmd), shell_state),
+        None => ExitStatus::SUCCESS,
+    }
+}
+
+// builtin NAME [ARG...]
+// runs the builtin NAME, never a file with that name
+pub fn builtin(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let name = match cmd.args.first() {
+        Some(name) => name,
+        None => return ExitStatus::SUCCESS,
+    };
+
+    match BuiltinCommands::from_str(name) {
+        Ok(builtin) => builtin::run(builtin, &shifted(cmd), shell_state),
+        Err(()) => {
+            shell_state
+                .output
+                .write_stderr(format!("builtin: {}: not a shell builtin\n", name));
+            ExitStatus::FAILURE
+        }
+    }
+}
+
+// exec [COMMAND [ARG...]]
+// The command replaces the shell: it runs like `command` does, then the shell exits with
+// its status. Without a command nothing happens, like bash an interactive shell doesn't
+// exit when the command isn't found.
+pub fn exec(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    if cmd.args.is_empty() {
+        return ExitStatus::SUCCESS;
+    }
+
+    let status = executor::execute_command(&shifted(cmd), shell_state);
+    if status == ExitStatus::NOT_FOUND && shell_state.interactive {
+        return status;
+    }
+
+    // a command that waits for input runs again as `exec` once the line resumes
+    if shell_state.suspension.is_none() {
+        shell_state.exit.get_or_insert(status);
+    }
+    status
+}
+
+// the command given as the arguments of another one
+fn shifted(cmd: &Command) -> Command {
+    Command {
+        keyword: cmd.args[0].clone(),
+        args: cmd.args[1..].to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_skips_aliases() {
+        let mut state = ShellState::default();
+        state.define_default_aliases();
+
+        assert_eq!(
+            command(&Command::new("command", &["echo", "a"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            command(&Command::new("command", &["ll"]), &mut state),
+            ExitStatus::NOT_FOUND
+        );
+        assert_eq!(state.output.stdout(), b"a\n");
+        assert_eq!(state.output.stderr(), b"shell: command not found: ll\n");
+    }
+
+    #[test]
+    fn test_command_v() {
+        let mut state = ShellState::default();
+        state.define_default_aliases();
+
+        let status = command(
+            &Command::new("command", &["-v", "ll", "cd", "vim"]),
+            &mut state,
+        );
+
+        assert_eq!(status, ExitStatus::FAILURE);
+        assert_eq!(state.output.stdout(), b"alias ll='ls -la'\ncd\n");
+        assert_eq!(state.output.stderr(), b"");
+
+        command(&Command::new("command", &["-V", "cd"]), &mut state);
+        assert_eq!(
+            state.output.stdout(),
+            b"alias ll='ls -la'\ncd\ncd is a shell builtin\n"
+        );
+    }
+
+    #[test]
+    fn test_builtin() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            builtin(&Command::new("builtin", &["echo", "a"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            builtin(&Command::new("builtin", &["vim"]), &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(state.output.stdout(), b"a\n");
+        assert_eq!(state.output.stderr(), b"builtin: vim: not a shell builtin\n");
+    }
+
+    #[test]
+    fn test_exec() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            exec(&Command::new("exec", &[]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(state.exit, None);
+
+        assert_eq!(
+            exec(&Command::new("exec", &["false"]), &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(state.exit, Some(ExitStatus::FAILURE));
+    }
+
+    #[test]
+    fn test_exec_not_found() {
+        let mut state = ShellState {
+            interactive: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            exec(&Command::new("exec", &["vim"]), &mut state),
+            ExitStatus::NOT_FOUND
+        );
+        assert_eq!(state.exit, None);
+
+        state.interactive = false;
+        exec(&Command::new("exec", &["vim"]), &mut state);
+        assert_eq!(state.exit, Some(ExitStatus::NOT_FOUND));
+    }
+}
@@ -0,0 +1,501 @@
+use std::io;
+use std::path::Path;
+
+use crate::builtin::parse_flags;
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::filesystem::{self, FileType};
+use crate::shell_state::ShellState;
+
+// cp [-rRfn] SOURCE... DESTINATION
+// copies files, with -r directories and everything in them. With more than one source,
+// or when the destination is a directory, they are copied in to it. -n doesn't overwrite
+// files that exist.
+// >> cp -r notes backup
+pub fn cp(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (flags, operands) = match parse_flags(&cmd.args, "rRfn") {
+        Ok(parsed) => parsed,
+        Err(message) => return fail("cp", &message, shell_state),
+    };
+    let targets = match targets(&operands, shell_state) {
+        Ok(targets) => targets,
+        Err(message) => return fail("cp", &message, shell_state),
+    };
+
+    let recursive = flags.contains('r') || flags.contains('R');
+    let no_clobber = flags.rfind('n') > flags.rfind('f');
+    let mut status = ExitStatus::SUCCESS;
+
+    for (source, target) in targets {
+        match can_copy(&source, &target, recursive, no_clobber, shell_state) {
+            Ok(true) => {
+                let (from, to) = (
+                    shell_state.resolve_path(&source),
+                    shell_state.resolve_path(&target),
+                );
+                if !copy(&from, &to, &source, &target, no_clobber, shell_state) {
+                    status = ExitStatus::FAILURE;
+                }
+            }
+            Ok(false) => (),
+            Err(message) => {
+                shell_state
+                    .output
+                    .write_stderr(format!("cp: {}\n", message));
+                status = ExitStatus::FAILURE;
+            }
+        }
+    }
+
+    status
+}
+
+// mv [-fn] SOURCE... DESTINATION
+// moves files and directories, with more than one source, or when the destination is a
+// directory, in to it. A file replaces a file and a directory an empty directory, -n doesn't
+// replace anything.
+// >> mv draft.txt posts/
+pub fn mv(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (flags, operands) = match parse_flags(&cmd.args, "fn") {
+        Ok(parsed) => parsed,
+        Err(message) => return fail("mv", &message, shell_state),
+    };
+    let targets = match targets(&operands, shell_state) {
+        Ok(targets) => targets,
+        Err(message) => return fail("mv", &message, shell_state),
+    };
+
+    let no_clobber = flags.rfind('n') > flags.rfind('f');
+    let mut status = ExitStatus::SUCCESS;
+
+    for (source, target) in targets {
+        if let Err(message) = move_to(&source, &target, no_clobber, shell_state) {
+            shell_state
+                .output
+                .write_stderr(format!("mv: {}\n", message));
+            status = ExitStatus::FAILURE;
+        }
+    }
+
+    status
+}
+
+// The sources with the paths they go to. With more than one source, or when the
+// destination is a directory, they go in to it with their own name.
+// >> targets(["a", "b/c", "dir"])
+// Ok(vec![("a", "dir/a"), ("b/c", "dir/c")])
+fn targets(operands: &[String], shell_state: &ShellState) -> Result<Vec<(String, String)>, String> {
+    let (destination, sources) = match operands.split_last() {
+        None => return Err(String::from("missing file operand")),
+        Some((destination, [])) => {
+            return Err(format!(
+                "missing destination file operand after '{}'",
+                destination
+            ))
+        }
+        Some(split) => split,
+    };
+
+    let path = shell_state.resolve_path(destination);
+    let is_directory = shell_state.fs.is_dir(&path);
+    if !is_directory && sources.len() > 1 {
+        return Err(match shell_state.fs.exists(&path) {
+            true => format!("target '{}' is not a directory", destination),
+            false => format!("target '{}': No such file or directory", destination),
+        });
+    }
+
+    let targets = sources.iter().map(|source| {
+        let target = match is_directory {
+            true => {
+                let name = source
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or("");
+                format!("{}/{}", destination.trim_end_matches('/'), name)
+            }
+            false => destination.clone(),
+        };
+
+        (source.clone(), target)
+    });
+
+    Ok(targets.collect())
+}
+
+// Whether the source can be copied to the target, false when -n skips it
+fn can_copy(
+    source: &str,
+    target: &str,
+    recursive: bool,
+    no_clobber: bool,
+    shell_state: &ShellState,
+) -> Result<bool, String> {
+    let from = shell_state.resolve_path(source);
+    let to = shell_state.resolve_path(target);
+    let file_type = match shell_state.fs.metadata(&from) {
+        Ok(metadata) => metadata.file_type,
+        Err(error) => return Err(stat_error(source, &error)),
+    };
+    let is_directory = file_type == FileType::Directory;
+
+    if is_directory && !recursive {
+        return Err(format!("-r not specified; omitting directory '{}'", source));
+    }
+    if from == to {
+        return Err(format!("'{}' and '{}' are the same file", source, target));
+    }
+    if is_directory && to.starts_with(&from) {
+        return Err(format!(
+            "cannot copy a directory, '{}', into itself, '{}'",
+            source, target
+        ));
+    }
+
+    match shell_state.fs.metadata(&to) {
+        Ok(_) if no_clobber => Ok(false),
+        Ok(metadata) => match overwrite_error(&file_type, &metadata.file_type, source, target) {
+            Some(message) => Err(message),
+            None => Ok(true),
+        },
+        Err(_) => Ok(true),
+    }
+}
+
+fn move_to(
+    source: &str,
+    target: &str,
+    no_clobber: bool,
+    shell_state: &ShellState,
+) -> Result<(), String> {
+    let from = shell_state.resolve_path(source);
+    let to = shell_state.resolve_path(target);
+    // a link moves itself, not what it points to
+    let file_type = match shell_state.fs.symlink_metadata(&from) {
+        Ok(metadata) => metadata.file_type,
+        Err(error) => return Err(stat_error(source, &error)),
+    };
+
+    if from == to {
+        return Err(format!("'{}' and '{}' are the same file", source, target));
+    }
+    if to.starts_with(&from) {
+        return Err(format!(
+            "cannot move '{}' to a subdirectory of itself, '{}'",
+            source, target
+        ));
+    }
+
+    match shell_state.fs.symlink_metadata(&to) {
+        Ok(_) if no_clobber => return Ok(()),
+        Ok(metadata) => {
+            if let Some(message) = overwrite_error(&file_type, &metadata.file_type, source, target)
+            {
+                return Err(message);
+            }
+        }
+        Err(_) => (),
+    }
+
+    shell_state.fs.rename(&from, &to).map_err(|error| {
+        format!(
+            "cannot move '{}' to '{}': {}",
+            source,
+            target,
+            filesystem::describe_error(&error)
+        )
+    })
+}
+
+fn stat_error(source: &str, error: &io::Error) -> String {
+    format!(
+        "cannot stat '{}': {}",
+        source,
+        filesystem::describe_error(error)
+    )
+}
+
+// a directory and a file or link can't replace each other
+fn overwrite_error(from: &FileType, to: &FileType, source: &str, target: &str) -> Option<String> {
+    match (*from == FileType::Directory, *to == FileType::Directory) {
+        (true, false) => Some(format!(
+            "cannot overwrite non-directory '{}' with directory '{}'",
+            target, source
+        )),
+        (false, true) => Some(format!(
+            "cannot overwrite directory '{}' with non-directory",
+            target
+        )),
+        _ => None,
+    }
+}
+
+// Copies a file, or a directory with everything in it in to the directory at `to` which is
+// created when it doesn't exist. The links in a directory are copied as links, a link that
+// is given is followed. Returns whether everything is copied.
+fn copy(
+    from: &Path,
+    to: &Path,
+    source: &str,
+    target: &str,
+    no_clobber: bool,
+    shell_state: &mut ShellState,
+) -> bool {
+    let fs = shell_state.fs.clone();
+
+    if fs.is_dir(from) {
+        if !fs.is_dir(to) {
+            if let Err(error) = fs.create_dir(to) {
+                write_error("cannot create directory", target, &error, shell_state);
+                return false;
+            }
+        }
+
+        let mut copied = true;
+        for name in fs.read_dir(from).unwrap_or_default() {
+            let source = format!("{}/{}", source.trim_end_matches('/'), name);
+            let target = format!("{}/{}", target.trim_end_matches('/'), name);
+            let (from, to) = (from.join(&name), to.join(&name));
+
+            copied &= match fs.read_link(&from) {
+                Ok(link) => copy_link(&link, &to, &target, no_clobber, shell_state),
+                Err(_) => copy(&from, &to, &source, &target, no_clobber, shell_state),
+            };
+        }
+        return copied;
+    }
+
+    if no_clobber && fs.exists(to) {
+        return true;
+    }
+
+    let contents = match fs.read(from) {
+        Ok(contents) => contents,
+        Err(error) => {
+            write_error("cannot open", source, &error, shell_state);
+            return false;
+        }
+    };
+
+    match fs.write(to, &contents) {
+        Ok(()) => true,
+        Err(error) => {
+            write_error("cannot create regular file", target, &error, shell_state);
+            false
+        }
+    }
+}
+
+fn copy_link(
+    link: &Path,
+    to: &Path,
+    target: &str,
+    no_clobber: bool,
+    shell_state: &mut ShellState,
+) -> bool {
+    let fs = shell_state.fs.clone();
+    if fs.symlink_metadata(to).is_ok() {
+        if no_clobber {
+            return true;
+        }
+        // like a file that is copied, the link replaces what is there
+        if let Err(error) = fs.remove_file(to) {
+            write_error("cannot remove", target, &error, shell_state);
+            return false;
+        }
+    }
+
+    match fs.symlink(link, to) {
+        Ok(()) => true,
+        Err(error) => {
+            write_error("cannot create symbolic link", target, &error, shell_state);
+            false
+        }
+    }
+}
+
+fn write_error(message: &str, path: &str, error: &io::Error, shell_state: &mut ShellState) {
+    shell_state.output.write_stderr(format!(
+        "cp: {} '{}': {}\n",
+        message,
+        path,
+        filesystem::describe_error(error)
+    ));
+}
+
+fn fail(name: &str, message: &str, shell_state: &mut ShellState) -> ExitStatus {
+    shell_state
+        .output
+        .write_stderr(format!("{}: {}\n", name, message));
+    ExitStatus::FAILURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> ShellState {
+        let state = ShellState::default();
+        state.fs.create_dir(Path::new("/dir")).unwrap();
+        state.fs.create_dir(Path::new("/dir/sub")).unwrap();
+        state.fs.write(Path::new("/dir/sub/file"), b"x").unwrap();
+        state.fs.create_dir(Path::new("/empty")).unwrap();
+        state.fs.write(Path::new("/a"), b"a").unwrap();
+        state.fs.write(Path::new("/b"), b"b").unwrap();
+        state
+    }
+
+    fn stderr(shell_state: &ShellState) -> String {
+        String::from_utf8_lossy(&shell_state.output.stderr()).to_string()
+    }
+
+    #[test]
+    fn test_cp() {
+        let mut state = state();
+
+        assert_eq!(
+            cp(&Command::new("cp", &["a", "c"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            cp(&Command::new("cp", &["a", "b", "empty"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            cp(&Command::new("cp", &["-r", "dir", "copy"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            cp(&Command::new("cp", &["-r", "dir/", "empty"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            cp(&Command::new("cp", &["-n", "b", "a"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+
+        assert_eq!(state.fs.read(Path::new("/c")).unwrap(), b"a");
+        assert_eq!(state.fs.read(Path::new("/a")).unwrap(), b"a");
+        assert_eq!(
+            state.fs.read_dir(Path::new("/empty")).unwrap(),
+            vec!["a", "b", "dir"]
+        );
+        assert_eq!(state.fs.read(Path::new("/copy/sub/file")).unwrap(), b"x");
+        assert_eq!(
+            state.fs.read(Path::new("/empty/dir/sub/file")).unwrap(),
+            b"x"
+        );
+        assert_eq!(stderr(&state), "");
+    }
+
+    #[test]
+    fn test_cp_errors() {
+        let mut state = state();
+
+        let commands: &[&[&str]] = &[
+            &[],
+            &["a"],
+            &["a", "b", "c"],
+            &["a", "b", "a"],
+            &["missing", "dir", "a", "empty"],
+            &["a", "a"],
+            &["-r", "dir", "dir/sub"],
+            &["-r", "dir", "a"],
+            &["-x", "a", "b"],
+        ];
+        for args in commands {
+            assert_eq!(cp(&Command::new("cp", args), &mut state), ExitStatus::FAILURE);
+        }
+        assert_eq!(
+            cp(&Command::new("cp", &["a", "dir/sub"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+
+        assert_eq!(
+            stderr(&state),
+            "cp: missing file operand\n\
+             cp: missing destination file operand after 'a'\n\
+             cp: target 'c': No such file or directory\n\
+             cp: target 'a' is not a directory\n\
+             cp: cannot stat 'missing': No such file or directory\n\
+             cp: -r not specified; omitting directory 'dir'\n\
+             cp: 'a' and 'a' are the same file\n\
+             cp: cannot copy a directory, 'dir', into itself, 'dir/sub/dir'\n\
+             cp: cannot overwrite non-directory 'a' with directory 'dir'\n\
+             cp: invalid option -- 'x'\n"
+        );
+        assert_eq!(state.fs.read(Path::new("/empty/a")).unwrap(), b"a");
+        assert_eq!(state.fs.read(Path::new("/dir/sub/a")).unwrap(), b"a");
+    }
+
+    #[test]
+    fn test_mv() {
+        let mut state = state();
+
+        assert_eq!(
+            mv(&Command::new("mv", &["a", "c"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            mv(&Command::new("mv", &["c", "b"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            mv(&Command::new("mv", &["b", "dir/sub/"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            mv(&Command::new("mv", &["dir", "empty"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+
+        assert_eq!(state.fs.read_dir(Path::new("/")).unwrap(), vec!["empty"]);
+        assert_eq!(
+            state.fs.read_dir(Path::new("/empty/dir/sub")).unwrap(),
+            vec!["b", "file"]
+        );
+        assert_eq!(state.fs.read(Path::new("/empty/dir/sub/b")).unwrap(), b"a");
+    }
+
+    #[test]
+    fn test_mv_overwrite_rules() {
+        let mut state = state();
+        state.fs.create_dir(Path::new("/empty/dir")).unwrap();
+        state.fs.create_dir(Path::new("/empty/sub")).unwrap();
+        state.fs.write(Path::new("/empty/sub/x"), b"").unwrap();
+        state.fs.create_dir(Path::new("/empty/a")).unwrap();
+
+        let commands: &[&[&str]] = &[
+            &["missing", "a"],
+            &["a", "a"],
+            &["dir", "dir/sub/inner"],
+            &["dir", "b"],
+            &["a", "empty"],
+            &["dir/sub", "empty"],
+        ];
+        for args in commands {
+            assert_eq!(mv(&Command::new("mv", args), &mut state), ExitStatus::FAILURE);
+        }
+        assert_eq!(
+            mv(&Command::new("mv", &["-n", "a", "b"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            mv(&Command::new("mv", &["dir", "empty"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+
+        assert_eq!(
+            stderr(&state),
+            "mv: cannot stat 'missing': No such file or directory\n\
+             mv: 'a' and 'a' are the same file\n\
+             mv: cannot move 'dir' to a subdirectory of itself, 'dir/sub/inner'\n\
+             mv: cannot overwrite non-directory 'b' with directory 'dir'\n\
+             mv: cannot overwrite directory 'empty/a' with non-directory\n\
+             mv: cannot move 'dir/sub' to 'empty/sub': Directory not empty\n"
+        );
+        assert_eq!(state.fs.read(Path::new("/b")).unwrap(), b"b");
+        assert!(state.fs.exists(Path::new("/empty/dir/sub/file")));
+    }
+}
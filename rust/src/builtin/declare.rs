@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use crate::command::Command;
+use crate::executor::{self, assignment};
+use crate::exit_status::ExitStatus;
+use crate::parser::word;
+use crate::shell_state::ShellState;
+use crate::variable::Value;
+
+const USAGE: &str = "declare: usage: declare [-aAp] [name[=value] ...]\n";
+
+// declare [-aAp] [NAME[=VALUE]...] or typeset
+// -a makes the variables indexed arrays and -A associative arrays, the values are
+// assigned after that. -p prints the variables the way they can be declared again,
+// without names all of them are printed.
+// >> declare -A colors=([sky]=blue)
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let mut kind = None;
+    let mut print = false;
+    let mut names = cmd.args.as_slice();
+
+    while let Some(arg) = names.first() {
+        let flags = match arg.strip_prefix('-') {
+            Some("-") => {
+                names = &names[1..];
+                break;
+            }
+            Some(flags) if !flags.is_empty() => flags,
+            _ => break,
+        };
+
+        for flag in flags.chars() {
+            match flag {
+                'a' | 'A' => kind = Some(flag),
+                'p' => print = true,
+                flag => {
+                    shell_state
+                        .output
+                        .write_stderr(format!("declare: -{}: invalid option\n{}", flag, USAGE));
+                    return ExitStatus::USAGE;
+                }
+            }
+        }
+        names = &names[1..];
+    }
+
+    if names.is_empty() {
+        let declarations: String = shell_state
+            .variables
+            .iter()
+            .filter(|(_, value)| match kind {
+                Some('a') => matches!(value, Value::Indexed(_)),
+                Some('A') => value.is_associative(),
+                _ => true,
+            })
+            .map(|(name, value)| format_declaration(name, value) + "\n")
+            .collect();
+        shell_state.output.write_stdout(declarations);
+
+        return ExitStatus::SUCCESS;
+    }
+
+    let mut status = ExitStatus::SUCCESS;
+    for arg in names {
+        let result = match print {
+            true => print_variable(arg, shell_state),
+            false => declare(arg, kind, shell_state),
+        };
+
+        if let Err(message) = result {
+            shell_state
+                .output
+                .write_stderr(format!("declare: {}\n", message));
+            status = ExitStatus::FAILURE;
+        }
+    }
+
+    status
+}
+
+fn print_variable(name: &str, shell_state: &mut ShellState) -> Result<(), String> {
+    match shell_state.variables.get(name) {
+        Some(value) => {
+            let declaration = format_declaration(name, value) + "\n";
+            shell_state.output.write_stdout(declaration);
+            Ok(())
+        }
+        None => Err(format!("{}: not found", name)),
+    }
+}
+
+// `NAME` or `NAME=VALUE`, the kind of array the variable becomes is set before the value
+fn declare(arg: &str, kind: Option<char>, shell_state: &mut ShellState) -> Result<(), String> {
+    let assignment = word::split_assignment(arg);
+    let name = assignment
+        .as_ref()
+        .map_or(arg, |assignment| assignment.name);
+    if !executor::is_name(name) {
+        return Err(format!("`{}': not a valid identifier", arg));
+    }
+
+    let converted = match (kind, shell_state.variables.get(name)) {
+        (Some('A'), Some(Value::Indexed(_))) => {
+            return Err(format!(
+                "{}: cannot convert indexed to associative array",
+                name
+            ))
+        }
+        (Some('a'), Some(Value::Associative(_))) => {
+            return Err(format!(
+                "{}: cannot convert associative to indexed array",
+                name
+            ))
+        }
+        (Some('A'), Some(Value::String(value))) => Some(Value::Associative(
+            std::iter::once((String::from("0"), value.clone())).collect(),
+        )),
+        (Some('A'), None) => Some(Value::Associative(BTreeMap::new())),
+        (Some('a'), Some(Value::String(value))) => Some(Value::indexed(vec![value.clone()])),
+        (Some('a'), None) => Some(Value::Indexed(BTreeMap::new())),
+        _ => None,
+    };
+
+    if let Some(value) = converted {
+        shell_state.variables.insert(String::from(name), value);
+    }
+
+    match assignment {
+        Some(assignment) => assignment::assign(&assignment, shell_state)
+            .map(|_| ())
+            .map_err(|error| error.0),
+        None => Ok(()),
+    }
+}
+
+// >> format_declaration("arr", Value::indexed(vec!["a"]))
+// "declare -a arr=([0]=\"a\")"
+fn format_declaration(name: &str, value: &Value) -> String {
+    let flag = match value {
+        Value::String(_) => "-",
+        Value::Indexed(_) => "a",
+        Value::Associative(_) => "A",
+    };
+
+    format!("declare -{} {}={}", flag, name, value.format())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declare(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("declare", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_declare_arrays() {
+        let mut state = ShellState::default();
+        state.set_variable("S", String::from("s"));
+
+        let status = declare(&["-a", "arr=(a 'b c')", "S"], &mut state);
+        assert_eq!(status, ExitStatus::SUCCESS);
+        assert_eq!(
+            declare(&["-A", "map", "map[k]=v"], &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(declare(&["A=\"$S\""], &mut state), ExitStatus::SUCCESS);
+
+        declare(&["-p", "arr", "S", "map", "A"], &mut state);
+        assert_eq!(
+            state.output.stdout(),
+            b"declare -a arr=([0]=\"a\" [1]=\"b c\")\ndeclare -a S=([0]=\"s\")\n\
+              declare -A map=([k]=\"v\")\ndeclare -- A=\"s\"\n"
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        let mut state = ShellState::default();
+        state.set_variable("S", String::from("s"));
+        state
+            .variables
+            .insert(String::from("E"), Value::Indexed(BTreeMap::new()));
+
+        declare(&["-a"], &mut state);
+        declare(&["-p"], &mut state);
+
+        assert_eq!(
+            state.output.stdout(),
+            b"declare -a E=()\ndeclare -a E=()\ndeclare -- OPTIND=\"1\"\ndeclare -- S=\"s\"\n"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = ShellState::default();
+        declare(&["-A", "map"], &mut state);
+        declare(&["-a", "arr"], &mut state);
+
+        assert_eq!(
+            declare(&["-a", "map", "1x=1"], &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(declare(&["-A", "arr"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(declare(&["-p", "missing"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(declare(&["-x"], &mut state), ExitStatus::USAGE);
+        assert!(state.variables["map"].is_associative());
+
+        assert_eq!(
+            String::from_utf8_lossy(&state.output.stderr()),
+            "declare: map: cannot convert associative to indexed array\n\
+             declare: `1x=1': not a valid identifier\n\
+             declare: arr: cannot convert indexed to associative array\n\
+             declare: missing: not found\n\
+             declare: -x: invalid option\n\
+             declare: usage: declare [-aAp] [name[=value] ...]\n"
+        );
+    }
+}
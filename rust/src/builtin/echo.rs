@@ -1,8 +1,44 @@
+use crate::builtin::escape::{self, Escapes};
 use crate::command::Command;
+use crate::exit_status::ExitStatus;
 use crate::shell_state::ShellState;
 
-pub fn run(cmd: &Command, shell_state: &mut ShellState) {
-    shell_state.output.set_stdout(cmd.args.join(" "));
+// echo [-neE] [ARG...]
+// writes the arguments separated by spaces and a line break, -n leaves out the line break.
+// -e expands backslash escapes and -E doesn't, without either it depends on `shopt xpg_echo`.
+// `\c` ends the output there, without the line break. An argument that isn't only
+// these flags is printed, eg: `echo -x` or `echo --`.
+// >> echo -e 'a\tb\c' c
+// "a\tb"
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let mut newline = true;
+    let mut escapes = shell_state.shopt.xpg_echo;
+
+    let is_option = |arg: &&String| {
+        arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| "neE".contains(c))
+    };
+    let options = cmd.args.iter().take_while(is_option).count();
+
+    for flag in cmd.args[..options].iter().flat_map(|arg| arg[1..].chars()) {
+        match flag {
+            'n' => newline = false,
+            'e' => escapes = true,
+            _ => escapes = false,
+        }
+    }
+
+    let text = cmd.args[options..].join(" ");
+    let (mut bytes, stopped) = match escapes {
+        true => escape::expand(&text, Escapes::Echo),
+        false => (text.into_bytes(), false),
+    };
+
+    if newline && !stopped {
+        bytes.push(b'\n');
+    }
+
+    shell_state.output.write_stdout(bytes);
+    ExitStatus::SUCCESS
 }
 
 #[cfg(test)]
@@ -19,7 +55,7 @@ mod tests {
 
         run(&cmd, &mut state);
 
-        assert_eq!(state.output.stdout, Some(String::from("")));
+        assert_eq!(state.output.stdout(), b"\n");
     }
 
     #[test]
@@ -32,6 +68,33 @@ mod tests {
 
         run(&cmd, &mut state);
 
-        assert_eq!(state.output.stdout, Some(String::from("--test ee!! \nabc")));
+        assert_eq!(state.output.stdout(), b"--test ee!! \nabc\n");
+    }
+
+    fn echo(args: &[&str], shell_state: &mut ShellState) -> Vec<u8> {
+        let cmd = Command::new("echo", args);
+
+        run(&cmd, shell_state);
+        shell_state.output.drain().flat_map(|chunk| chunk.bytes).collect()
+    }
+
+    #[test]
+    fn test_options() {
+        let mut state = ShellState::default();
+
+        assert_eq!(echo(&["-n", "a"], &mut state), b"a");
+        assert_eq!(echo(&["-e", "a\\tb\\0101"], &mut state), b"a\tbA\n");
+        assert_eq!(echo(&["-neE", "a\\tb"], &mut state), b"a\\tb");
+        assert_eq!(echo(&["-x", "--", "-n"], &mut state), b"-x -- -n\n");
+        assert_eq!(echo(&["-e", "a\\cb", "c"], &mut state), b"a");
+    }
+
+    #[test]
+    fn test_xpg_echo() {
+        let mut state = ShellState::default();
+        state.shopt.xpg_echo = true;
+
+        assert_eq!(echo(&["a\\tb"], &mut state), b"a\tb\n");
+        assert_eq!(echo(&["-E", "a\\tb"], &mut state), b"a\\tb\n");
     }
 }
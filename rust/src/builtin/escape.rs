@@ -0,0 +1,141 @@
+// Where backslash escapes are expanded, they differ in how octal numbers are written
+// and whether `\c` ends the output
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Escapes {
+    // the format of `printf`: `\NNN`
+    Format,
+    // an argument of `printf %b`: `\0NNN` or `\NNN`, `\c` ends the output
+    Argument,
+    // `echo -e`: only `\0NNN`, `\c` ends the output
+    Echo,
+}
+
+// Bytes of the text with its escapes expanded, and whether `\c` ended it there.
+// An unknown escape stays as it is, eg: `\q`.
+// >> expand("a\\tb\\x41\\101", Escapes::Format)
+// (b"a\tbAA", false)
+pub fn expand(text: &str, escapes: Escapes) -> (Vec<u8>, bool) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut bytes = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != '\\' || i + 1 == chars.len() {
+            push_char(&mut bytes, chars[i]);
+            i += 1;
+            continue;
+        }
+
+        let escape = chars[i + 1];
+        i += 2;
+
+        let byte = match escape {
+            'a' => 0x07,
+            'b' => 0x08,
+            'e' | 'E' => 0x1b,
+            'f' => 0x0c,
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            'v' => 0x0b,
+            '\\' => b'\\',
+            '\'' | '"' | '?' if escapes == Escapes::Format => escape as u8,
+            'c' if escapes != Escapes::Format => return (bytes, true),
+            '0' if escapes != Escapes::Format => {
+                let (value, length) = parse_number(&chars[i..], 8, 3);
+                i += length;
+                value as u8
+            }
+            '0'..='7' if escapes != Escapes::Echo => {
+                let (value, length) = parse_number(&chars[i - 1..], 8, 3);
+                i += length - 1;
+                value as u8
+            }
+            'x' | 'u' | 'U' => {
+                let max_length = match escape {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let (value, length) = parse_number(&chars[i..], 16, max_length);
+
+                match (escape, length, char::from_u32(value)) {
+                    (_, 0, _) | (_, _, None) => {
+                        bytes.push(b'\\');
+                        push_char(&mut bytes, escape);
+                        continue;
+                    }
+                    ('x', _, _) => {
+                        i += length;
+                        value as u8
+                    }
+                    (_, _, Some(char)) => {
+                        i += length;
+                        push_char(&mut bytes, char);
+                        continue;
+                    }
+                }
+            }
+            escape => {
+                bytes.push(b'\\');
+                push_char(&mut bytes, escape);
+                continue;
+            }
+        };
+
+        bytes.push(byte);
+    }
+
+    (bytes, false)
+}
+
+// the value of the digits at the start of the chars, and how many there are
+fn parse_number(chars: &[char], radix: u32, max_length: usize) -> (u32, usize) {
+    let digits: Vec<u32> = chars
+        .iter()
+        .take(max_length)
+        .map_while(|char| char.to_digit(radix))
+        .collect();
+    let value = digits.iter().fold(0, |value, digit| value * radix + digit);
+
+    (value, digits.len())
+}
+
+fn push_char(bytes: &mut Vec<u8>, char: char) {
+    bytes.extend(char.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_escapes() {
+        assert_eq!(
+            expand("a\\tb\\x41\\101\\0101\\u263a\\q\\c\\", Escapes::Format),
+            ("a\tbAA\x081☺\\q\\c\\".as_bytes().to_vec(), false)
+        );
+        assert_eq!(
+            expand("\\xZ\\\"", Escapes::Format),
+            (b"\\xZ\"".to_vec(), false)
+        );
+    }
+
+    #[test]
+    fn test_argument_escapes() {
+        assert_eq!(
+            expand("\\0101\\101\\e[0m\\\"", Escapes::Argument),
+            (b"AA\x1b[0m\\\"".to_vec(), false)
+        );
+        assert_eq!(expand("a\\cb", Escapes::Argument), (b"a".to_vec(), true));
+    }
+
+    #[test]
+    fn test_echo_escapes() {
+        assert_eq!(
+            expand("\\0101\\101\\0\\x4\\?", Escapes::Echo),
+            (b"A\\101\0\x04\\?".to_vec(), false)
+        );
+        assert_eq!(expand("a\\cb", Escapes::Echo), (b"a".to_vec(), true));
+    }
+}
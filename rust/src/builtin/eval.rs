@@ -0,0 +1,63 @@
+use crate::command::Command;
+use crate::executor::{self, resume};
+use crate::exit_status::ExitStatus;
+use crate::parser::token;
+use crate::shell_state::ShellState;
+
+// eval [ARG...]
+// the arguments are joined with spaces and run as a line of the shell, so the words
+// are parsed and expanded once more
+// >> A='echo hi'; eval "$A > out"
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let line = cmd.args.join(" ");
+    if token::tokenize_raw_line(&line).is_empty() {
+        return ExitStatus::SUCCESS;
+    }
+
+    match executor::run_within(&line, shell_state) {
+        // the line waits for input, eg: `eval read a`
+        Ok(()) if resume::defer_lines(shell_state, || None) => ExitStatus::SUCCESS,
+        Ok(()) => shell_state.output.status,
+        Err(exception) => {
+            shell_state
+                .output
+                .write_stderr(format!("eval: {}\n", exception));
+            ExitStatus::USAGE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("eval", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_eval() {
+        let mut state = ShellState::default();
+        state
+            .set_variable("B", String::from("$A"));
+
+        assert_eq!(
+            eval(&["A=1;", "echo", "$B", "&&", "false"], &mut state),
+            ExitStatus::FAILURE
+        );
+        eval(&["eval", "echo", "$B"], &mut state);
+        assert_eq!(state.output.stdout(), b"$A\n1\n");
+    }
+
+    #[test]
+    fn test_empty_and_invalid_lines() {
+        let mut state = ShellState::default();
+
+        assert_eq!(eval(&[], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(eval(&["  "], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(eval(&["echo", "&&"], &mut state), ExitStatus::USAGE);
+        assert!(state.output.stderr().starts_with(b"eval: "));
+    }
+}
@@ -0,0 +1,267 @@
+use crate::command::Command;
+use crate::executor;
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+// getopts OPTSTRING NAME [ARG...]
+// Parses one option of the arguments, or of the positional parameters without ARG,
+// each time it runs. The option goes in NAME, its argument in OPTARG and OPTIND is
+// the index of the next argument. A letter followed by ":" in OPTSTRING takes an argument.
+// It fails once the options end, at the first word that isn't one or after `--`.
+// >> getopts ab: opt -a -b file
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (optstring, name) = match cmd.args.as_slice() {
+        [optstring, name, ..] => (optstring.as_str(), name.as_str()),
+        _ => {
+            shell_state
+                .output
+                .write_stderr("getopts: usage: getopts optstring name [arg ...]\n");
+            return ExitStatus::USAGE;
+        }
+    };
+
+    if !executor::is_name(name) {
+        shell_state
+            .output
+            .write_stderr(format!("getopts: `{}': not a valid identifier\n", name));
+        return ExitStatus::FAILURE;
+    }
+
+    let args = match cmd.args.len() > 2 {
+        true => &cmd.args[2..],
+        false => &shell_state.positional_parameters[..],
+    };
+
+    let optind = shell_state
+        .parameter("OPTIND")
+        .and_then(|optind| optind.parse::<usize>().ok())
+        .filter(|optind| *optind > 0)
+        .unwrap_or(1);
+
+    // another `$OPTIND` than the last one starts at the first flag of its argument
+    let position = match shell_state.getopts_position {
+        (index, position) if index == optind => position,
+        _ => 1,
+    };
+
+    let (result, status) = match parse_option(optstring, args, optind, position) {
+        Some(parsed) => (parsed, ExitStatus::SUCCESS),
+        None => {
+            let optind = match args.get(optind - 1) {
+                Some(arg) if arg == "--" => optind + 1,
+                _ => optind,
+            };
+            (Parsed::new("?", None, optind, 1), ExitStatus::FAILURE)
+        }
+    };
+
+    if let Some(message) = result.error {
+        shell_state
+            .output
+            .write_stderr(format!("shell: {} -- {}\n", message, result.option));
+    }
+
+    shell_state.set_variable(name, result.value);
+    match result.optarg {
+        Some(optarg) => shell_state.set_variable("OPTARG", optarg),
+        None => {
+            shell_state.variables.remove("OPTARG");
+        }
+    };
+    shell_state.set_variable("OPTIND", result.optind.to_string());
+    shell_state.getopts_position = (result.optind, result.position);
+
+    status
+}
+
+// what one run of getopts sets
+#[derive(Debug, PartialEq)]
+struct Parsed {
+    value: String,
+    optarg: Option<String>,
+    optind: usize,
+    position: usize,
+    option: char,
+    // the message when the option is wrong and errors aren't silent
+    error: Option<&'static str>,
+}
+
+impl Parsed {
+    fn new(value: &str, optarg: Option<String>, optind: usize, position: usize) -> Parsed {
+        Parsed {
+            value: String::from(value),
+            optarg,
+            optind,
+            position,
+            option: '?',
+            error: None,
+        }
+    }
+}
+
+// The option at `position` in the argument at `optind`, there is none when the options ended.
+// A leading ":" in the optstring reports errors through the name and OPTARG.
+fn parse_option(
+    optstring: &str,
+    args: &[String],
+    optind: usize,
+    position: usize,
+) -> Option<Parsed> {
+    let (silent, optstring) = match optstring.strip_prefix(':') {
+        Some(optstring) => (true, optstring),
+        None => (false, optstring),
+    };
+
+    let arg: Vec<char> = match args.get(optind - 1) {
+        Some(arg) if arg.starts_with('-') && arg != "-" && arg != "--" => arg.chars().collect(),
+        _ => return None,
+    };
+
+    let option = arg[position.min(arg.len() - 1)];
+    let rest: String = arg[(position + 1).min(arg.len())..].iter().collect();
+
+    // the next run continues in the same argument while it has flags
+    let (next_optind, next_position) = match rest.is_empty() {
+        true => (optind + 1, 1),
+        false => (optind, position + 1),
+    };
+
+    let takes_argument = match optstring.find(option) {
+        Some(index) if option != ':' => optstring[index + option.len_utf8()..].starts_with(':'),
+        _ => {
+            let parsed = match silent {
+                true => Parsed::new("?", Some(option.to_string()), next_optind, next_position),
+                false => Parsed {
+                    option,
+                    error: Some("illegal option"),
+                    ..Parsed::new("?", None, next_optind, next_position)
+                },
+            };
+            return Some(parsed);
+        }
+    };
+
+    let value = option.to_string();
+    if !takes_argument {
+        return Some(Parsed::new(&value, None, next_optind, next_position));
+    }
+
+    // the argument is the rest of the word or the next word
+    let parsed = match (rest.is_empty(), args.get(optind)) {
+        (false, _) => Parsed::new(&value, Some(rest), optind + 1, 1),
+        (true, Some(argument)) => Parsed::new(&value, Some(argument.clone()), optind + 2, 1),
+        (true, None) if silent => Parsed::new(":", Some(value), optind + 1, 1),
+        (true, None) => Parsed {
+            option,
+            error: Some("option requires an argument"),
+            ..Parsed::new("?", None, optind + 1, 1)
+        },
+    };
+
+    Some(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn getopts(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("getopts", args);
+
+        run(&cmd, shell_state)
+    }
+
+    // runs getopts until it fails, with the name and OPTARG of each option
+    fn parse_all(optstring: &str, args: &[&str], shell_state: &mut ShellState) -> Vec<String> {
+        let getopts_args: Vec<&str> = [optstring, "opt"].iter().chain(args).copied().collect();
+
+        let mut options = Vec::new();
+        while getopts(&getopts_args, shell_state).is_success() {
+            let optarg = shell_state.parameter("OPTARG");
+            options.push(format!(
+                "{}{}",
+                shell_state.parameter("opt").unwrap(),
+                optarg
+                    .map(|optarg| format!("={}", optarg))
+                    .unwrap_or_default()
+            ));
+        }
+
+        options
+    }
+
+    #[test]
+    fn test_options() {
+        let mut state = ShellState::default();
+
+        let options = parse_all("ab:c", &["-a", "-b", "file", "-cbx", "-", "-a"], &mut state);
+
+        assert_eq!(options, vec!["a", "b=file", "c", "b=x"]);
+        assert_eq!(state.parameter("OPTIND"), Some(String::from("5")));
+        assert_eq!(state.parameter("opt"), Some(String::from("?")));
+        assert_eq!(state.parameter("OPTARG"), None);
+    }
+
+    #[test]
+    fn test_bundled_flags() {
+        let mut state = ShellState::default();
+
+        let options = parse_all("abc", &["-acb", "-c", "--", "-a"], &mut state);
+
+        assert_eq!(options, vec!["a", "c", "b", "c"]);
+        assert_eq!(state.parameter("OPTIND"), Some(String::from("4")));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = ShellState::default();
+
+        let options = parse_all("ab:", &["-x", "-a", "-b"], &mut state);
+
+        assert_eq!(options, vec!["?", "a", "?"]);
+        assert_eq!(state.parameter("OPTIND"), Some(String::from("4")));
+        assert_eq!(
+            state.output.stderr(),
+            b"shell: illegal option -- x\nshell: option requires an argument -- b\n"
+        );
+    }
+
+    #[test]
+    fn test_silent_errors() {
+        let mut state = ShellState::default();
+
+        let options = parse_all(":ab:", &["-xa", "-b"], &mut state);
+
+        assert_eq!(options, vec!["?=x", "a", ":=b"]);
+        assert_eq!(state.output.stderr(), b"");
+    }
+
+    #[test]
+    fn test_positional_parameters() {
+        let mut state = ShellState {
+            positional_parameters: vec![String::from("-v"), String::from("arg")],
+            ..ShellState::default()
+        };
+
+        assert_eq!(getopts(&["v", "opt"], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(getopts(&["v", "opt"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(state.parameter("OPTIND"), Some(String::from("2")));
+
+        // setting OPTIND starts over
+        state
+            .set_variable("OPTIND", String::from("1"));
+        assert_eq!(getopts(&["v", "opt"], &mut state), ExitStatus::SUCCESS);
+    }
+
+    #[test]
+    fn test_usage() {
+        let mut state = ShellState::default();
+
+        assert_eq!(getopts(&["ab"], &mut state), ExitStatus::USAGE);
+        assert_eq!(getopts(&["ab", "1x"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(
+            state.output.stderr(),
+            b"getopts: usage: getopts optstring name [arg ...]\ngetopts: `1x': not a valid identifier\n"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use crate::builtin::{read_input, write_file_error};
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+#[derive(Debug, PartialEq)]
+enum Count {
+    Lines(usize),
+    Bytes(usize),
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    count: Count,
+    paths: Vec<String>,
+}
+
+// head [-n LINES | -c BYTES] [FILE]...
+// -c counts bytes, so it can cut binary files
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let options = match parse_options(&cmd.args) {
+        Ok(options) => options,
+        Err(message) => {
+            shell_state
+                .output
+                .write_stderr(format!("head: {}\n", message));
+            return ExitStatus::USAGE;
+        }
+    };
+
+    let mut status = ExitStatus::SUCCESS;
+    for (i, path) in options.paths.iter().enumerate() {
+        let bytes = match read_input(path, shell_state) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                write_file_error("head", path, &error, shell_state);
+                status = ExitStatus::FAILURE;
+                continue;
+            }
+        };
+
+        if options.paths.len() > 1 {
+            let separator = if i == 0 { "" } else { "\n" };
+            shell_state.output.write_stdout(format!(
+                "{}==> {} <==\n",
+                separator,
+                display_name(path)
+            ));
+        }
+
+        shell_state
+            .output
+            .write_stdout(take(&bytes, &options.count));
+    }
+
+    status
+}
+
+fn take<'a>(bytes: &'a [u8], count: &Count) -> &'a [u8] {
+    match count {
+        Count::Bytes(n) => &bytes[..bytes.len().min(*n)],
+        Count::Lines(n) => {
+            let mut lines = 0;
+
+            for (i, byte) in bytes.iter().enumerate() {
+                if lines == *n {
+                    return &bytes[..i];
+                }
+
+                if *byte == b'\n' {
+                    lines += 1;
+                }
+            }
+
+            bytes
+        }
+    }
+}
+
+fn display_name(path: &str) -> &str {
+    match path {
+        "-" => "standard input",
+        path => path,
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut count = Count::Lines(10);
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, value) = match arg.as_str() {
+            "-n" | "-c" => (&arg[..2], args.next().map(|value| value.as_str())),
+            arg if arg.starts_with("-n") || arg.starts_with("-c") => (&arg[..2], Some(&arg[2..])),
+            arg if arg.len() > 1
+                && arg.starts_with('-')
+                && arg[1..].bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                ("-n", Some(&arg[1..]))
+            }
+            arg if arg.len() > 1 && arg.starts_with('-') => {
+                return Err(format!("invalid option -- '{}'", &arg[1..]))
+            }
+            _ => {
+                paths.push(arg.clone());
+                continue;
+            }
+        };
+
+        let value = value.ok_or(format!("option requires an argument -- '{}'", &flag[1..]))?;
+        let number = value.parse().map_err(|_| match flag {
+            "-c" => format!("invalid number of bytes: '{}'", value),
+            _ => format!("invalid number of lines: '{}'", value),
+        })?;
+
+        count = match flag {
+            "-c" => Count::Bytes(number),
+            _ => Count::Lines(number),
+        };
+    }
+
+    if paths.is_empty() {
+        paths.push(String::from("-"));
+    }
+
+    Ok(Options { count, paths })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn head(args: &[&str], shell_state: &mut ShellState) {
+        let cmd = Command::new("head", args);
+
+        run(&cmd, shell_state);
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let expected = vec![
+            (vec![], Count::Lines(10)),
+            (vec!["-n", "3"], Count::Lines(3)),
+            (vec!["-n3"], Count::Lines(3)),
+            (vec!["-3"], Count::Lines(3)),
+            (vec!["-c", "5"], Count::Bytes(5)),
+            (vec!["-c5"], Count::Bytes(5)),
+        ];
+
+        for (args, count) in expected {
+            let args: Vec<String> = args.into_iter().map(String::from).collect();
+            assert_eq!(
+                parse_options(&args),
+                Ok(Options {
+                    count,
+                    paths: vec![String::from("-")]
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_options_errors() {
+        let args = vec![String::from("-c"), String::from("x")];
+        assert_eq!(
+            parse_options(&args),
+            Err(String::from("invalid number of bytes: 'x'"))
+        );
+
+        let args = vec![String::from("-n")];
+        assert_eq!(
+            parse_options(&args),
+            Err(String::from("option requires an argument -- 'n'"))
+        );
+    }
+
+    #[test]
+    fn test_head_lines() {
+        let mut state = ShellState::default();
+        state.output.stdin = Some(b"1\n2\n3\n".to_vec());
+
+        head(&["-n", "2"], &mut state);
+
+        assert_eq!(state.output.stdout(), b"1\n2\n");
+    }
+
+    #[test]
+    fn test_head_bytes_is_binary_safe() {
+        let mut state = ShellState::default();
+        state
+            .fs
+            .write(Path::new("/bin"), &[0xff, 0xfe, 0x00, 0x01])
+            .unwrap();
+
+        head(&["-c", "3", "bin"], &mut state);
+
+        assert_eq!(state.output.stdout(), vec![0xff, 0xfe, 0x00]);
+    }
+
+    #[test]
+    fn test_head_multiple_files() {
+        let mut state = ShellState::default();
+        state.fs.write(Path::new("/a"), b"a\n").unwrap();
+        state.fs.write(Path::new("/b"), b"b\n").unwrap();
+
+        head(&["a", "b"], &mut state);
+
+        assert_eq!(state.output.stdout(), b"==> a <==\na\n\n==> b <==\nb\n");
+    }
+}
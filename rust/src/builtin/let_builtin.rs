@@ -0,0 +1,62 @@
+use crate::command::Command;
+use crate::executor::arithmetic::{self, ArithmeticError};
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+// let EXPRESSION...
+// evaluates every argument as an arithmetic expression,
+// succeeds when the last one is not zero
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    if cmd.args.is_empty() {
+        shell_state
+            .output
+            .write_stderr("let: expression expected\n");
+        return ExitStatus::FAILURE;
+    }
+
+    let mut last = 0;
+    for expression in &cmd.args {
+        match arithmetic::evaluate(expression, shell_state) {
+            Ok(value) => last = value,
+            Err(ArithmeticError(message)) => {
+                shell_state
+                    .output
+                    .write_stderr(format!("let: {}\n", message));
+                return ExitStatus::FAILURE;
+            }
+        }
+    }
+
+    ExitStatus::from(last != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn let_builtin(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("let", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_let() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            let_builtin(&["a = 5", "b = a * 2"], &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(state.parameter("b"), Some(String::from("10")));
+        assert_eq!(let_builtin(&["a - 5"], &mut state), ExitStatus::FAILURE);
+    }
+
+    #[test]
+    fn test_let_error() {
+        let mut state = ShellState::default();
+
+        assert_eq!(let_builtin(&["1 / 0"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(state.output.stderr(), b"let: 1 / 0: division by 0\n");
+    }
+}
@@ -0,0 +1,486 @@
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+
+use crate::builtin::parse_flags;
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::filesystem::{self, FileType, Metadata};
+use crate::shell_state::ShellState;
+
+// the width of the terminal when COLUMNS isn't set
+const DEFAULT_WIDTH: usize = 80;
+// a long listing shows the year instead of the time for files older than this
+const SIX_MONTHS: Duration = Duration::from_secs(15_778_476);
+
+#[derive(Debug, Default)]
+struct Options {
+    // -a also lists "." and "..", -A only the entries starting with a "."
+    all: bool,
+    almost_all: bool,
+    long: bool,
+    one_per_line: bool,
+    human: bool,
+    recursive: bool,
+    by_time: bool,
+    reverse: bool,
+    // -F marks directories with a "/" and links with a "@"
+    classify: bool,
+    // -d lists directories themselves instead of what's in them
+    directory: bool,
+}
+
+struct Entry {
+    name: String,
+    metadata: Metadata,
+    // what a symbolic link points to, a long listing shows it
+    target: Option<String>,
+}
+
+impl Entry {
+    // A link is followed when `follow` is true, otherwise the entry is the link itself
+    fn new(name: String, path: &Path, follow: bool, shell_state: &ShellState) -> io::Result<Self> {
+        let metadata = match follow {
+            true => shell_state.fs.metadata(path)?,
+            false => shell_state.fs.symlink_metadata(path)?,
+        };
+        let target = match metadata.file_type {
+            FileType::Symlink => shell_state.fs.read_link(path).ok(),
+            _ => None,
+        };
+
+        Ok(Entry {
+            name,
+            metadata,
+            target: target.map(|target| target.to_string_lossy().to_string()),
+        })
+    }
+}
+
+// ls [-aAlhRtrdF1C] [FILE]...
+// lists the files and the contents of the directories, in columns as wide as COLUMNS.
+// -l shows the type, size and modification time of every entry, -h with sizes like 1.5K.
+// -t sorts the newest first, -r reverses the order and -R lists subdirectories too.
+// >> ls -la
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (flags, mut operands) = match parse_flags(&cmd.args, "aAlhRtrdF1C") {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            shell_state
+                .output
+                .write_stderr(format!("ls: {}\n", message));
+            return ExitStatus::USAGE;
+        }
+    };
+
+    let mut options = Options::default();
+    for flag in flags.chars() {
+        match flag {
+            'a' => options.all = true,
+            'A' => options.almost_all = true,
+            'l' => options.long = true,
+            'h' => options.human = true,
+            'R' => options.recursive = true,
+            't' => options.by_time = true,
+            'r' => options.reverse = true,
+            'd' => options.directory = true,
+            'F' => options.classify = true,
+            '1' => options.one_per_line = true,
+            // columns are the default
+            _ => options.one_per_line = false,
+        }
+    }
+
+    if operands.is_empty() {
+        operands.push(String::from("."));
+    }
+
+    let mut status = ExitStatus::SUCCESS;
+    let mut files = Vec::new();
+    let mut directories = Vec::new();
+
+    // like ls, a link to a directory that is given is listed as the directory
+    let follow = !options.long && !options.directory && !options.classify;
+
+    for operand in &operands {
+        let path = shell_state.resolve_path(operand);
+
+        match Entry::new(operand.clone(), &path, follow, shell_state) {
+            Ok(entry) if entry.metadata.file_type == FileType::Directory && !options.directory => {
+                directories.push(entry)
+            }
+            Ok(entry) => files.push(entry),
+            Err(error) => {
+                shell_state.output.write_stderr(format!(
+                    "ls: cannot access '{}': {}\n",
+                    operand,
+                    filesystem::describe_error(&error)
+                ));
+                // like ls, 2 is for serious trouble
+                status = ExitStatus::USAGE;
+            }
+        }
+    }
+
+    sort(&mut files, &options);
+    sort(&mut directories, &options);
+
+    let mut output = format_entries(&files, &options, shell_state);
+    let with_headers = !files.is_empty() || operands.len() > 1 || options.recursive;
+
+    for directory in directories {
+        if let Err((path, error)) = list_directory(
+            &directory.name,
+            with_headers,
+            &options,
+            &mut output,
+            shell_state,
+        ) {
+            shell_state.output.write_stdout(std::mem::take(&mut output));
+            shell_state.output.write_stderr(format!(
+                "ls: cannot open directory '{}': {}\n",
+                path,
+                filesystem::describe_error(&error)
+            ));
+            status = ExitStatus::FAILURE;
+        }
+    }
+
+    shell_state.output.write_stdout(output);
+    status
+}
+
+// Adds the listing of a directory to the output, with -R the ones of its subdirectories follow
+fn list_directory(
+    path: &str,
+    with_header: bool,
+    options: &Options,
+    output: &mut String,
+    shell_state: &ShellState,
+) -> Result<(), (String, io::Error)> {
+    let resolved = shell_state.resolve_path(path);
+    let mut names = shell_state
+        .fs
+        .read_dir(&resolved)
+        .map_err(|error| (String::from(path), error))?;
+
+    if options.all {
+        names.splice(0..0, vec![String::from("."), String::from("..")]);
+    }
+
+    let mut entries: Vec<Entry> = names
+        .into_iter()
+        .filter(|name| options.all || options.almost_all || !name.starts_with('.'))
+        .filter_map(|name| {
+            let path = shell_state.resolve_path(&format!("{}/{}", path, name));
+            Entry::new(name, &path, false, shell_state).ok()
+        })
+        .collect();
+    sort(&mut entries, options);
+
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    if with_header {
+        output.push_str(&format!("{}:\n", path));
+    }
+    output.push_str(&format_entries(&entries, options, shell_state));
+
+    if options.recursive {
+        let subdirectories = entries.iter().filter(|entry| {
+            entry.metadata.file_type == FileType::Directory
+                && entry.name != "."
+                && entry.name != ".."
+        });
+
+        for entry in subdirectories {
+            let path = format!("{}/{}", path.trim_end_matches('/'), entry.name);
+            list_directory(&path, true, options, output, shell_state)?;
+        }
+    }
+
+    Ok(())
+}
+
+// by name, or the newest first with -t
+fn sort(entries: &mut [Entry], options: &Options) {
+    entries.sort_by(|a, b| match options.by_time {
+        true => b
+            .metadata
+            .modified
+            .cmp(&a.metadata.modified)
+            .then_with(|| a.name.cmp(&b.name)),
+        false => a.name.cmp(&b.name),
+    });
+
+    if options.reverse {
+        entries.reverse();
+    }
+}
+
+fn format_entries(entries: &[Entry], options: &Options, shell_state: &ShellState) -> String {
+    let names: Vec<String> = entries
+        .iter()
+        .map(|entry| match (options.classify, entry.metadata.file_type) {
+            (true, FileType::Directory) => format!("{}/", entry.name),
+            // a long listing shows where the link points instead
+            (true, FileType::Symlink) if !options.long => format!("{}@", entry.name),
+            _ => entry.name.clone(),
+        })
+        .collect();
+
+    if options.long {
+        return format_long(entries, &names, options, shell_state.clock.time());
+    }
+    if options.one_per_line {
+        return names.iter().map(|name| format!("{}\n", name)).collect();
+    }
+
+    let width = shell_state
+        .parameter("COLUMNS")
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(DEFAULT_WIDTH);
+    format_columns(&names, width)
+}
+
+// >> format_long(entries, names, options, now)
+// "drwxr-xr-x    0 Jan  1 00:00 docs\n-rw-r--r-- 1500 Jan  1 00:00 notes.txt\n"
+fn format_long(entries: &[Entry], names: &[String], options: &Options, now: SystemTime) -> String {
+    let sizes: Vec<String> = entries
+        .iter()
+        .map(|entry| match options.human {
+            true => human_size(entry.metadata.len),
+            false => entry.metadata.len.to_string(),
+        })
+        .collect();
+    let size_width = sizes.iter().map(String::len).max().unwrap_or(0);
+
+    let mut output = String::new();
+    for ((entry, name), size) in entries.iter().zip(names).zip(sizes) {
+        let mode = match entry.metadata.file_type {
+            FileType::Directory => "drwxr-xr-x",
+            FileType::File => "-rw-r--r--",
+            FileType::Symlink => "lrwxrwxrwx",
+        };
+        let name = match &entry.target {
+            Some(target) => format!("{} -> {}", name, target),
+            None => name.clone(),
+        };
+
+        output.push_str(&format!(
+            "{} {:>width$} {} {}\n",
+            mode,
+            size,
+            format_time(entry.metadata.modified, now),
+            name,
+            width = size_width
+        ));
+    }
+
+    output
+}
+
+// Sizes in powers of 1024 that are rounded up, with a decimal below 10
+// >> human_size(1500)
+// "1.5K"
+fn human_size(len: u64) -> String {
+    let mut size = len as f64;
+    let mut units = ["K", "M", "G", "T", "P"].iter();
+    let mut unit = "";
+
+    while size >= 1024.0 {
+        match units.next() {
+            Some(next) => unit = next,
+            None => break,
+        }
+        size /= 1024.0;
+    }
+
+    match (unit, size < 10.0) {
+        ("", _) => len.to_string(),
+        (unit, true) => format!("{:.1}{}", (size * 10.0).ceil() / 10.0, unit),
+        (unit, false) => format!("{}{}", size.ceil(), unit),
+    }
+}
+
+// Times of the last six months show the time of day, older and future ones the year
+fn format_time(modified: SystemTime, now: SystemTime) -> String {
+    let date = DateTime::<Utc>::from(modified);
+
+    match now.duration_since(modified) {
+        Ok(age) if age < SIX_MONTHS => date.format("%b %e %H:%M").to_string(),
+        _ => date.format("%b %e  %Y").to_string(),
+    }
+}
+
+// The names filled in to columns from top to bottom, with as many columns as fit in
+// the width. Every column is as wide as its longest name and 2 spaces.
+// >> format_columns(["a", "bb", "c"], 6)
+// "a  c\nbb\n"
+fn format_columns(names: &[String], width: usize) -> String {
+    let lengths: Vec<usize> = names.iter().map(|name| name.chars().count()).collect();
+    let mut layout = (names.len(), vec![0]);
+
+    for columns in 1..=names.len() {
+        let rows = names.len().div_ceil(columns);
+        let widths: Vec<usize> = lengths
+            .chunks(rows)
+            .map(|column| column.iter().max().unwrap_or(&0) + 2)
+            .collect();
+
+        if columns == 1 || widths.iter().sum::<usize>() <= width {
+            layout = (rows, widths);
+        }
+    }
+
+    let (rows, widths) = layout;
+    let mut output = String::new();
+    for row in 0..rows {
+        let mut column = 0;
+        let mut i = row;
+
+        while i < names.len() {
+            let is_last = i + rows >= names.len();
+            match is_last {
+                true => output.push_str(&names[i]),
+                false => output.push_str(&format!("{:<1$}", names[i], widths[column])),
+            }
+
+            column += 1;
+            i += rows;
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn ls(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("ls", args);
+
+        run(&cmd, shell_state)
+    }
+
+    fn output(args: &[&str], shell_state: &mut ShellState) -> String {
+        ls(args, shell_state);
+        let output = shell_state.output.stdout();
+        shell_state.output.drain().for_each(drop);
+
+        String::from_utf8_lossy(&output).to_string()
+    }
+
+    fn state() -> ShellState {
+        let state = ShellState::default();
+        state.fs.create_dir(Path::new("/docs")).unwrap();
+        state.fs.create_dir(Path::new("/docs/old")).unwrap();
+        state
+            .fs
+            .write(Path::new("/docs/notes.txt"), &[0; 1500])
+            .unwrap();
+        state.fs.write(Path::new("/.hidden"), b"").unwrap();
+        state.fs.write(Path::new("/b.txt"), b"b").unwrap();
+        state.fs.write(Path::new("/a.txt"), b"aa").unwrap();
+        state
+    }
+
+    #[test]
+    fn test_list() {
+        let mut state = state();
+
+        assert_eq!(output(&[], &mut state), "a.txt  b.txt  docs\n");
+        assert_eq!(
+            output(&["-1A"], &mut state),
+            ".hidden\na.txt\nb.txt\ndocs\n"
+        );
+        assert_eq!(
+            output(&["-a", "-F"], &mut state),
+            "./  ../  .hidden  a.txt  b.txt  docs/\n"
+        );
+        assert_eq!(
+            output(&["-1r", "docs", "a.txt"], &mut state),
+            "a.txt\n\ndocs:\nold\nnotes.txt\n"
+        );
+        assert_eq!(output(&["-d", "docs"], &mut state), "docs\n");
+    }
+
+    #[test]
+    fn test_recursive() {
+        let mut state = state();
+        state.current_dir = String::from("/docs");
+
+        assert_eq!(
+            output(&["-R"], &mut state),
+            ".:\nnotes.txt  old\n\n./old:\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_by_time() {
+        let mut state = state();
+        let day = Duration::from_secs(24 * 60 * 60);
+        for (path, days) in [("/a.txt", 1), ("/b.txt", 3), ("/docs", 2)] {
+            let time = SystemTime::UNIX_EPOCH + day * days;
+            state.fs.set_modified(Path::new(path), time).unwrap();
+        }
+
+        assert_eq!(output(&["-t"], &mut state), "b.txt  docs  a.txt\n");
+        assert_eq!(output(&["-tr"], &mut state), "a.txt  docs  b.txt\n");
+    }
+
+    #[test]
+    fn test_long() {
+        let mut state = state();
+
+        assert_eq!(
+            output(&["-l", "docs"], &mut state),
+            "-rw-r--r-- 1500 Jan  1 00:00 notes.txt\ndrwxr-xr-x    0 Jan  1 00:00 old\n"
+        );
+        assert_eq!(
+            output(&["-lh", "docs/notes.txt", "a.txt"], &mut state),
+            "-rw-r--r--    2 Jan  1 00:00 a.txt\n-rw-r--r-- 1.5K Jan  1 00:00 docs/notes.txt\n"
+        );
+    }
+
+    #[test]
+    fn test_human_size_and_time() {
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(20000), "20K");
+        assert_eq!(human_size(3_000_000), "2.9M");
+
+        let now = SystemTime::UNIX_EPOCH + SIX_MONTHS * 2;
+        assert_eq!(format_time(now - SIX_MONTHS / 2, now), "Oct  1 22:21");
+        assert_eq!(format_time(SystemTime::UNIX_EPOCH, now), "Jan  1  1970");
+    }
+
+    #[test]
+    fn test_columns() {
+        let names: Vec<String> = (1..=7).map(|i| "x".repeat(i)).collect();
+
+        assert_eq!(
+            format_columns(&names, 24),
+            "x    xxxx    xxxxxxx\nxx   xxxxx\nxxx  xxxxxx\n"
+        );
+        assert_eq!(format_columns(&names[..2], 1), "x\nxx\n");
+        assert_eq!(format_columns(&[], 80), "");
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = state();
+
+        assert_eq!(ls(&["missing", "a.txt"], &mut state), ExitStatus::USAGE);
+        assert_eq!(ls(&["-y"], &mut state), ExitStatus::USAGE);
+        assert_eq!(state.output.stdout(), b"a.txt\n");
+        assert_eq!(
+            String::from_utf8_lossy(&state.output.stderr()),
+            "ls: cannot access 'missing': No such file or directory\nls: invalid option -- 'y'\n"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use std::io;
+
+use crate::builtin::parse_flags;
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::filesystem::{self, FileType};
+use crate::shell_state::ShellState;
+
+// mkdir [-pv] DIRECTORY...
+// -p also creates the missing parents, a directory that exists is no error then.
+// -v tells about every directory it creates.
+// >> mkdir -p projects/shell/src
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (flags, operands) = match parse_flags(&cmd.args, "pv") {
+        Ok(parsed) => parsed,
+        Err(message) => return fail(&message, shell_state),
+    };
+    if operands.is_empty() {
+        return fail("missing operand", shell_state);
+    }
+
+    let verbose = flags.contains('v');
+    let mut status = ExitStatus::SUCCESS;
+
+    for operand in &operands {
+        let directories = match flags.contains('p') {
+            true => parents(operand),
+            false => vec![operand.as_str()],
+        };
+
+        for directory in directories {
+            let created = match create(directory, flags.contains('p'), shell_state) {
+                Ok(created) => created,
+                Err(error) => {
+                    shell_state.output.write_stderr(format!(
+                        "mkdir: cannot create directory '{}': {}\n",
+                        directory,
+                        filesystem::describe_error(&error)
+                    ));
+                    status = ExitStatus::FAILURE;
+                    break;
+                }
+            };
+
+            if verbose && created {
+                shell_state
+                    .output
+                    .write_stdout(format!("mkdir: created directory '{}'\n", directory));
+            }
+        }
+    }
+
+    status
+}
+
+// Returns whether the directory is created, with -p an existing one is skipped
+fn create(directory: &str, parents: bool, shell_state: &ShellState) -> io::Result<bool> {
+    let path = shell_state.resolve_path(directory);
+
+    match shell_state.fs.metadata(&path) {
+        Ok(metadata) if parents && metadata.file_type == FileType::Directory => Ok(false),
+        Ok(_) if parents => Err(io::ErrorKind::NotADirectory.into()),
+        _ => shell_state.fs.create_dir(&path).map(|_| true),
+    }
+}
+
+// The directory and every parent in it, the outermost first
+// >> parents("a/b/c")
+// vec!["a", "a/b", "a/b/c"]
+fn parents(directory: &str) -> Vec<&str> {
+    let directory = directory.trim_end_matches('/');
+    let mut parents: Vec<&str> = directory
+        .match_indices('/')
+        .map(|(i, _)| &directory[..i])
+        .filter(|parent| !parent.is_empty() && !parent.ends_with('/'))
+        .collect();
+
+    if !directory.is_empty() {
+        parents.push(directory);
+    }
+    parents
+}
+
+fn fail(message: &str, shell_state: &mut ShellState) -> ExitStatus {
+    shell_state
+        .output
+        .write_stderr(format!("mkdir: {}\n", message));
+    ExitStatus::FAILURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn mkdir(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("mkdir", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_create_directories() {
+        let mut state = ShellState::default();
+
+        assert_eq!(mkdir(&["a", "b"], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(
+            mkdir(&["-pv", "a/c/d", "/e"], &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(mkdir(&["-p", "a/c"], &mut state), ExitStatus::SUCCESS);
+        assert!(state.fs.is_dir(Path::new("/a/c/d")));
+        assert!(state.fs.is_dir(Path::new("/b")));
+        assert_eq!(
+            state.output.stdout(),
+            b"mkdir: created directory 'a/c'\nmkdir: created directory 'a/c/d'\n\
+              mkdir: created directory '/e'\n"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = ShellState::default();
+        state.fs.write(Path::new("/f"), b"").unwrap();
+
+        assert_eq!(mkdir(&["f", "x/y"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(mkdir(&["-p", "f/y"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(mkdir(&[], &mut state), ExitStatus::FAILURE);
+        assert_eq!(mkdir(&["-x"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(
+            String::from_utf8_lossy(&state.output.stderr()),
+            "mkdir: cannot create directory 'f': File exists\n\
+             mkdir: cannot create directory 'x/y': No such file or directory\n\
+             mkdir: cannot create directory 'f': Not a directory\n\
+             mkdir: missing operand\n\
+             mkdir: invalid option -- 'x'\n"
+        );
+    }
+
+    #[test]
+    fn test_parents() {
+        assert_eq!(parents("a/b/c/"), vec!["a", "a/b", "a/b/c"]);
+        assert_eq!(parents("/a//b"), vec!["/a", "/a//b"]);
+    }
+}
@@ -1,16 +1,46 @@
+use std::io;
 use std::str::FromStr;
 
 use crate::command::Command;
 use crate::executor::history;
+use crate::exit_status::ExitStatus;
+use crate::filesystem;
 use crate::shell_state::ShellState;
 
+mod alias;
+mod base64;
 mod bools;
 mod cat;
+mod cd;
 pub mod clear;
+mod command;
+mod copy;
+mod declare;
 mod echo;
+mod escape;
+mod eval;
+mod getopts;
+mod head;
+mod let_builtin;
+mod ls;
+mod mkdir;
+mod printf;
+mod pwd;
+mod read;
+mod rm;
+mod set;
+mod shift;
+mod shopt;
+pub mod source;
+pub mod test;
+mod touch;
+mod trap;
+mod type_builtin;
+mod unset;
+mod xxd;
 
-#[derive(Debug, PartialEq)]
-enum BuiltinCommands {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BuiltinCommands {
     Echo,
     History,
     Cd,
@@ -19,6 +49,36 @@ enum BuiltinCommands {
     Cat,
     False,
     True,
+    Head,
+    Base64,
+    Xxd,
+    Shopt,
+    Let,
+    Test,
+    Alias,
+    Unalias,
+    Source,
+    Eval,
+    Exec,
+    Command,
+    Builtin,
+    Type,
+    Which,
+    Set,
+    Trap,
+    Getopts,
+    Shift,
+    Declare,
+    Unset,
+    Read,
+    Printf,
+    Ls,
+    Mkdir,
+    Touch,
+    Rm,
+    Rmdir,
+    Cp,
+    Mv,
 }
 
 impl FromStr for BuiltinCommands {
@@ -34,23 +94,170 @@ impl FromStr for BuiltinCommands {
             "cat" => Ok(BuiltinCommands::Cat),
             "false" => Ok(BuiltinCommands::False),
             "true" => Ok(BuiltinCommands::True),
+            "head" => Ok(BuiltinCommands::Head),
+            "base64" => Ok(BuiltinCommands::Base64),
+            "xxd" => Ok(BuiltinCommands::Xxd),
+            "shopt" => Ok(BuiltinCommands::Shopt),
+            "let" => Ok(BuiltinCommands::Let),
+            "test" | "[" => Ok(BuiltinCommands::Test),
+            "alias" => Ok(BuiltinCommands::Alias),
+            "unalias" => Ok(BuiltinCommands::Unalias),
+            "source" | "." => Ok(BuiltinCommands::Source),
+            "eval" => Ok(BuiltinCommands::Eval),
+            "exec" => Ok(BuiltinCommands::Exec),
+            "command" => Ok(BuiltinCommands::Command),
+            "builtin" => Ok(BuiltinCommands::Builtin),
+            "type" => Ok(BuiltinCommands::Type),
+            "which" => Ok(BuiltinCommands::Which),
+            "set" => Ok(BuiltinCommands::Set),
+            "trap" => Ok(BuiltinCommands::Trap),
+            "getopts" => Ok(BuiltinCommands::Getopts),
+            "shift" => Ok(BuiltinCommands::Shift),
+            "declare" | "typeset" => Ok(BuiltinCommands::Declare),
+            "unset" => Ok(BuiltinCommands::Unset),
+            "read" => Ok(BuiltinCommands::Read),
+            "printf" => Ok(BuiltinCommands::Printf),
+            "ls" => Ok(BuiltinCommands::Ls),
+            "mkdir" => Ok(BuiltinCommands::Mkdir),
+            "touch" => Ok(BuiltinCommands::Touch),
+            "rm" => Ok(BuiltinCommands::Rm),
+            "rmdir" => Ok(BuiltinCommands::Rmdir),
+            "cp" => Ok(BuiltinCommands::Cp),
+            "mv" => Ok(BuiltinCommands::Mv),
             _ => Err(()),
         }
     }
 }
 
-pub fn evaluate(cmd: &Command, shell_state: &mut ShellState) -> Result<(), ()> {
-    match BuiltinCommands::from_str(&cmd.keyword) {
-        Ok(BuiltinCommands::Echo) => Ok(echo::run(cmd, shell_state)),
-        Ok(BuiltinCommands::History) => Ok(history::run(shell_state)),
-        Ok(BuiltinCommands::Clear) => Ok(clear::run(shell_state)),
-        Ok(BuiltinCommands::Cat) => Ok(cat::run(cmd, shell_state)),
-        Ok(BuiltinCommands::False) => Ok(bools::false_builtin(shell_state)),
-        Ok(BuiltinCommands::True) => Ok(bools::true_builtin(shell_state)),
-        _ => Err(()),
+// words the parser gives a meaning, `type` reports them before builtins
+const KEYWORDS: [&str; 9] = ["!", "case", "esac", "in", "time", "{", "}", "[[", "]]"];
+
+// What a command name refers to
+#[derive(Debug, PartialEq)]
+pub enum CommandType {
+    Alias(String),
+    Keyword,
+    Builtin(BuiltinCommands),
+    // a name with a slash is the path of a file, eg: a script
+    File,
+}
+
+// Looks up a name the way `type` reports it, aliases and keywords come first
+pub fn resolve(name: &str, shell_state: &ShellState) -> Option<CommandType> {
+    if let Some(value) = shell_state.aliases.get(name) {
+        return Some(CommandType::Alias(value.clone()));
+    }
+
+    if KEYWORDS.contains(&name) {
+        return Some(CommandType::Keyword);
+    }
+
+    resolve_command(name, shell_state)
+}
+
+// What a command runs as. Aliases and keywords are taken care of by the parser,
+// so a command that runs only finds builtins and files.
+pub fn resolve_command(name: &str, shell_state: &ShellState) -> Option<CommandType> {
+    if let Ok(builtin) = BuiltinCommands::from_str(name) {
+        return Some(CommandType::Builtin(builtin));
+    }
+
+    // a name with a slash is a path, it's never looked up as a command
+    match name.contains('/') && shell_state.fs.exists(&shell_state.resolve_path(name)) {
+        true => Some(CommandType::File),
+        false => None,
+    }
+}
+
+pub fn run(builtin: BuiltinCommands, cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    match builtin {
+        BuiltinCommands::Echo => echo::run(cmd, shell_state),
+        BuiltinCommands::History => history::run(shell_state),
+        BuiltinCommands::Clear => clear::run(shell_state),
+        BuiltinCommands::Cat => cat::run(cmd, shell_state),
+        BuiltinCommands::False => bools::false_builtin(),
+        BuiltinCommands::True => bools::true_builtin(),
+        BuiltinCommands::Head => head::run(cmd, shell_state),
+        BuiltinCommands::Base64 => base64::run(cmd, shell_state),
+        BuiltinCommands::Xxd => xxd::run(cmd, shell_state),
+        BuiltinCommands::Shopt => shopt::run(cmd, shell_state),
+        BuiltinCommands::Let => let_builtin::run(cmd, shell_state),
+        BuiltinCommands::Test => test::run(cmd, shell_state),
+        BuiltinCommands::Alias => alias::alias(cmd, shell_state),
+        BuiltinCommands::Unalias => alias::unalias(cmd, shell_state),
+        BuiltinCommands::Source => source::run(cmd, shell_state),
+        BuiltinCommands::Eval => eval::run(cmd, shell_state),
+        BuiltinCommands::Exec => command::exec(cmd, shell_state),
+        BuiltinCommands::Command => command::command(cmd, shell_state),
+        BuiltinCommands::Builtin => command::builtin(cmd, shell_state),
+        BuiltinCommands::Type => type_builtin::type_builtin(cmd, shell_state),
+        BuiltinCommands::Which => type_builtin::which(cmd, shell_state),
+        BuiltinCommands::Set => set::run(cmd, shell_state),
+        BuiltinCommands::Trap => trap::run(cmd, shell_state),
+        BuiltinCommands::Getopts => getopts::run(cmd, shell_state),
+        BuiltinCommands::Shift => shift::run(cmd, shell_state),
+        BuiltinCommands::Declare => declare::run(cmd, shell_state),
+        BuiltinCommands::Unset => unset::run(cmd, shell_state),
+        BuiltinCommands::Read => read::run(cmd, shell_state),
+        BuiltinCommands::Printf => printf::run(cmd, shell_state),
+        BuiltinCommands::Ls => ls::run(cmd, shell_state),
+        BuiltinCommands::Mkdir => mkdir::run(cmd, shell_state),
+        BuiltinCommands::Touch => touch::run(cmd, shell_state),
+        BuiltinCommands::Rm => rm::rm(cmd, shell_state),
+        BuiltinCommands::Rmdir => rm::rmdir(cmd, shell_state),
+        BuiltinCommands::Cp => copy::cp(cmd, shell_state),
+        BuiltinCommands::Mv => copy::mv(cmd, shell_state),
+        BuiltinCommands::Cd => cd::run(cmd, shell_state),
+        BuiltinCommands::Pwd => pwd::run(shell_state),
     }
 }
 
+// Contents of a file given as argument, "-" is the stdin of the command
+fn read_input(path: &str, shell_state: &mut ShellState) -> io::Result<Vec<u8>> {
+    match path {
+        "-" => Ok(shell_state.output.stdin.take().unwrap_or_default()),
+        path => shell_state.fs.read(&shell_state.resolve_path(path)),
+    }
+}
+
+// >> write_file_error("cat", "foo", NotFound)
+// "cat: foo: No such file or directory"
+fn write_file_error(name: &str, path: &str, error: &io::Error, shell_state: &mut ShellState) {
+    shell_state.output.write_stderr(format!(
+        "{}: {}: {}\n",
+        name,
+        path,
+        filesystem::describe_error(error)
+    ));
+}
+
+// Splits the flags of a command like the coreutils from its operands, eg: `rm -rf a b`.
+// Flags can be bundled and follow the operands, `--` ends them and `-` is an operand.
+// >> parse_flags(["-rf", "a", "-i"], "rfi")
+// Ok(("rfi", ["a"]))
+fn parse_flags(args: &[String], allowed: &str) -> Result<(String, Vec<String>), String> {
+    let mut flags = String::new();
+    let mut operands = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix('-') {
+            Some("-") => operands.extend(args.by_ref().cloned()),
+            Some(bundle) if !bundle.is_empty() => {
+                for flag in bundle.chars() {
+                    if !allowed.contains(flag) {
+                        return Err(format!("invalid option -- '{}'", flag));
+                    }
+                    flags.push(flag);
+                }
+            }
+            _ => operands.push(arg.clone()),
+        }
+    }
+
+    Ok((flags, operands))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -65,4 +272,18 @@ mod tests {
     fn test_builtin_commands_from_str_when_undefined() {
         assert!(BuiltinCommands::from_str("notabuiltin").is_err());
     }
+
+    #[test]
+    fn test_parse_flags() {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|arg| String::from(*arg)).collect() };
+
+        assert_eq!(
+            parse_flags(&args(&["-rf", "a", "-i", "-", "--", "-b"]), "rfi"),
+            Ok((String::from("rfi"), args(&["a", "-", "-b"])))
+        );
+        assert_eq!(
+            parse_flags(&args(&["-rx"]), "rfi"),
+            Err(String::from("invalid option -- 'x'"))
+        );
+    }
 }
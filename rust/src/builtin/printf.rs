@@ -0,0 +1,805 @@
+use std::convert::TryFrom;
+
+use crate::builtin::escape::{self, Escapes};
+use crate::command::Command;
+use crate::executor;
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+const USAGE: &str = "printf: usage: printf [-v var] format [arguments]\n";
+
+// A width or precision, `*` takes it from the next argument
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Count {
+    Fixed(usize),
+    Argument,
+}
+
+// a conversion of the format, eg: `%-5.2f`
+#[derive(Debug, PartialEq, Clone)]
+struct Spec {
+    left: bool,
+    zero: bool,
+    plus: bool,
+    space: bool,
+    alternate: bool,
+    width: Option<Count>,
+    precision: Option<Count>,
+    conversion: char,
+}
+
+#[derive(Debug, PartialEq)]
+enum Piece {
+    // text with its escapes expanded
+    Literal(Vec<u8>),
+    Conversion(Spec),
+    // the format is wrong from here on, nothing after it is written
+    Invalid(String),
+}
+
+// printf [-v VAR] FORMAT [ARGUMENTS...]
+// writes the arguments the way the conversions of the format say, eg: `%-5.2f`.
+// The format is used again while there are arguments left, a conversion without an
+// argument gets an empty string or 0. With -v the output is assigned to VAR instead.
+// >> printf '%-4s|%03d\n' ab 7 cd 8
+// "ab  |007\ncd  |008\n"
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (variable, args) = match parse_options(&cmd.args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            shell_state
+                .output
+                .write_stderr(format!("printf: {}{}", message, USAGE));
+            return ExitStatus::USAGE;
+        }
+    };
+
+    if let Some(variable) = variable.filter(|variable| !executor::is_name(variable)) {
+        shell_state
+            .output
+            .write_stderr(format!("printf: `{}': not a valid identifier\n", variable));
+        return ExitStatus::USAGE;
+    }
+
+    let format = match args.first() {
+        Some(format) => format,
+        None => {
+            shell_state.output.write_stderr(USAGE);
+            return ExitStatus::USAGE;
+        }
+    };
+
+    let mut printer = Printer {
+        args: &args[1..],
+        next: 0,
+        output: Vec::new(),
+        errors: String::new(),
+        status: ExitStatus::SUCCESS,
+    };
+    printer.print(&parse_format(format));
+
+    shell_state.output.write_stderr(printer.errors);
+    match variable {
+        Some(variable) => {
+            let value = String::from_utf8_lossy(&printer.output).to_string();
+            shell_state.set_variable(variable, value);
+        }
+        None => shell_state.output.write_stdout(printer.output),
+    }
+
+    printer.status
+}
+
+// the variable of -v and the format with its arguments
+fn parse_options(args: &[String]) -> Result<(Option<&str>, &[String]), String> {
+    let (variable, rest) = match args.first().map(String::as_str) {
+        Some("-v") => match args.get(1) {
+            Some(variable) => (Some(variable.as_str()), &args[2..]),
+            None => return Err(String::from("-v: option requires an argument\n")),
+        },
+        Some(arg) if arg.starts_with("-v") => (Some(&arg[2..]), &args[1..]),
+        Some(arg) if arg.starts_with('-') && arg.len() > 1 && arg != "--" => {
+            return Err(format!("{}: invalid option\n", &arg[..2]));
+        }
+        _ => (None, args),
+    };
+
+    match rest.first().map(String::as_str) {
+        Some("--") => Ok((variable, &rest[1..])),
+        _ => Ok((variable, rest)),
+    }
+}
+
+// Splits the format in to text and conversions, `%%` is a `%`
+fn parse_format(format: &str) -> Vec<Piece> {
+    let chars: Vec<char> = format.chars().collect();
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                text.extend(chars[i..].iter().take(2));
+                i += 2;
+                continue;
+            }
+            '%' if chars.get(i + 1) == Some(&'%') => {
+                text.push_str("\\045");
+                i += 2;
+                continue;
+            }
+            '%' => (),
+            char => {
+                text.push(char);
+                i += 1;
+                continue;
+            }
+        }
+
+        pieces.push(Piece::Literal(escape::expand(&text, Escapes::Format).0));
+        text.clear();
+
+        let (piece, end) = parse_spec(&chars, i);
+        let is_invalid = matches!(piece, Piece::Invalid(_));
+        pieces.push(piece);
+        if is_invalid {
+            return pieces;
+        }
+        i = end;
+    }
+
+    pieces.push(Piece::Literal(escape::expand(&text, Escapes::Format).0));
+    pieces
+}
+
+// parses the conversion starting at the `%`, returns it and the index after it
+fn parse_spec(chars: &[char], start: usize) -> (Piece, usize) {
+    let mut spec = Spec {
+        left: false,
+        zero: false,
+        plus: false,
+        space: false,
+        alternate: false,
+        width: None,
+        precision: None,
+        conversion: 's',
+    };
+    let mut i = start + 1;
+
+    while let Some(flag) = chars.get(i) {
+        match flag {
+            '-' => spec.left = true,
+            '0' => spec.zero = true,
+            '+' => spec.plus = true,
+            ' ' => spec.space = true,
+            '#' => spec.alternate = true,
+            _ => break,
+        }
+        i += 1;
+    }
+
+    let (width, end) = parse_count(chars, i);
+    spec.width = width;
+    i = end;
+
+    if chars.get(i) == Some(&'.') {
+        let (precision, end) = parse_count(chars, i + 1);
+        spec.precision = Some(precision.unwrap_or(Count::Fixed(0)));
+        i = end;
+    }
+
+    // the size of the number doesn't matter, eg: `%ld`
+    while chars.get(i).is_some_and(|char| "hlLjzt".contains(*char)) {
+        i += 1;
+    }
+
+    match chars.get(i) {
+        Some(conversion) if "sbqcdiuoxXfFeEgG".contains(*conversion) => {
+            spec.conversion = *conversion;
+            (Piece::Conversion(spec), i + 1)
+        }
+        Some(conversion) => (
+            Piece::Invalid(format!("`{}': invalid format character", conversion)),
+            i,
+        ),
+        None => {
+            let spec: String = chars[start..].iter().collect();
+            (
+                Piece::Invalid(format!("`{}': missing format character", spec)),
+                i,
+            )
+        }
+    }
+}
+
+fn parse_count(chars: &[char], start: usize) -> (Option<Count>, usize) {
+    if chars.get(start) == Some(&'*') {
+        return (Some(Count::Argument), start + 1);
+    }
+
+    let digits: String = chars[start..]
+        .iter()
+        .take_while(|char| char.is_ascii_digit())
+        .collect();
+    match digits.parse() {
+        Ok(count) => (Some(Count::Fixed(count)), start + digits.len()),
+        Err(_) => (None, start),
+    }
+}
+
+struct Printer<'a> {
+    args: &'a [String],
+    next: usize,
+    output: Vec<u8>,
+    errors: String,
+    status: ExitStatus,
+}
+
+impl Printer<'_> {
+    fn print(&mut self, pieces: &[Piece]) {
+        loop {
+            let start = self.next;
+
+            for piece in pieces {
+                match piece {
+                    Piece::Literal(bytes) => self.output.extend(bytes),
+                    Piece::Conversion(spec) => {
+                        // `\c` in the argument of `%b` ends the output
+                        if !self.convert(spec) {
+                            return;
+                        }
+                    }
+                    Piece::Invalid(message) => {
+                        self.errors.push_str(&format!("printf: {}\n", message));
+                        self.status = ExitStatus::FAILURE;
+                        return;
+                    }
+                }
+            }
+
+            // the format is used again for the arguments that are left
+            if self.next == start || self.next >= self.args.len() {
+                return;
+            }
+        }
+    }
+
+    fn next_arg(&mut self) -> &str {
+        let arg = self.args.get(self.next).map_or("", String::as_str);
+        self.next += 1;
+        arg
+    }
+
+    fn next_integer(&mut self) -> i128 {
+        let arg = self.next_arg().to_string();
+        let (value, error) = parse_integer(&arg);
+        self.report(&arg, error);
+        value
+    }
+
+    fn report(&mut self, arg: &str, error: Option<NumberError>) {
+        match error {
+            Some(NumberError::Invalid) => {
+                self.errors
+                    .push_str(&format!("printf: {}: invalid number\n", arg));
+                self.status = ExitStatus::FAILURE;
+            }
+            Some(NumberError::OutOfRange) => self.errors.push_str(&format!(
+                "printf: warning: {}: Numerical result out of range\n",
+                arg
+            )),
+            None => (),
+        }
+    }
+
+    // Writes the conversion of the next argument, returns whether the output goes on
+    fn convert(&mut self, spec: &Spec) -> bool {
+        let mut spec = spec.clone();
+        let mut width = 0;
+
+        match spec.width {
+            Some(Count::Fixed(count)) => width = count,
+            // a negative width from an argument aligns left
+            Some(Count::Argument) => {
+                let count = self.next_integer();
+                spec.left |= count < 0;
+                width = count.unsigned_abs().min(usize::MAX as u128) as usize;
+            }
+            None => (),
+        }
+
+        let precision = match spec.precision {
+            Some(Count::Fixed(count)) => Some(count),
+            Some(Count::Argument) => usize::try_from(self.next_integer()).ok(),
+            None => None,
+        };
+
+        let mut go_on = true;
+        let (sign, body, zero_padded) = match spec.conversion {
+            's' | 'b' | 'q' | 'c' => {
+                let arg = self.next_arg();
+                let mut body = match spec.conversion {
+                    's' => arg.as_bytes().to_vec(),
+                    'b' => {
+                        let (bytes, stopped) = escape::expand(arg, Escapes::Argument);
+                        go_on = !stopped;
+                        bytes
+                    }
+                    'q' => quote(arg).into_bytes(),
+                    // the char of an empty argument is the NUL byte
+                    _ => arg.chars().next().unwrap_or('\0').to_string().into_bytes(),
+                };
+
+                if let Some(precision) = precision {
+                    body.truncate(precision);
+                }
+                (String::new(), body, false)
+            }
+            'd' | 'i' => {
+                let value = self.next_integer();
+                let value = value.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+                let digits = pad_digits(value.unsigned_abs().to_string(), precision, false);
+
+                let sign = sign(value < 0, &spec);
+                (sign, digits.into_bytes(), precision.is_none())
+            }
+            'u' | 'o' | 'x' | 'X' => {
+                let value = self.next_integer();
+                // like a process does, a negative number wraps around
+                let value = match value < 0 {
+                    true => value.max(i64::MIN as i128) as i64 as u64,
+                    false => value.min(u64::MAX as u128 as i128) as u64,
+                };
+                let digits = match spec.conversion {
+                    'o' => format!("{:o}", value),
+                    'x' => format!("{:x}", value),
+                    'X' => format!("{:X}", value),
+                    _ => value.to_string(),
+                };
+                let is_octal = spec.conversion == 'o';
+                let digits = pad_digits(digits, precision, spec.alternate && is_octal);
+
+                let prefix = match spec.conversion {
+                    'x' if spec.alternate && value != 0 => "0x",
+                    'X' if spec.alternate && value != 0 => "0X",
+                    _ => "",
+                };
+                (
+                    String::from(prefix),
+                    digits.into_bytes(),
+                    precision.is_none(),
+                )
+            }
+            _ => {
+                let arg = self.next_arg().to_string();
+                let (value, error) = parse_float(&arg);
+                self.report(&arg, error);
+
+                let sign = sign(value.is_sign_negative() && !value.is_nan(), &spec);
+                let body = format_float(value.abs(), precision.unwrap_or(6), &spec);
+                (sign, body.into_bytes(), value.is_finite())
+            }
+        };
+
+        let length = sign.len() + body.len();
+        let padding = width.saturating_sub(length);
+        if spec.left {
+            self.output.extend(sign.bytes().chain(body));
+            self.output.extend(vec![b' '; padding]);
+        } else if spec.zero && zero_padded {
+            self.output.extend(sign.bytes());
+            self.output.extend(vec![b'0'; padding]);
+            self.output.extend(body);
+        } else {
+            self.output.extend(vec![b' '; padding]);
+            self.output.extend(sign.bytes().chain(body));
+        }
+
+        go_on
+    }
+}
+
+fn sign(is_negative: bool, spec: &Spec) -> String {
+    let sign = match is_negative {
+        true => "-",
+        false if spec.plus => "+",
+        false if spec.space => " ",
+        false => "",
+    };
+
+    String::from(sign)
+}
+
+// The precision of an integer is its minimum number of digits, 0 with a precision
+// of 0 has none. `%#o` always starts with a 0.
+fn pad_digits(digits: String, precision: Option<usize>, leading_zero: bool) -> String {
+    let digits = match precision {
+        Some(0) if digits == "0" => String::new(),
+        Some(precision) => format!("{:0>1$}", digits, precision),
+        None => digits,
+    };
+
+    match leading_zero && !digits.starts_with('0') {
+        true => format!("0{}", digits),
+        false => digits,
+    }
+}
+
+// A float of `%f`, `%e` or `%g` without its sign
+// >> format_float(12345.678, 6, 'e')
+// "1.234568e+04"
+fn format_float(value: f64, precision: usize, spec: &Spec) -> String {
+    let upper = spec.conversion.is_ascii_uppercase();
+    if !value.is_finite() {
+        let text = if value.is_nan() { "nan" } else { "inf" };
+        return if upper {
+            text.to_uppercase()
+        } else {
+            String::from(text)
+        };
+    }
+
+    let text = match spec.conversion.to_ascii_lowercase() {
+        'f' => format!("{:.*}", precision, value),
+        'e' => exponential(value, precision),
+        // the shortest of `%f` and `%e` with `precision` significant digits,
+        // without the zeros at the end
+        _ => {
+            let precision = precision.max(1);
+            let exponent = exponent(value, precision - 1);
+            let text = match exponent < -4 || exponent >= precision as i32 {
+                true => exponential(value, precision - 1),
+                false => format!("{:.*}", (precision as i32 - 1 - exponent) as usize, value),
+            };
+
+            match spec.alternate {
+                true => text,
+                false => remove_trailing_zeros(&text),
+            }
+        }
+    };
+
+    let text = match spec.alternate && precision == 0 && !text.contains('.') {
+        true => match text.find('e') {
+            Some(i) => format!("{}.{}", &text[..i], &text[i..]),
+            None => text + ".",
+        },
+        false => text,
+    };
+
+    if upper {
+        text.to_uppercase()
+    } else {
+        text
+    }
+}
+
+// `%e` writes the exponent with a sign and at least two digits
+fn exponential(value: f64, precision: usize) -> String {
+    let exponent = exponent(value, precision);
+    let text = format!("{:.*e}", precision, value);
+    let mantissa = &text[..text.find('e').unwrap_or(text.len())];
+    let sign = if exponent < 0 { '-' } else { '+' };
+
+    format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+}
+
+// the exponent of the value written with `precision` digits after the point
+fn exponent(value: f64, precision: usize) -> i32 {
+    let text = format!("{:.*e}", precision, value);
+    text[text.find('e').map_or(text.len(), |i| i + 1)..]
+        .parse()
+        .unwrap_or(0)
+}
+
+fn remove_trailing_zeros(text: &str) -> String {
+    let (number, exponent) = text.split_at(text.find('e').unwrap_or(text.len()));
+    let number = match number.contains('.') {
+        true => number.trim_end_matches('0').trim_end_matches('.'),
+        false => number,
+    };
+
+    format!("{}{}", number, exponent)
+}
+
+#[derive(Debug, PartialEq)]
+enum NumberError {
+    // the number is 0 or what the start of the argument is
+    Invalid,
+    // the number is the largest or smallest one
+    OutOfRange,
+}
+
+// A number is decimal, octal with a leading 0, hex with 0x, or the code of the char
+// after a quote. An empty argument is 0.
+// >> parse_integer("0x1f")
+// (31, None)
+fn parse_integer(arg: &str) -> (i128, Option<NumberError>) {
+    let text = arg.trim_start();
+    if let Some(quoted) = text.strip_prefix(['\'', '"']) {
+        return (quoted.chars().next().map_or(0, |char| char as i128), None);
+    }
+    if arg.is_empty() {
+        return (0, None);
+    }
+
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (radix, digits) = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(digits) => (16, digits),
+        None if text.starts_with('0') => (8, text),
+        None => (10, text),
+    };
+
+    let length = digits
+        .find(|char: char| !char.is_digit(radix))
+        .unwrap_or(digits.len());
+    let mut value: i128 = 0;
+    let mut error = None;
+
+    for digit in digits[..length]
+        .chars()
+        .filter_map(|char| char.to_digit(radix))
+    {
+        value = value * radix as i128 + digit as i128;
+        if value > u64::MAX as i128 {
+            value = u64::MAX as i128;
+            error = Some(NumberError::OutOfRange);
+        }
+    }
+
+    let value = if negative { -value } else { value };
+    if value > i64::MAX as i128 || value < i64::MIN as i128 {
+        error = Some(NumberError::OutOfRange);
+    }
+    if length == 0 || length < digits.len() {
+        error = Some(NumberError::Invalid);
+    }
+
+    (value, error)
+}
+
+// A float is what the start of the argument is, hex and quoted chars are like integers
+fn parse_float(arg: &str) -> (f64, Option<NumberError>) {
+    let text = arg.trim_start();
+    let is_integer = text.starts_with(['\'', '"'])
+        || text
+            .trim_start_matches(['-', '+'])
+            .starts_with("0x");
+    if is_integer || arg.is_empty() {
+        let (value, error) = parse_integer(arg);
+        return (value as f64, error);
+    }
+
+    let length = (0..=text.len())
+        .rev()
+        .filter(|length| text.is_char_boundary(*length))
+        .find(|length| text[..*length].parse::<f64>().is_ok())
+        .unwrap_or(0);
+    let value = text[..length].parse().unwrap_or(0.0);
+
+    match length == text.len() {
+        true => (value, None),
+        false => (value, Some(NumberError::Invalid)),
+    }
+}
+
+// Quotes the text so the shell reads it back as the same word, like `%q` of bash does.
+// Text with control chars is quoted with `$'...'`.
+// >> quote("it's here")
+// "it\\'s\\ here"
+fn quote(text: &str) -> String {
+    if text.is_empty() {
+        return String::from("''");
+    }
+
+    if text.chars().any(char::is_control) {
+        let mut quoted = String::from("$'");
+        for char in text.chars() {
+            match char {
+                '\n' => quoted.push_str("\\n"),
+                '\t' => quoted.push_str("\\t"),
+                '\r' => quoted.push_str("\\r"),
+                '\x07' => quoted.push_str("\\a"),
+                '\x08' => quoted.push_str("\\b"),
+                '\x0b' => quoted.push_str("\\v"),
+                '\x0c' => quoted.push_str("\\f"),
+                '\x1b' => quoted.push_str("\\E"),
+                '\\' | '\'' => {
+                    quoted.push('\\');
+                    quoted.push(char);
+                }
+                char if char.is_control() => quoted.push_str(&format!("\\{:03o}", char as u32)),
+                char => quoted.push(char),
+            }
+        }
+        quoted.push('\'');
+        return quoted;
+    }
+
+    let mut quoted = String::new();
+    for (i, char) in text.chars().enumerate() {
+        let is_special = " '\"\\|&;()<>!{}*[?]^$`,".contains(char);
+        if is_special || (i == 0 && (char == '~' || char == '#')) {
+            quoted.push('\\');
+        }
+        quoted.push(char);
+    }
+
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printf(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("printf", args);
+
+        run(&cmd, shell_state)
+    }
+
+    fn output(args: &[&str]) -> String {
+        let mut state = ShellState::default();
+        printf(args, &mut state);
+        String::from_utf8_lossy(&state.output.stdout()).to_string()
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(output(&["%s-%s|", "a", "b", "c"]), "a-b|c-|");
+        assert_eq!(
+            output(&["%5s|%-5s|%.2s|%05s|", "ab", "ab", "abc", "ab"]),
+            "   ab|ab   |ab|   ab|"
+        );
+        assert_eq!(
+            output(&["%-*.*s|%*s|", "6", "2", "abcdef", "-3", "a"]),
+            "ab    |a  |"
+        );
+        assert_eq!(
+            output(&["%c%c|%%|a\\101\\x41\\q", "hello", ""]),
+            "h\0|%|aAA\\q"
+        );
+        assert_eq!(output(&["no arguments\\n", "a", "b"]), "no arguments\n");
+    }
+
+    #[test]
+    fn test_escapes_of_arguments() {
+        assert_eq!(output(&["%b|%5b|", "a\\tb\\0101", "a\\n"]), "a\tbA|   a\n|");
+        assert_eq!(output(&["x%bz\\n", "a\\cb", "c"]), "xa");
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(
+            output(&["%d %i %d %d|", "42", "-0x10", "010", "'A"]),
+            "42 -16 8 65|"
+        );
+        assert_eq!(
+            output(&[
+                "%05d|%-5d|%+d|% d|%.3d|%.0d|",
+                "42",
+                "42",
+                "5",
+                "5",
+                "7",
+                "0"
+            ]),
+            "00042|42   |+5| 5|007||"
+        );
+        assert_eq!(
+            output(&["%u %o %x %X", "-1", "8", "255", "255"]),
+            "18446744073709551615 10 ff FF"
+        );
+        assert_eq!(
+            output(&["%#x %#o %#.0o %ld", "255", "8", "0", "3"]),
+            "0xff 010 0 3"
+        );
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(
+            output(&["%5.2f|%f|%.0f", "3.14159", "1e3", "2.5"]),
+            " 3.14|1000.000000|2"
+        );
+        assert_eq!(
+            output(&["%e %+5.1e %E", "12345.678", "3", "1"]),
+            "1.234568e+04 +3.0e+00 1.000000E+00"
+        );
+        assert_eq!(
+            output(&[
+                "%g %g %g %g %g",
+                "0.0001",
+                "0.00001",
+                "123456789",
+                "100000",
+                "0"
+            ]),
+            "0.0001 1e-05 1.23457e+08 100000 0"
+        );
+        assert_eq!(
+            output(&["%#g %G %g %f", "1", "0.000001", "1e100", "inf"]),
+            "1.00000 1E-06 1e+100 inf"
+        );
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(
+            output(&["%q ", "a b", "it's", "", "#a", "a#b", "~", "x=y", "a*b?[c]"]),
+            "a\\ b it\\'s '' \\#a a#b \\~ x=y a\\*b\\?\\[c\\] "
+        );
+        assert_eq!(output(&["%q", "a\nb\x01"]), "$'a\\nb\\001'");
+    }
+
+    #[test]
+    fn test_assign_to_variable() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            printf(&["-v", "out", "%s=%d", "a", "1"], &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            printf(&["-vx", "--", "-%s", "b"], &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(state.parameter("out"), Some(String::from("a=1")));
+        assert_eq!(state.parameter("x"), Some(String::from("-b")));
+        assert!(state.output.chunks().next().is_none());
+    }
+
+    #[test]
+    fn test_invalid_numbers() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            printf(&["%d %d %x|", "abc", "12abc", "3.5"], &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(printf(&["%f|", "x"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(
+            printf(&["%d|", "99999999999999999999"], &mut state),
+            ExitStatus::SUCCESS
+        );
+
+        assert_eq!(
+            state.output.stdout(),
+            b"0 12 3|0.000000|9223372036854775807|"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&state.output.stderr()),
+            "printf: abc: invalid number\nprintf: 12abc: invalid number\n\
+             printf: 3.5: invalid number\nprintf: x: invalid number\n\
+             printf: warning: 99999999999999999999: Numerical result out of range\n"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = ShellState::default();
+
+        assert_eq!(printf(&["a%yb"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(printf(&["%5"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(printf(&["%5%"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(printf(&[], &mut state), ExitStatus::USAGE);
+        assert_eq!(printf(&["-x"], &mut state), ExitStatus::USAGE);
+        assert_eq!(printf(&["-v", "1x", "a"], &mut state), ExitStatus::USAGE);
+
+        assert_eq!(state.output.stdout(), b"a");
+        assert_eq!(
+            String::from_utf8_lossy(&state.output.stderr()),
+            "printf: `y': invalid format character\n\
+             printf: `%5': missing format character\n\
+             printf: `%': invalid format character\n\
+             printf: usage: printf [-v var] format [arguments]\n\
+             printf: -x: invalid option\n\
+             printf: usage: printf [-v var] format [arguments]\n\
+             printf: `1x': not a valid identifier\n"
+        );
+    }
+}
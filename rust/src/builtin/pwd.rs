@@ -0,0 +1,8 @@
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+pub fn run(shell_state: &mut ShellState) -> ExitStatus {
+    let current_dir = format!("{}\n", shell_state.current_dir);
+    shell_state.output.write_stdout(current_dir);
+    ExitStatus::SUCCESS
+}
@@ -0,0 +1,373 @@
+use std::mem;
+use std::time::Duration;
+
+use crate::command::Command;
+use crate::executor;
+use crate::executor::resume::{self, Input, InputRequest};
+use crate::exit_status::{ExitStatus, SIGALRM};
+use crate::shell_state::ShellState;
+use crate::variable::Value;
+
+const USAGE: &str = "read: usage: read [-rs] [-a array] [-p prompt] [-t timeout] [name ...]\n";
+
+#[derive(Debug, PartialEq, Default)]
+struct Options {
+    raw: bool,
+    silent: bool,
+    array: Option<String>,
+    prompt: Option<String>,
+    timeout: Option<String>,
+    names: Vec<String>,
+}
+
+// read [-rs] [-a ARRAY] [-p PROMPT] [-t TIMEOUT] [NAME...]
+// reads a line and splits it on IFS in to the names, the last name gets the rest of the line.
+// Without names the whole line is REPLY, with -a the fields become the elements of ARRAY.
+// Without -r a backslash quotes the next char, at the end of a line of stdin it joins the next.
+// Without stdin the host gives the line: the running line waits for it with the prompt of -p
+// shown, -s and -t tell the host how to read it. See `executor::resume`.
+// >> read -r -p 'Name: ' first last
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let options = match parse_options(&cmd.args) {
+        Ok(options) => options,
+        Err(message) => {
+            shell_state
+                .output
+                .write_stderr(format!("read: {}\n{}", message, USAGE));
+            return ExitStatus::USAGE;
+        }
+    };
+
+    let timeout = match &options.timeout {
+        Some(timeout) => match parse_timeout(timeout) {
+            Some(timeout) => Some(timeout),
+            None => {
+                shell_state.output.write_stderr(format!(
+                    "read: {}: invalid timeout specification\n",
+                    timeout
+                ));
+                return ExitStatus::FAILURE;
+            }
+        },
+        None => None,
+    };
+
+    let mut names = options.names.iter().chain(&options.array);
+    if let Some(name) = names.find(|name| !executor::is_name(name)) {
+        shell_state
+            .output
+            .write_stderr(format!("read: `{}': not a valid identifier\n", name));
+        return ExitStatus::FAILURE;
+    }
+
+    // `-t 0` only tells whether there is input to read
+    if timeout == Some(Duration::ZERO) {
+        let stdin = shell_state.output.stdin.as_ref();
+        return ExitStatus::from(stdin.is_some_and(|stdin| !stdin.is_empty()));
+    }
+
+    let (line, status) = match shell_state.output.stdin.as_mut() {
+        Some(stdin) => read_stdin(stdin, options.raw),
+        None => match shell_state.input.take() {
+            Some(Input::Line(line)) => (line, ExitStatus::SUCCESS),
+            Some(Input::End) => (String::new(), ExitStatus::FAILURE),
+            Some(Input::TimedOut) => (String::new(), ExitStatus::from_signal(SIGALRM)),
+            // the line can't wait here, so there is no input
+            None if shell_state.nested > 0 => (String::new(), ExitStatus::FAILURE),
+            None => {
+                if let Some(prompt) = options.prompt {
+                    shell_state.output.write_stderr(prompt);
+                }
+
+                let request = InputRequest {
+                    silent: options.silent,
+                    timeout,
+                };
+                resume::await_input(request, shell_state);
+                return ExitStatus::SUCCESS;
+            }
+        },
+    };
+
+    assign(&line, &options, shell_state);
+    status
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let flags = match arg.strip_prefix('-') {
+            Some("-") => break,
+            Some(flags) if !flags.is_empty() => flags,
+            _ => {
+                options.names.push(arg.clone());
+                break;
+            }
+        };
+
+        for (i, flag) in flags.char_indices() {
+            match flag {
+                'r' => options.raw = true,
+                's' => options.silent = true,
+                'a' | 'p' | 't' => {
+                    // the value follows the flag, eg: `-pName:`, or is the next argument
+                    let value = match &flags[i + 1..] {
+                        "" => args
+                            .next()
+                            .cloned()
+                            .ok_or_else(|| format!("-{}: option requires an argument", flag))?,
+                        value => String::from(value),
+                    };
+
+                    match flag {
+                        'a' => options.array = Some(value),
+                        'p' => options.prompt = Some(value),
+                        _ => options.timeout = Some(value),
+                    }
+                    break;
+                }
+                flag => return Err(format!("-{}: invalid option", flag)),
+            }
+        }
+    }
+
+    options.names.extend(args.cloned());
+    Ok(options)
+}
+
+// seconds with an optional fraction
+// >> parse_timeout("1.5")
+// Some(Duration::from_millis(1500))
+fn parse_timeout(timeout: &str) -> Option<Duration> {
+    if !timeout.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+
+    timeout.parse::<f64>().ok().map(Duration::from_secs_f64)
+}
+
+// Takes a line from stdin, the rest stays for the next command. A line that ends
+// at the end of stdin instead of with a line break fails, eg: `read` at the end of a file.
+fn read_stdin(stdin: &mut Vec<u8>, raw: bool) -> (String, ExitStatus) {
+    let mut line = Vec::new();
+
+    loop {
+        let end = stdin.iter().position(|byte| *byte == b'\n');
+        let rest = stdin.split_off(end.map_or(stdin.len(), |end| end + 1));
+        let mut taken = mem::replace(stdin, rest);
+
+        if end.is_none() {
+            line.extend(taken);
+            return (
+                String::from_utf8_lossy(&line).to_string(),
+                ExitStatus::FAILURE,
+            );
+        }
+
+        taken.pop();
+        line.extend(taken);
+
+        let backslashes = line.iter().rev().take_while(|byte| **byte == b'\\').count();
+        if raw || backslashes % 2 == 0 {
+            return (
+                String::from_utf8_lossy(&line).to_string(),
+                ExitStatus::SUCCESS,
+            );
+        }
+
+        // the backslash joins the next line
+        line.pop();
+    }
+}
+
+fn assign(line: &str, options: &Options, shell_state: &mut ShellState) {
+    let chars = unescape(line, options.raw);
+    let ifs = shell_state
+        .parameter("IFS")
+        .unwrap_or_else(|| String::from(" \t\n"));
+
+    if let Some(array) = &options.array {
+        let fields = split_fields(&chars, &ifs, usize::MAX);
+        shell_state
+            .variables
+            .insert(array.clone(), Value::indexed(fields));
+        return;
+    }
+
+    // REPLY is the line as it is, not split and without removing whitespace
+    if options.names.is_empty() {
+        let line = chars.iter().map(|(char, _)| char).collect();
+        shell_state.set_variable("REPLY", line);
+        return;
+    }
+
+    let mut fields = split_fields(&chars, &ifs, options.names.len()).into_iter();
+    for name in &options.names {
+        shell_state.set_variable(name, fields.next().unwrap_or_default());
+    }
+}
+
+// The chars of a line and whether they are quoted by a backslash, the backslashes
+// are removed. With -r a backslash is an ordinary char.
+fn unescape(line: &str, raw: bool) -> Vec<(char, bool)> {
+    let mut result = Vec::new();
+    let mut chars = line.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' if !raw => result.extend(chars.next().map(|next| (next, true))),
+            char => result.push((char, false)),
+        }
+    }
+
+    result
+}
+
+// Splits the chars in to at most `count` fields. Whitespace of IFS around the fields
+// is removed, other chars of IFS separate the fields one at a time. The last field
+// is the rest of the line.
+// >> split_fields("a  b:c d", " :", 2)
+// vec!["a", "b:c d"]
+fn split_fields(chars: &[(char, bool)], ifs: &str, count: usize) -> Vec<String> {
+    let is_space =
+        |(char, quoted): (char, bool)| !quoted && char.is_whitespace() && ifs.contains(char);
+    let is_delimiter =
+        |(char, quoted): (char, bool)| !quoted && !char.is_whitespace() && ifs.contains(char);
+    let skip_spaces = |mut i: usize| {
+        while i < chars.len() && is_space(chars[i]) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut fields = Vec::new();
+    let mut i = skip_spaces(0);
+
+    while i < chars.len() {
+        if fields.len() + 1 == count {
+            let mut end = chars.len();
+            while end > i && is_space(chars[end - 1]) {
+                end -= 1;
+            }
+
+            fields.push(chars[i..end].iter().map(|(char, _)| char).collect());
+            break;
+        }
+
+        let start = i;
+        while i < chars.len() && !is_space(chars[i]) && !is_delimiter(chars[i]) {
+            i += 1;
+        }
+        fields.push(chars[start..i].iter().map(|(char, _)| char).collect());
+
+        i = skip_spaces(i);
+        if i < chars.len() && is_delimiter(chars[i]) {
+            i = skip_spaces(i + 1);
+        }
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("read", args);
+
+        run(&cmd, shell_state)
+    }
+
+    fn variable(name: &str, shell_state: &ShellState) -> String {
+        shell_state.parameter(name).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_read_stdin() {
+        let mut state = ShellState::default();
+        state.output.stdin = Some(b"  one  two three \nsecond\\\n line\nlast".to_vec());
+
+        assert_eq!(read(&["a", "b"], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(variable("a", &state), "one");
+        assert_eq!(variable("b", &state), "two three");
+
+        assert_eq!(read(&[], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(variable("REPLY", &state), "second line");
+
+        assert_eq!(read(&["a", "b"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(variable("a", &state), "last");
+        assert_eq!(variable("b", &state), "");
+        assert_eq!(read(&["-t", "0"], &mut state), ExitStatus::FAILURE);
+    }
+
+    #[test]
+    fn test_escapes_and_ifs() {
+        let mut state = ShellState::default();
+        state.set_variable("IFS", String::from(" :"));
+        state.output.stdin = Some(b"a\\ b:c::d \\\\\na\\ b:c\n".to_vec());
+
+        read(&["-a", "fields"], &mut state);
+        assert_eq!(
+            state.variables["fields"].values(),
+            vec!["a b", "c", "", "d", "\\"]
+        );
+
+        read(&["-r", "x", "y"], &mut state);
+        assert_eq!(variable("x", &state), "a\\");
+        assert_eq!(variable("y", &state), "b:c");
+    }
+
+    #[test]
+    fn test_input_of_the_host() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            read(&["-s", "-t1.5", "-pName: ", "name"], &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(state.output.stderr(), b"Name: ");
+        assert_eq!(
+            state.input_request(),
+            Some(&InputRequest {
+                silent: true,
+                timeout: Some(Duration::from_millis(1500)),
+            })
+        );
+
+        state.suspension = None;
+        state.input = Some(Input::Line(String::from("Ada")));
+        assert_eq!(read(&["name"], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(variable("name", &state), "Ada");
+
+        state.input = Some(Input::TimedOut);
+        assert_eq!(read(&["name"], &mut state).code(), 142);
+        assert_eq!(variable("name", &state), "");
+
+        state.nested = 1;
+        assert_eq!(read(&["name"], &mut state), ExitStatus::FAILURE);
+        assert!(state.suspension.is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = ShellState::default();
+
+        assert_eq!(read(&["-x"], &mut state), ExitStatus::USAGE);
+        assert_eq!(read(&["-p"], &mut state), ExitStatus::USAGE);
+        assert_eq!(read(&["-t", "-1"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(read(&["-a", "1x"], &mut state), ExitStatus::FAILURE);
+
+        assert_eq!(
+            String::from_utf8_lossy(&state.output.stderr()),
+            "read: -x: invalid option\n\
+             read: usage: read [-rs] [-a array] [-p prompt] [-t timeout] [name ...]\n\
+             read: -p: option requires an argument\n\
+             read: usage: read [-rs] [-a array] [-p prompt] [-t timeout] [name ...]\n\
+             read: -1: invalid timeout specification\n\
+             read: `1x': not a valid identifier\n"
+        );
+    }
+}
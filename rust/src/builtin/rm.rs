@@ -0,0 +1,355 @@
+use std::io;
+use std::path::Path;
+
+use crate::builtin::parse_flags;
+use crate::command::Command;
+use crate::executor::resume::{self, Input, InputRequest};
+use crate::exit_status::ExitStatus;
+use crate::filesystem::{self, FileType};
+use crate::shell_state::ShellState;
+
+enum Answer {
+    Yes,
+    No,
+    // the line waits for the host to answer, see `ask`
+    Waiting,
+}
+
+// rm [-rRfid] FILE...
+// removes files, with -r directories and everything in them and with -d empty directories.
+// -f ignores missing files. -i asks before removing every file, the answer is a line of
+// stdin or one the host gives, see `executor::resume`.
+// >> rm -rf build
+pub fn rm(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (flags, operands) = match parse_flags(&cmd.args, "rRfid") {
+        Ok(parsed) => parsed,
+        Err(message) => return fail("rm", &message, shell_state),
+    };
+    if operands.is_empty() && !flags.contains('f') {
+        return fail("rm", "missing operand", shell_state);
+    }
+
+    let recursive = flags.contains('r') || flags.contains('R');
+    // the last of -f and -i wins
+    let interactive = flags.rfind('i') > flags.rfind('f');
+    let mut status = ExitStatus::SUCCESS;
+
+    for (i, operand) in operands.iter().enumerate() {
+        let path = shell_state.resolve_path(operand);
+        let name = operand.trim_end_matches('/').rsplit('/').next();
+
+        if name == Some(".") || name == Some("..") {
+            shell_state.output.write_stderr(format!(
+                "rm: refusing to remove '.' or '..' directory: skipping '{}'\n",
+                operand
+            ));
+            status = ExitStatus::FAILURE;
+            continue;
+        }
+        if recursive && path.parent().is_none() {
+            shell_state.output.write_stderr(format!(
+                "rm: it is dangerous to operate recursively on '{}'\n",
+                operand
+            ));
+            status = ExitStatus::FAILURE;
+            continue;
+        }
+
+        let metadata = match shell_state.fs.symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(_) if flags.contains('f') => continue,
+            Err(error) => {
+                write_error("rm: cannot remove", operand, &error, shell_state);
+                status = ExitStatus::FAILURE;
+                continue;
+            }
+        };
+
+        let is_directory = metadata.file_type == FileType::Directory;
+        if is_directory && !recursive && !flags.contains('d') {
+            let error = io::ErrorKind::IsADirectory.into();
+            write_error("rm: cannot remove", operand, &error, shell_state);
+            status = ExitStatus::FAILURE;
+            continue;
+        }
+
+        if interactive {
+            let kind = match metadata.file_type {
+                FileType::Directory => "directory",
+                FileType::File if metadata.len == 0 => "regular empty file",
+                FileType::File => "regular file",
+                FileType::Symlink => "symbolic link",
+            };
+            let question = format!("rm: remove {} '{}'? ", kind, operand);
+
+            // once the host answers, rm runs again for the files that are left
+            let retry = || Command {
+                keyword: cmd.keyword.clone(),
+                args: std::iter::once(format!("-{}", flags))
+                    .chain(std::iter::once(String::from("--")))
+                    .chain(operands[i..].iter().cloned())
+                    .collect(),
+            };
+
+            match ask(question, retry, shell_state) {
+                Answer::Yes => (),
+                Answer::No => continue,
+                Answer::Waiting => return status,
+            }
+        }
+
+        if !remove(&path, operand, recursive, shell_state) {
+            status = ExitStatus::FAILURE;
+        }
+    }
+
+    status
+}
+
+// rmdir [-p] DIRECTORY...
+// removes empty directories, -p also the parents in the path that become empty
+// >> rmdir -p a/b/c
+pub fn rmdir(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (flags, operands) = match parse_flags(&cmd.args, "p") {
+        Ok(parsed) => parsed,
+        Err(message) => return fail("rmdir", &message, shell_state),
+    };
+    if operands.is_empty() {
+        return fail("rmdir", "missing operand", shell_state);
+    }
+
+    let mut status = ExitStatus::SUCCESS;
+    for operand in &operands {
+        let mut directory = operand.trim_end_matches('/');
+
+        loop {
+            let path = shell_state.resolve_path(directory);
+            if let Err(error) = shell_state.fs.remove_dir(&path) {
+                write_error("rmdir: failed to remove", directory, &error, shell_state);
+                status = ExitStatus::FAILURE;
+                break;
+            }
+
+            match directory.rsplit_once('/') {
+                Some((parent, _)) if flags.contains('p') && !parent.is_empty() => {
+                    directory = parent.trim_end_matches('/')
+                }
+                _ => break,
+            }
+        }
+    }
+
+    status
+}
+
+// Removes the file or directory, returns whether everything in it is removed
+fn remove(path: &Path, operand: &str, recursive: bool, shell_state: &mut ShellState) -> bool {
+    // a link to a directory is removed itself, not what is in the directory
+    let is_directory = matches!(
+        shell_state.fs.symlink_metadata(path),
+        Ok(metadata) if metadata.file_type == FileType::Directory
+    );
+    let mut removed = true;
+
+    if is_directory && recursive {
+        for name in shell_state.fs.read_dir(path).unwrap_or_default() {
+            let child = format!("{}/{}", operand.trim_end_matches('/'), name);
+            removed &= remove(&path.join(&name), &child, recursive, shell_state);
+        }
+    }
+
+    let result = match is_directory {
+        true => shell_state.fs.remove_dir(path),
+        false => shell_state.fs.remove_file(path),
+    };
+    // the error of a directory that isn't empty is already told about its files
+    match result {
+        Ok(()) => removed,
+        Err(_) if !removed => false,
+        Err(error) => {
+            write_error("rm: cannot remove", operand, &error, shell_state);
+            false
+        }
+    }
+}
+
+// Asks a yes or no question. The answer is the next line of stdin, otherwise the host
+// answers: the line waits for it and then the command runs again as `retry`, which
+// starts at the file that is asked about.
+fn ask(question: String, retry: impl FnOnce() -> Command, shell_state: &mut ShellState) -> Answer {
+    let answer = match shell_state.output.stdin.as_mut() {
+        Some(stdin) => {
+            let end = stdin.iter().position(|byte| *byte == b'\n');
+            let rest = stdin.split_off(end.map_or(stdin.len(), |end| end + 1));
+            let line = std::mem::replace(stdin, rest);
+            shell_state.output.write_stderr(question);
+            String::from_utf8_lossy(&line).to_string()
+        }
+        None => match shell_state.input.take() {
+            // the question was asked before the line waited
+            Some(Input::Line(line)) => line,
+            Some(_) => String::new(),
+            None => {
+                shell_state.output.write_stderr(question);
+                // without a host that can answer there is no answer
+                if shell_state.nested > 0 {
+                    return Answer::No;
+                }
+
+                let request = InputRequest {
+                    silent: false,
+                    timeout: None,
+                };
+                resume::await_input_retrying(request, retry(), shell_state);
+                return Answer::Waiting;
+            }
+        },
+    };
+
+    match answer.trim_start().starts_with(['y', 'Y']) {
+        true => Answer::Yes,
+        false => Answer::No,
+    }
+}
+
+// >> write_error("rm: cannot remove", "a", NotFound)
+// "rm: cannot remove 'a': No such file or directory"
+fn write_error(message: &str, path: &str, error: &io::Error, shell_state: &mut ShellState) {
+    shell_state.output.write_stderr(format!(
+        "{} '{}': {}\n",
+        message,
+        path,
+        filesystem::describe_error(error)
+    ));
+}
+
+fn fail(name: &str, message: &str, shell_state: &mut ShellState) -> ExitStatus {
+    shell_state
+        .output
+        .write_stderr(format!("{}: {}\n", name, message));
+    ExitStatus::FAILURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> ShellState {
+        let state = ShellState::default();
+        state.fs.create_dir(Path::new("/dir")).unwrap();
+        state.fs.create_dir(Path::new("/dir/sub")).unwrap();
+        state.fs.write(Path::new("/dir/sub/file"), b"x").unwrap();
+        state.fs.create_dir(Path::new("/empty")).unwrap();
+        state.fs.write(Path::new("/a"), b"a").unwrap();
+        state.fs.write(Path::new("/b"), b"").unwrap();
+        state
+    }
+
+    #[test]
+    fn test_rm() {
+        let mut state = state();
+
+        assert_eq!(
+            rm(&Command::new("rm", &["a", "-d", "empty"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            rm(&Command::new("rm", &["-r", "dir/"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            rm(&Command::new("rm", &["-f", "missing"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(state.fs.read_dir(Path::new("/")).unwrap(), vec!["b"]);
+    }
+
+    #[test]
+    fn test_rm_errors() {
+        let mut state = state();
+
+        assert_eq!(
+            rm(&Command::new("rm", &["missing", "dir", "a"]), &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(
+            rm(&Command::new("rm", &["-rf", ".", "dir/..", "/"]), &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(rm(&Command::new("rm", &[]), &mut state), ExitStatus::FAILURE);
+        assert_eq!(rm(&Command::new("rm", &["-f"]), &mut state), ExitStatus::SUCCESS);
+
+        assert!(!state.fs.exists(Path::new("/a")));
+        assert_eq!(
+            String::from_utf8_lossy(&state.output.stderr()),
+            "rm: cannot remove 'missing': No such file or directory\n\
+             rm: cannot remove 'dir': Is a directory\n\
+             rm: refusing to remove '.' or '..' directory: skipping '.'\n\
+             rm: refusing to remove '.' or '..' directory: skipping 'dir/..'\n\
+             rm: it is dangerous to operate recursively on '/'\n\
+             rm: missing operand\n"
+        );
+    }
+
+    #[test]
+    fn test_interactive_with_stdin() {
+        let mut state = state();
+        state.output.stdin = Some(b"y\nn\nyes\n".to_vec());
+
+        let cmd = Command::new("rm", &["-ri", "a", "b", "dir"]);
+        assert_eq!(rm(&cmd, &mut state), ExitStatus::SUCCESS);
+
+        assert_eq!(
+            state.fs.read_dir(Path::new("/")).unwrap(),
+            vec!["b", "empty"]
+        );
+        assert_eq!(
+            state.output.stderr(),
+            b"rm: remove regular file 'a'? rm: remove regular empty file 'b'? \
+              rm: remove directory 'dir'? "
+        );
+    }
+
+    #[test]
+    fn test_interactive_with_the_host() {
+        let mut state = state();
+
+        rm(&Command::new("rm", &["-i", "a", "b"]), &mut state);
+        assert!(state.input_request().is_some());
+        assert_eq!(state.output.stderr(), b"rm: remove regular file 'a'? ");
+
+        state.suspension = None;
+        state.input = Some(Input::Line(String::from("y")));
+        rm(&Command::new("rm", &["-i", "--", "a", "b"]), &mut state);
+        assert!(!state.fs.exists(Path::new("/a")));
+        assert!(state.input_request().is_some());
+    }
+
+    #[test]
+    fn test_rmdir() {
+        let mut state = state();
+        state.fs.create_dir(Path::new("/empty/inner")).unwrap();
+
+        assert_eq!(
+            rmdir(&Command::new("rmdir", &["-p", "empty/inner/"]), &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(
+            rmdir(&Command::new("rmdir", &["dir", "a", "missing"]), &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(
+            rmdir(&Command::new("rmdir", &["-p", "dir/sub"]), &mut state),
+            ExitStatus::FAILURE
+        );
+
+        assert!(!state.fs.exists(Path::new("/empty")));
+        assert_eq!(
+            String::from_utf8_lossy(&state.output.stderr()),
+            "rmdir: failed to remove 'dir': Directory not empty\n\
+             rmdir: failed to remove 'a': Not a directory\n\
+             rmdir: failed to remove 'missing': No such file or directory\n\
+             rmdir: failed to remove 'dir/sub': Directory not empty\n"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::options::SetOptions;
+use crate::parser::word;
+use crate::shell_state::ShellState;
+use crate::variable::Value;
+
+const USAGE: &str = "set: usage: set [-efuxC] [-o option-name] [--] [arg ...]\n";
+
+// set [-efuxC] [+efuxC] [-o NAME] [+o NAME] [--] [ARG...]
+// `-` turns options on and `+` off. The words after the options become the positional
+// parameters, `--` ends the options so they can start with a "-". Without arguments
+// the variables are listed, `-o` alone lists the options.
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    if cmd.args.is_empty() {
+        let variables: String = shell_state
+            .variables
+            .iter()
+            .map(|(name, value)| match value {
+                Value::String(value) => format!("{}={}\n", name, word::quote(value)),
+                array => format!("{}={}\n", name, array.format()),
+            })
+            .collect();
+        shell_state.output.write_stdout(variables);
+
+        return ExitStatus::SUCCESS;
+    }
+
+    let mut args = cmd.args.iter();
+    let mut positional_parameters = None;
+
+    while let Some(arg) = args.next() {
+        let (value, flags) = match (arg.strip_prefix('-'), arg.strip_prefix('+')) {
+            _ if arg == "--" => {
+                positional_parameters = Some(args.by_ref().cloned().collect());
+                break;
+            }
+            (Some(flags), _) if !flags.is_empty() => (true, flags),
+            (_, Some(flags)) if !flags.is_empty() => (false, flags),
+            _ => {
+                positional_parameters =
+                    Some(std::iter::once(arg).chain(args.by_ref()).cloned().collect());
+                break;
+            }
+        };
+
+        if flags == "o" {
+            match args.next() {
+                Some(name) => {
+                    if shell_state.options.set(name, value).is_none() {
+                        shell_state
+                            .output
+                            .write_stderr(format!("set: {}: invalid option name\n", name));
+                        return ExitStatus::FAILURE;
+                    }
+                }
+                None => list_options(value, shell_state),
+            }
+
+            continue;
+        }
+
+        for flag in flags.chars() {
+            match SetOptions::name_of_flag(flag) {
+                Some(name) => {
+                    shell_state.options.set(name, value);
+                }
+                None => {
+                    let sign = if value { '-' } else { '+' };
+                    shell_state
+                        .output
+                        .write_stderr(format!("set: {}{}: invalid option\n{}", sign, flag, USAGE));
+                    return ExitStatus::USAGE;
+                }
+            }
+        }
+    }
+
+    if let Some(positional_parameters) = positional_parameters {
+        shell_state.positional_parameters = positional_parameters;
+    }
+
+    ExitStatus::SUCCESS
+}
+
+// `set -o` shows the state of the options, `set +o` the commands that restore it
+fn list_options(value: bool, shell_state: &mut ShellState) {
+    for name in SetOptions::NAMES {
+        let on = shell_state.options.get(name).unwrap_or_default();
+
+        let line = match value {
+            true => format!("{:<15}\t{}\n", name, if on { "on" } else { "off" }),
+            false => format!("set {}o {}\n", if on { '-' } else { '+' }, name),
+        };
+        shell_state.output.write_stdout(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("set", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_flags() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            set(&["-eu", "-o", "pipefail", "-x"], &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert!(state.options.errexit && state.options.nounset && state.options.xtrace);
+        assert!(state.options.pipefail);
+
+        set(&["+x", "+o", "errexit", "-C"], &mut state);
+        assert!(!state.options.xtrace && !state.options.errexit);
+        assert!(state.options.noclobber);
+        assert!(state.positional_parameters.is_empty());
+    }
+
+    #[test]
+    fn test_list_options() {
+        let mut state = ShellState::default();
+        state.options.noglob = true;
+
+        set(&["-o"], &mut state);
+        set(&["+o"], &mut state);
+
+        let expected = "errexit        \toff\nnoclobber      \toff\nnoglob         \ton\n\
+                        nounset        \toff\npipefail       \toff\nxtrace         \toff\n\
+                        set +o errexit\nset +o noclobber\nset -o noglob\nset +o nounset\n\
+                        set +o pipefail\nset +o xtrace\n";
+        assert_eq!(String::from_utf8_lossy(&state.output.stdout()), expected);
+    }
+
+    #[test]
+    fn test_positional_parameters() {
+        let mut state = ShellState::default();
+
+        set(&["-f", "a", "-b"], &mut state);
+        assert!(state.options.noglob);
+        assert_eq!(state.positional_parameters, vec!["a", "-b"]);
+
+        set(&["--", "-x"], &mut state);
+        assert_eq!(state.positional_parameters, vec!["-x"]);
+        assert!(!state.options.xtrace);
+
+        set(&["--"], &mut state);
+        assert!(state.positional_parameters.is_empty());
+    }
+
+    #[test]
+    fn test_list_variables() {
+        let mut state = ShellState::default();
+        state
+            .set_variable("A", String::from("it's"));
+        state.set_variable("B", String::from("b"));
+
+        set(&[], &mut state);
+
+        assert_eq!(state.output.stdout(), b"A='it'\\''s'\nB=b\nOPTIND=1\n");
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let mut state = ShellState::default();
+
+        assert_eq!(set(&["-z"], &mut state), ExitStatus::USAGE);
+        assert_eq!(set(&["-o", "vi"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(
+            state.output.stderr(),
+            b"set: -z: invalid option\nset: usage: set [-efuxC] [-o option-name] [--] [arg ...]\n\
+              set: vi: invalid option name\n"
+        );
+    }
+}
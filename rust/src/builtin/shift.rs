@@ -0,0 +1,94 @@
+use std::convert::TryFrom;
+
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+// shift [N]
+// drops the first N positional parameters, 1 by default, so `$N+1` becomes `$1`.
+// Nothing is dropped when there are less than N of them.
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let count = match cmd.args.as_slice() {
+        [] => 1,
+        [count] => match count.parse::<i64>() {
+            Ok(count) => count,
+            Err(_) => {
+                shell_state
+                    .output
+                    .write_stderr(format!("shift: {}: numeric argument required\n", count));
+                return ExitStatus::FAILURE;
+            }
+        },
+        _ => {
+            shell_state
+                .output
+                .write_stderr("shift: too many arguments\n");
+            return ExitStatus::FAILURE;
+        }
+    };
+
+    let parameters = &mut shell_state.positional_parameters;
+    match usize::try_from(count) {
+        Ok(count) if count <= parameters.len() => {
+            parameters.drain(..count);
+            ExitStatus::SUCCESS
+        }
+        _ => {
+            shell_state
+                .output
+                .write_stderr(format!("shift: {}: shift count out of range\n", count));
+            ExitStatus::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shift(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("shift", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut state = ShellState {
+            positional_parameters: ["a", "b", "c", "d"]
+                .iter()
+                .map(|p| String::from(*p))
+                .collect(),
+            ..ShellState::default()
+        };
+
+        assert_eq!(shift(&[], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(state.positional_parameters, vec!["b", "c", "d"]);
+
+        assert_eq!(shift(&["2"], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(state.positional_parameters, vec!["d"]);
+
+        assert_eq!(shift(&["0"], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(shift(&["1"], &mut state), ExitStatus::SUCCESS);
+        assert!(state.positional_parameters.is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = ShellState {
+            positional_parameters: vec![String::from("a")],
+            ..ShellState::default()
+        };
+
+        assert_eq!(shift(&["2"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(shift(&["-1"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(shift(&["x"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(shift(&["1", "2"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(state.positional_parameters, vec!["a"]);
+        assert_eq!(
+            state.output.stderr(),
+            b"shift: 2: shift count out of range\nshift: -1: shift count out of range\n\
+              shift: x: numeric argument required\nshift: too many arguments\n"
+        );
+    }
+}
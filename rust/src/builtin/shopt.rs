@@ -0,0 +1,143 @@
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::options::{SetOptions, ShoptOptions};
+use crate::shell_state::ShellState;
+
+// shopt [-s | -u] [-q] [-o] [NAME]...
+// without -s or -u the state of the options is printed, the status tells if all of them are on.
+// -o uses the options of `set`.
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let mut value = None;
+    let mut quiet = false;
+    let mut set_options = false;
+    let mut names = Vec::new();
+
+    for arg in &cmd.args {
+        match arg.as_str() {
+            "-s" => value = Some(true),
+            "-u" => value = Some(false),
+            "-q" => quiet = true,
+            "-o" => set_options = true,
+            arg if arg.starts_with('-') => {
+                shell_state
+                    .output
+                    .write_stderr(format!("shopt: {}: invalid option\n", arg));
+                return ExitStatus::USAGE;
+            }
+            name => names.push(name),
+        }
+    }
+
+    let get = |shell_state: &ShellState, name: &str| match set_options {
+        true => shell_state.options.get(name),
+        false => shell_state.shopt.get(name),
+    };
+
+    if let Some(name) = names.iter().find(|name| get(shell_state, name).is_none()) {
+        shell_state
+            .output
+            .write_stderr(format!("shopt: {}: invalid shell option name\n", name));
+        return ExitStatus::FAILURE;
+    }
+
+    if let Some(value) = value {
+        for name in names {
+            match set_options {
+                true => shell_state.options.set(name, value),
+                false => shell_state.shopt.set(name, value),
+            };
+        }
+
+        return ExitStatus::SUCCESS;
+    }
+
+    if names.is_empty() {
+        names = match set_options {
+            true => SetOptions::NAMES.to_vec(),
+            false => ShoptOptions::NAMES.to_vec(),
+        };
+    }
+
+    let mut all_on = true;
+    for name in names {
+        let on = get(shell_state, name).unwrap_or_default();
+        all_on &= on;
+
+        if !quiet {
+            let state = if on { "on" } else { "off" };
+            shell_state
+                .output
+                .write_stdout(format!("{:<15}\t{}\n", name, state));
+        }
+    }
+
+    ExitStatus::from(all_on)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shopt(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("shopt", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_set_and_unset() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            shopt(&["-s", "nullglob", "dotglob"], &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert!(state.shopt.nullglob);
+        assert!(state.shopt.dotglob);
+
+        shopt(&["-u", "nullglob"], &mut state);
+        assert!(!state.shopt.nullglob);
+    }
+
+    #[test]
+    fn test_query() {
+        let mut state = ShellState::default();
+        state.shopt.globstar = true;
+
+        assert_eq!(shopt(&["-q", "globstar"], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(
+            shopt(&["-q", "globstar", "failglob"], &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(state.output.stdout(), b"");
+
+        shopt(&["globstar"], &mut state);
+        assert_eq!(state.output.stdout(), b"globstar       \ton\n");
+    }
+
+    #[test]
+    fn test_set_options() {
+        let mut state = ShellState::default();
+
+        shopt(&["-s", "-o", "pipefail"], &mut state);
+        assert!(state.options.pipefail);
+        assert_eq!(shopt(&["-s", "pipefail"], &mut state), ExitStatus::FAILURE);
+
+        shopt(&["-o", "pipefail", "xtrace"], &mut state);
+        assert_eq!(
+            state.output.stdout(),
+            b"pipefail       \ton\nxtrace         \toff\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_name() {
+        let mut state = ShellState::default();
+
+        assert_eq!(shopt(&["-s", "unknown"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(
+            state.output.stderr(),
+            b"shopt: unknown: invalid shell option name\n"
+        );
+    }
+}
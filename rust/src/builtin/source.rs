@@ -0,0 +1,141 @@
+use std::mem;
+
+use crate::builtin::write_file_error;
+use crate::command::Command;
+use crate::exception::Exception;
+use crate::executor;
+use crate::executor::resume::{self, Frame};
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+// source FILE [ARGUMENTS...] or . FILE [ARGUMENTS...]
+// runs the lines of the file in the current shell, so variables, aliases and the directory
+// it changes stay changed. The arguments are the positional parameters while it runs.
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let name = cmd.keyword.as_str();
+    let path = match cmd.args.first() {
+        Some(path) => path,
+        None => {
+            shell_state.output.write_stderr(format!(
+                "{0}: filename argument required\n{0}: usage: {0} filename [arguments]\n",
+                name
+            ));
+            return ExitStatus::USAGE;
+        }
+    };
+
+    let script = match shell_state.fs.read(&shell_state.resolve_path(path)) {
+        Ok(script) => String::from_utf8_lossy(&script).to_string(),
+        Err(error) => {
+            write_file_error(name, path, &error, shell_state);
+            return ExitStatus::FAILURE;
+        }
+    };
+
+    // without arguments the script sees the positional parameters of the caller
+    let arguments = cmd.args[1..].to_vec();
+    let saved = match arguments.is_empty() {
+        true => None,
+        false => Some(mem::replace(&mut shell_state.positional_parameters, arguments)),
+    };
+
+    // an empty file succeeds, when the script waits for input the line that sources it waits
+    shell_state.output.status = ExitStatus::SUCCESS;
+    let result = executor::run_script(&script, shell_state);
+
+    let frame = || Frame::Sourced {
+        name: String::from(name),
+        path: path.clone(),
+        saved: saved.clone(),
+    };
+    if resume::defer_lines(shell_state, || Some(frame())) {
+        return ExitStatus::SUCCESS;
+    }
+
+    finish(name, path, result, saved, shell_state)
+}
+
+// What's left once the script ran, also when it waited for input before
+pub fn finish(
+    name: &str,
+    path: &str,
+    result: Result<(), Exception>,
+    saved: Option<Vec<String>>,
+    shell_state: &mut ShellState,
+) -> ExitStatus {
+    let status = match result {
+        Ok(()) => shell_state.output.status,
+        Err(exception) => {
+            shell_state
+                .output
+                .write_stderr(format!("{}: {}: {}\n", name, path, exception));
+            ExitStatus::USAGE
+        }
+    };
+
+    // the RETURN trap runs while the arguments of the script are still set
+    shell_state.output.status = status;
+    executor::trap::run_trap("RETURN", shell_state);
+
+    if let Some(saved) = saved {
+        shell_state.positional_parameters = saved;
+    }
+
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn source(keyword: &str, args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new(keyword, args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_runs_in_the_current_shell() {
+        let mut state = ShellState::default();
+        let script = "A=1\nalias say='echo said'\nsay $A\nfalse";
+        state.fs.write(Path::new("/rc"), script.as_bytes()).unwrap();
+
+        let status = source(".", &["rc"], &mut state);
+
+        assert_eq!(state.output.stdout(), b"said 1\n");
+        assert_eq!(state.parameter("A"), Some(String::from("1")));
+        assert!(state.aliases.contains_key("say"));
+        assert_eq!(status, ExitStatus::FAILURE);
+    }
+
+    #[test]
+    fn test_arguments() {
+        let mut state = ShellState {
+            positional_parameters: vec![String::from("outer")],
+            ..ShellState::default()
+        };
+        state.fs.write(Path::new("/args"), b"echo $# $1 $2 \"$@\"").unwrap();
+
+        source("source", &["/args", "a", "b c"], &mut state);
+        source("source", &["/args"], &mut state);
+
+        assert_eq!(state.output.stdout(), b"2 a b c a b c\n1 outer outer\n");
+        assert_eq!(state.positional_parameters, vec![String::from("outer")]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = ShellState::default();
+        state.fs.write(Path::new("/empty"), b"").unwrap();
+
+        assert_eq!(source("source", &["missing"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(source(".", &[], &mut state), ExitStatus::USAGE);
+        assert_eq!(source("source", &["empty"], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(
+            state.output.stderr(),
+            b"source: missing: No such file or directory\n\
+              .: filename argument required\n.: usage: . filename [arguments]\n"
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::filesystem::FileType;
+use crate::parser::condition::{is_binary_operator, is_unary_operator};
+use crate::shell_state::ShellState;
+
+// test EXPRESSION
+// [ EXPRESSION ]
+// exits with 0 when the expression is true, 1 when it's false and 2 on an error
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let args = match (cmd.keyword.as_str(), cmd.args.split_last()) {
+        ("[", Some((last, args))) if last == "]" => args,
+        ("[", _) => return usage_error(&cmd.keyword, "missing `]'", shell_state),
+        _ => &cmd.args[..],
+    };
+
+    match evaluate(args, shell_state) {
+        Ok(result) => ExitStatus::from(result),
+        Err(message) => usage_error(&cmd.keyword, &message, shell_state),
+    }
+}
+
+fn usage_error(name: &str, message: &str, shell_state: &mut ShellState) -> ExitStatus {
+    shell_state
+        .output
+        .write_stderr(format!("{}: {}\n", name, message));
+    ExitStatus::USAGE
+}
+
+// Up to three arguments the amount of arguments decides what they mean,
+// eg: `[ -n ]` tests whether "-n" is not empty
+fn evaluate(args: &[String], shell_state: &ShellState) -> Result<bool, String> {
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+
+    match args.as_slice() {
+        [] => Ok(false),
+        [arg] => Ok(!arg.is_empty()),
+        ["!", arg] => Ok(arg.is_empty()),
+        [op, operand] if is_unary_operator(op) => Ok(unary_test(op, operand, shell_state)),
+        [op, _] => Err(format!("{}: unary operator expected", op)),
+        [left, op, right] if is_binary_operator(op) && *op != "=~" => binary_test(left, op, right),
+        [left, "-a", right] => Ok(!left.is_empty() && !right.is_empty()),
+        [left, "-o", right] => Ok(!left.is_empty() || !right.is_empty()),
+        ["!", rest @ ..] if args.len() == 3 => Ok(!evaluate_words(rest, shell_state)?),
+        ["(", arg, ")"] => Ok(!arg.is_empty()),
+        [_, op, _] => Err(format!("{}: binary operator expected", op)),
+        _ => {
+            let mut parser = Parser {
+                args: &args,
+                position: 0,
+                shell_state,
+            };
+            let result = parser.or()?;
+
+            match parser.args.get(parser.position) {
+                Some(_) => Err(String::from("too many arguments")),
+                None => Ok(result),
+            }
+        }
+    }
+}
+
+fn evaluate_words(args: &[&str], shell_state: &ShellState) -> Result<bool, String> {
+    let args: Vec<String> = args.iter().map(|arg| String::from(*arg)).collect();
+    evaluate(&args, shell_state)
+}
+
+// `-o` binds weaker than `-a`, which binds weaker than `!`
+struct Parser<'a> {
+    args: &'a [&'a str],
+    position: usize,
+    shell_state: &'a ShellState,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Result<&'a str, String> {
+        let arg = self
+            .args
+            .get(self.position)
+            .ok_or_else(|| String::from("argument expected"))?;
+        self.position += 1;
+        Ok(arg)
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.args.get(self.position).copied()
+    }
+
+    fn or(&mut self) -> Result<bool, String> {
+        let mut result = self.and()?;
+
+        while self.peek() == Some("-o") {
+            self.position += 1;
+            let right = self.and()?;
+            result = result || right;
+        }
+
+        Ok(result)
+    }
+
+    fn and(&mut self) -> Result<bool, String> {
+        let mut result = self.not()?;
+
+        while self.peek() == Some("-a") {
+            self.position += 1;
+            let right = self.not()?;
+            result = result && right;
+        }
+
+        Ok(result)
+    }
+
+    fn not(&mut self) -> Result<bool, String> {
+        match self.peek() {
+            Some("!") => {
+                self.position += 1;
+                Ok(!self.not()?)
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<bool, String> {
+        let arg = self.next()?;
+
+        if arg == "(" {
+            let result = self.or()?;
+            return match self.next() {
+                Ok(")") => Ok(result),
+                _ => Err(String::from("`)' expected")),
+            };
+        }
+
+        if let Some(op) = self.peek().filter(|op| is_binary_operator(op)) {
+            self.position += 1;
+            let right = self.next()?;
+            return binary_test(arg, op, right);
+        }
+
+        if is_unary_operator(arg) {
+            let operand = self.next()?;
+            return Ok(unary_test(arg, operand, self.shell_state));
+        }
+
+        Ok(!arg.is_empty())
+    }
+}
+
+// Tests with one operand, files are looked up in the filesystem of the shell.
+// Files can't be executed, so only directories pass `-x`.
+pub fn unary_test(op: &str, operand: &str, shell_state: &ShellState) -> bool {
+    let metadata = || shell_state.fs.metadata(&shell_state.resolve_path(operand));
+
+    match op {
+        "-z" => operand.is_empty(),
+        "-n" => !operand.is_empty(),
+        "-v" => shell_state.parameter(operand).is_some(),
+        "-e" | "-a" | "-r" | "-w" => metadata().is_ok(),
+        "-f" => matches!(metadata(), Ok(metadata) if metadata.file_type == FileType::File),
+        "-d" | "-x" => {
+            matches!(metadata(), Ok(metadata) if metadata.file_type == FileType::Directory)
+        }
+        "-s" => matches!(metadata(), Ok(metadata) if metadata.len > 0),
+        "-h" | "-L" => matches!(
+            shell_state.fs.symlink_metadata(&shell_state.resolve_path(operand)),
+            Ok(metadata) if metadata.file_type == FileType::Symlink
+        ),
+        _ => false,
+    }
+}
+
+// String and integer comparisons, `=` compares strings literally
+pub fn binary_test(left: &str, op: &str, right: &str) -> Result<bool, String> {
+    let result = match op {
+        "=" | "==" => left == right,
+        "!=" => left != right,
+        "<" => left < right,
+        ">" => left > right,
+        op => {
+            let (left, right) = (integer(left)?, integer(right)?);
+            match op {
+                "-eq" => left == right,
+                "-ne" => left != right,
+                "-lt" => left < right,
+                "-le" => left <= right,
+                "-gt" => left > right,
+                "-ge" => left >= right,
+                op => return Err(format!("{}: binary operator expected", op)),
+            }
+        }
+    };
+
+    Ok(result)
+}
+
+fn integer(text: &str) -> Result<i64, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("{}: integer expression expected", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{FileSystem, MemoryFileSystem};
+    use std::path::Path;
+    use std::rc::Rc;
+
+    fn state() -> ShellState {
+        let fs = MemoryFileSystem::default();
+        fs.create_dir_all(Path::new("/dir"));
+        fs.write(Path::new("/file"), b"content").unwrap();
+        fs.write(Path::new("/empty"), b"").unwrap();
+        fs.symlink(Path::new("file"), Path::new("/link")).unwrap();
+
+        ShellState::new(Rc::new(fs), String::from("/"))
+    }
+
+    fn test(keyword: &str, args: &str, shell_state: &mut ShellState) -> ExitStatus {
+        let args: Vec<&str> = args.split(' ').filter(|arg| !arg.is_empty()).collect();
+        let cmd = Command::new(keyword, &args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_strings_and_integers() {
+        let mut state = state();
+
+        let expected = vec![
+            ("", false),
+            ("a", true),
+            ("-n", true),
+            ("! a", false),
+            ("-z a", false),
+            ("-n a", true),
+            ("a = a", true),
+            ("a != a", false),
+            ("a < b", true),
+            ("10 -gt 9", true),
+            ("-1 -le -1", true),
+            ("( a )", true),
+            ("! a = b", true),
+            ("a = a -a b = c", false),
+            ("a = a -o b = c", true),
+            ("! ( a = a -o b = c )", false),
+            ("-n a -a ! -z b", true),
+        ];
+
+        for (args, result) in expected {
+            assert_eq!(
+                test("test", args, &mut state),
+                ExitStatus::from(result),
+                "test {}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_files() {
+        let mut state = state();
+
+        let expected = vec![
+            ("-e file", true),
+            ("-e missing", false),
+            ("-f file", true),
+            ("-f dir", false),
+            ("-d dir", true),
+            ("-d /file", false),
+            ("-r file", true),
+            ("-s file", true),
+            ("-s empty", false),
+            ("-f link", true),
+            ("-h link", true),
+            ("-L file", false),
+        ];
+
+        for (args, result) in expected {
+            assert_eq!(
+                test("[", &format!("{} ]", args), &mut state),
+                ExitStatus::from(result),
+                "[ {} ]",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = state();
+
+        assert_eq!(test("[", "a", &mut state), ExitStatus::USAGE);
+        assert_eq!(test("test", "a -eq 1", &mut state), ExitStatus::USAGE);
+        assert_eq!(test("test", "-q a", &mut state), ExitStatus::USAGE);
+        assert_eq!(test("test", "a b c d", &mut state), ExitStatus::USAGE);
+        assert_eq!(
+            state.output.stderr(),
+            b"[: missing `]'\n\
+              test: a: integer expression expected\n\
+              test: -q: unary operator expected\n\
+              test: too many arguments\n"
+                .to_vec()
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use crate::builtin::parse_flags;
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::filesystem;
+use crate::shell_state::ShellState;
+
+// touch [-c] FILE...
+// sets the modification time of the files to now, a missing file is created empty
+// unless -c is given.
+// >> touch notes.txt
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (flags, operands) = match parse_flags(&cmd.args, "c") {
+        Ok(parsed) => parsed,
+        Err(message) => return fail(&message, shell_state),
+    };
+    if operands.is_empty() {
+        return fail("missing file operand", shell_state);
+    }
+
+    let mut status = ExitStatus::SUCCESS;
+    for operand in &operands {
+        let path = shell_state.resolve_path(operand);
+
+        let result = match shell_state.fs.exists(&path) {
+            true => shell_state.fs.set_modified(&path, shell_state.clock.time()),
+            false if flags.contains('c') => Ok(()),
+            false => shell_state.fs.write(&path, b""),
+        };
+
+        if let Err(error) = result {
+            shell_state.output.write_stderr(format!(
+                "touch: cannot touch '{}': {}\n",
+                operand,
+                filesystem::describe_error(&error)
+            ));
+            status = ExitStatus::FAILURE;
+        }
+    }
+
+    status
+}
+
+fn fail(message: &str, shell_state: &mut ShellState) -> ExitStatus {
+    shell_state
+        .output
+        .write_stderr(format!("touch: {}\n", message));
+    ExitStatus::FAILURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{ManualClock, Times};
+    use std::path::Path;
+    use std::rc::Rc;
+    use std::time::{Duration, SystemTime};
+
+    fn touch(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("touch", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_touch() {
+        let clock = Rc::new(ManualClock::default());
+        let mut state = ShellState {
+            clock: clock.clone(),
+            ..ShellState::default()
+        };
+        state.fs.write(Path::new("/old"), b"contents").unwrap();
+        clock.advance(Times {
+            real: Duration::from_secs(60),
+            ..Times::default()
+        });
+
+        assert_eq!(touch(&["old", "new"], &mut state), ExitStatus::SUCCESS);
+        assert_eq!(touch(&["-c", "missing"], &mut state), ExitStatus::SUCCESS);
+
+        let old = state.fs.metadata(Path::new("/old")).unwrap();
+        assert_eq!(
+            old.modified,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60)
+        );
+        assert_eq!(old.len, 8);
+        assert_eq!(state.fs.read(Path::new("/new")).unwrap(), b"");
+        assert!(!state.fs.exists(Path::new("/missing")));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = ShellState::default();
+
+        assert_eq!(touch(&["dir/file"], &mut state), ExitStatus::FAILURE);
+        assert_eq!(touch(&[], &mut state), ExitStatus::FAILURE);
+        assert_eq!(
+            String::from_utf8_lossy(&state.output.stderr()),
+            "touch: cannot touch 'dir/file': No such file or directory\n\
+             touch: missing file operand\n"
+        );
+    }
+}
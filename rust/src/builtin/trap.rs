@@ -0,0 +1,144 @@
+use crate::command::Command;
+use crate::executor::trap::{self, SIGNALS};
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+// trap [-p] [[ACTION] SIGNAL...]
+// ACTION runs when the shell gets SIGNAL: EXIT, INT, DEBUG, ERR or RETURN. An empty ACTION
+// ignores the signal, `-` or no ACTION resets it. Without a SIGNAL the traps are listed.
+// >> trap 'rm -f /tmp/out' EXIT
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let mut args = cmd.args.as_slice();
+
+    match args.first().map(String::as_str) {
+        None => {
+            print_traps(&[], shell_state);
+            return ExitStatus::SUCCESS;
+        }
+        Some("-p") => return print_traps(&args[1..], shell_state),
+        Some("--") => args = &args[1..],
+        Some(option) if option.starts_with('-') && option != "-" => {
+            shell_state.output.write_stderr(format!(
+                "trap: {}: invalid option\ntrap: usage: trap [-p] [[action] signal_spec ...]\n",
+                option
+            ));
+            return ExitStatus::USAGE;
+        }
+        Some(_) => (),
+    }
+
+    let (action, signals) = match args {
+        [] => return ExitStatus::SUCCESS,
+        [signal] => (None, std::slice::from_ref(signal)),
+        [action, signals @ ..] if action == "-" => (None, signals),
+        [action, signals @ ..] => (Some(action), signals),
+    };
+
+    let mut status = ExitStatus::SUCCESS;
+    for spec in signals {
+        match (trap::signal_name(spec), action) {
+            (Some(name), Some(action)) => {
+                shell_state.traps.insert(String::from(name), action.clone());
+            }
+            (Some(name), None) => {
+                shell_state.traps.remove(name);
+            }
+            (None, _) => {
+                shell_state
+                    .output
+                    .write_stderr(format!("trap: {}: invalid signal specification\n", spec));
+                status = ExitStatus::FAILURE;
+            }
+        }
+    }
+
+    status
+}
+
+// the traps are written as commands that set them again, without signals all of them are
+fn print_traps(specs: &[String], shell_state: &mut ShellState) -> ExitStatus {
+    let mut names = Vec::new();
+    let mut status = ExitStatus::SUCCESS;
+
+    for spec in specs {
+        match trap::signal_name(spec) {
+            Some(name) => names.push(name),
+            None => {
+                shell_state
+                    .output
+                    .write_stderr(format!("trap: {}: invalid signal specification\n", spec));
+                status = ExitStatus::FAILURE;
+            }
+        }
+    }
+
+    if specs.is_empty() {
+        names = SIGNALS.to_vec();
+    }
+
+    for name in names {
+        if let Some(action) = shell_state.traps.get(name) {
+            let line = format!("trap -- '{}' {}\n", action.replace('\'', "'\\''"), name);
+            shell_state.output.write_stdout(line);
+        }
+    }
+
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trap(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("trap", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_set_and_list() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            trap(&["echo 'bye'", "EXIT", "sigint"], &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(trap(&["", "ERR"], &mut state), ExitStatus::SUCCESS);
+
+        trap(&[], &mut state);
+        trap(&["-p", "ERR"], &mut state);
+        assert_eq!(
+            state.output.stdout(),
+            b"trap -- 'echo '\\''bye'\\''' EXIT\ntrap -- 'echo '\\''bye'\\''' INT\n\
+              trap -- '' ERR\ntrap -- '' ERR\n"
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut state = ShellState::default();
+        trap(&["echo", "EXIT", "INT", "DEBUG"], &mut state);
+
+        trap(&["-", "EXIT"], &mut state);
+        trap(&["INT"], &mut state);
+
+        assert_eq!(state.traps.keys().collect::<Vec<_>>(), vec!["DEBUG"]);
+    }
+
+    #[test]
+    fn test_invalid_signals() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            trap(&["echo", "HUP", "EXIT"], &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(trap(&["-x"], &mut state), ExitStatus::USAGE);
+        assert!(state.traps.contains_key("EXIT"));
+        assert!(state
+            .output
+            .stderr()
+            .starts_with(b"trap: HUP: invalid signal specification\ntrap: -x: invalid option\n"));
+    }
+}
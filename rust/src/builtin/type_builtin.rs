@@ -0,0 +1,159 @@
+use crate::builtin::alias::format_alias;
+use crate::builtin::{self, CommandType};
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+// how a name is described by `type`, `type -t`, `command -v` and `which`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Style {
+    Sentence,
+    Kind,
+    Name,
+    Which,
+}
+
+// type [-t] NAME...
+// tells what each name runs as, -t only prints its kind: alias, keyword, builtin or file
+pub fn type_builtin(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (style, names) = match cmd.args.first().map(String::as_str) {
+        Some("-t") => (Style::Kind, &cmd.args[1..]),
+        Some(option) if option.starts_with('-') && option != "-" => {
+            shell_state
+                .output
+                .write_stderr(format!("type: {}: invalid option\n", option));
+            return ExitStatus::USAGE;
+        }
+        _ => (Style::Sentence, &cmd.args[..]),
+    };
+
+    describe_names("type", names, style, shell_state)
+}
+
+// which NAME...
+pub fn which(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    describe_names("which", &cmd.args, Style::Which, shell_state)
+}
+
+// The status fails when a name is not found, only a sentence tells about it
+pub fn describe_names(
+    builtin_name: &str,
+    names: &[String],
+    style: Style,
+    shell_state: &mut ShellState,
+) -> ExitStatus {
+    let mut status = ExitStatus::SUCCESS;
+
+    for name in names {
+        let description = match builtin::resolve(name, shell_state) {
+            Some(command_type) => describe(name, &command_type, style),
+            None => {
+                status = ExitStatus::FAILURE;
+                match style {
+                    Style::Sentence => {
+                        shell_state
+                            .output
+                            .write_stderr(format!("{}: {}: not found\n", builtin_name, name));
+                        continue;
+                    }
+                    Style::Which => format!("{} not found", name),
+                    Style::Kind | Style::Name => continue,
+                }
+            }
+        };
+
+        shell_state.output.write_stdout(description + "\n");
+    }
+
+    status
+}
+
+// >> describe("ll", Alias("ls -la"), Style::Sentence)
+// "ll is aliased to `ls -la'"
+fn describe(name: &str, command_type: &CommandType, style: Style) -> String {
+    match (style, command_type) {
+        (Style::Sentence, CommandType::Alias(value)) => {
+            format!("{} is aliased to `{}'", name, value)
+        }
+        (Style::Sentence, CommandType::Keyword) => format!("{} is a shell keyword", name),
+        (Style::Sentence, CommandType::Builtin(_)) => format!("{} is a shell builtin", name),
+        (Style::Sentence, CommandType::File) => format!("{} is {}", name, name),
+        (Style::Kind, CommandType::Alias(_)) => String::from("alias"),
+        (Style::Kind, CommandType::Keyword) => String::from("keyword"),
+        (Style::Kind, CommandType::Builtin(_)) => String::from("builtin"),
+        (Style::Kind, CommandType::File) => String::from("file"),
+        (Style::Name, CommandType::Alias(value)) => format_alias(name, value),
+        (Style::Name, _) => String::from(name),
+        (Style::Which, CommandType::Alias(value)) => format!("{}: aliased to {}", name, value),
+        (Style::Which, CommandType::Keyword) => format!("{}: shell reserved word", name),
+        (Style::Which, CommandType::Builtin(_)) => format!("{}: shell built-in command", name),
+        (Style::Which, CommandType::File) => String::from(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn state() -> ShellState {
+        let mut state = ShellState::default();
+        state.define_default_aliases();
+        state.fs.write(Path::new("/script.sh"), b"echo hi").unwrap();
+        state
+    }
+
+    #[test]
+    fn test_type() {
+        let mut state = state();
+
+        let status = type_builtin(
+            &Command::new(
+                "type",
+                &["ll", "case", "echo", "./script.sh", "missing", "ls"],
+            ),
+            &mut state,
+        );
+
+        assert_eq!(status, ExitStatus::FAILURE);
+        assert_eq!(
+            state.output.stdout(),
+            b"ll is aliased to `ls -la'\ncase is a shell keyword\necho is a shell builtin\n\
+              ./script.sh is ./script.sh\nls is a shell builtin\n"
+        );
+        assert_eq!(state.output.stderr(), b"type: missing: not found\n");
+    }
+
+    #[test]
+    fn test_kinds() {
+        let mut state = state();
+
+        type_builtin(
+            &Command::new(
+                "type",
+                &["-t", "l", "time", "type", "/script.sh", "/missing"],
+            ),
+            &mut state,
+        );
+
+        assert_eq!(state.output.stdout(), b"alias\nkeyword\nbuiltin\nfile\n");
+        assert_eq!(state.output.stderr(), b"");
+    }
+
+    #[test]
+    fn test_which() {
+        let mut state = state();
+
+        let status = which(
+            &Command::new("which", &["la", "[[", "cd", "/script.sh", "vim"]),
+            &mut state,
+        );
+
+        assert_eq!(status, ExitStatus::FAILURE);
+        assert_eq!(
+            state.output.stdout(),
+            b"la: aliased to ls -A\n[[: shell reserved word\ncd: shell built-in command\n\
+              /script.sh\nvim not found\n"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::command::Command;
+use crate::executor::{self, assignment};
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+// unset [-fv] NAME...
+// removes variables, `unset 'arr[1]'` only removes an element of an array.
+// There are no functions, so with -f nothing is removed.
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let (functions, names) = match cmd.args.first().map(String::as_str) {
+        Some("-f") => (true, &cmd.args[1..]),
+        Some("-v") => (false, &cmd.args[1..]),
+        Some(option) if option.starts_with('-') && option != "-" => {
+            shell_state.output.write_stderr(format!(
+                "unset: {}: invalid option\nunset: usage: unset [-f] [-v] [name ...]\n",
+                option
+            ));
+            return ExitStatus::USAGE;
+        }
+        _ => (false, &cmd.args[..]),
+    };
+
+    if functions {
+        return ExitStatus::SUCCESS;
+    }
+
+    let mut status = ExitStatus::SUCCESS;
+    for name in names {
+        if let Err(message) = unset(name, shell_state) {
+            shell_state
+                .output
+                .write_stderr(format!("unset: {}\n", message));
+            status = ExitStatus::FAILURE;
+        }
+    }
+
+    status
+}
+
+fn unset(arg: &str, shell_state: &mut ShellState) -> Result<(), String> {
+    let (name, raw_subscript) = match arg.split_once('[') {
+        Some((name, subscript)) => (name, subscript.strip_suffix(']')),
+        None => (arg, None),
+    };
+
+    if !executor::is_name(name) || (arg.contains('[') && raw_subscript.is_none()) {
+        return Err(format!("`{}': not a valid identifier", arg));
+    }
+
+    match raw_subscript {
+        None | Some("@") | Some("*") => {
+            shell_state.variables.remove(name);
+        }
+        Some(raw_subscript) => {
+            let associative = assignment::is_associative(name, shell_state);
+            let subscript =
+                assignment::expand_subscript(name, raw_subscript, associative, shell_state)
+                    .map_err(|error| error.0)?;
+
+            if let Some(value) = shell_state.variables.get_mut(name) {
+                value.remove(&subscript);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variable::Value;
+
+    fn unset(args: &[&str], shell_state: &mut ShellState) -> ExitStatus {
+        let cmd = Command::new("unset", args);
+
+        run(&cmd, shell_state)
+    }
+
+    #[test]
+    fn test_unset() {
+        let mut state = ShellState::default();
+        state.set_variable("A", String::from("a"));
+        state.variables.insert(
+            String::from("arr"),
+            Value::indexed(vec![
+                String::from("x"),
+                String::from("y"),
+                String::from("z"),
+            ]),
+        );
+
+        assert_eq!(
+            unset(&["A", "arr[1]", "B"], &mut state),
+            ExitStatus::SUCCESS
+        );
+        assert_eq!(state.parameter("A"), None);
+        assert_eq!(state.variables["arr"].keys(), vec!["0", "2"]);
+
+        unset(&["-v", "arr[@]"], &mut state);
+        assert_eq!(state.variables.keys().collect::<Vec<_>>(), vec!["OPTIND"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = ShellState::default();
+
+        assert_eq!(
+            unset(&["1a", "a[1", "a[]"], &mut state),
+            ExitStatus::FAILURE
+        );
+        assert_eq!(unset(&["-x"], &mut state), ExitStatus::USAGE);
+        assert_eq!(
+            String::from_utf8_lossy(&state.output.stderr()),
+            "unset: `1a': not a valid identifier\nunset: `a[1': not a valid identifier\n\
+             unset: a[]: bad array subscript\nunset: -x: invalid option\n\
+             unset: usage: unset [-f] [-v] [name ...]\n"
+        );
+    }
+}
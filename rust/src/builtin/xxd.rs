@@ -0,0 +1,187 @@
+use crate::builtin::{read_input, write_file_error};
+use crate::command::Command;
+use crate::exit_status::ExitStatus;
+use crate::shell_state::ShellState;
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    plain: bool,
+    revert: bool,
+    columns: Option<usize>,
+    path: String,
+}
+
+// xxd [-p] [-r] [-c COLS] [FILE]
+// hex dump of the input, -r turns a dump back in to the original bytes
+pub fn run(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    let options = match parse_options(&cmd.args) {
+        Ok(options) => options,
+        Err(message) => {
+            shell_state
+                .output
+                .write_stderr(format!("xxd: {}\n", message));
+            return ExitStatus::USAGE;
+        }
+    };
+
+    let input = match read_input(&options.path, shell_state) {
+        Ok(input) => input,
+        Err(error) => {
+            write_file_error("xxd", &options.path, &error, shell_state);
+            return ExitStatus::FAILURE;
+        }
+    };
+
+    let output = match (options.revert, options.plain) {
+        (true, true) => revert_plain(&input),
+        (true, false) => revert_dump(&input),
+        (false, true) => plain(&input, options.columns.unwrap_or(30)).into_bytes(),
+        (false, false) => dump(&input, options.columns.unwrap_or(16)).into_bytes(),
+    };
+
+    shell_state.output.write_stdout(output);
+    ExitStatus::SUCCESS
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        plain: false,
+        revert: false,
+        columns: None,
+        path: String::from("-"),
+    };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-p" | "-ps" | "-plain" => options.plain = true,
+            "-r" | "-revert" => options.revert = true,
+            "-c" | "-cols" => match args.next().map(|cols| cols.parse()) {
+                Some(Ok(cols)) if cols > 0 => options.columns = Some(cols),
+                _ => return Err(String::from("invalid number of columns")),
+            },
+            arg if arg.len() > 1 && arg.starts_with('-') => {
+                return Err(format!("invalid option -- '{}'", &arg[1..]))
+            }
+            arg => options.path = String::from(arg),
+        }
+    }
+
+    Ok(options)
+}
+
+// 00000000: 6865 6c6c 6f0a                           hello.
+fn dump(bytes: &[u8], columns: usize) -> String {
+    let hex_width = columns * 2 + columns.div_ceil(2) - 1;
+    let mut result = String::new();
+
+    for (i, line) in bytes.chunks(columns).enumerate() {
+        let hex: Vec<String> = line
+            .chunks(2)
+            .map(|group| group.iter().map(|byte| format!("{:02x}", byte)).collect())
+            .collect();
+        let text: String = line
+            .iter()
+            .map(|byte| match byte {
+                0x20..=0x7e => *byte as char,
+                _ => '.',
+            })
+            .collect();
+
+        result.push_str(&format!(
+            "{:08x}: {:<width$}  {}\n",
+            i * columns,
+            hex.join(" "),
+            text,
+            width = hex_width
+        ));
+    }
+
+    result
+}
+
+fn plain(bytes: &[u8], columns: usize) -> String {
+    bytes
+        .chunks(columns)
+        .map(|line| {
+            let hex: String = line.iter().map(|byte| format!("{:02x}", byte)).collect();
+            format!("{}\n", hex)
+        })
+        .collect()
+}
+
+// every hex digit counts, anything else is ignored
+fn revert_plain(input: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = input
+        .iter()
+        .filter_map(|byte| (*byte as char).to_digit(16))
+        .map(|digit| digit as u8)
+        .collect();
+
+    digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).unwrap_or(&0))
+        .collect()
+}
+
+// only the hex columns between the offset and the text column are read
+fn revert_dump(input: &[u8]) -> Vec<u8> {
+    String::from_utf8_lossy(input)
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .flat_map(|(_, rest)| {
+            let hex = rest.split("  ").next().unwrap_or("");
+            revert_plain(hex.as_bytes())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xxd(args: &[&str], stdin: &[u8]) -> Vec<u8> {
+        let mut state = ShellState::default();
+        state.output.stdin = Some(stdin.to_vec());
+
+        let cmd = Command::new("xxd", args);
+
+        run(&cmd, &mut state);
+        state.output.stdout()
+    }
+
+    #[test]
+    fn test_dump() {
+        assert_eq!(
+            dump(b"hello\n", 16),
+            "00000000: 6865 6c6c 6f0a                           hello.\n"
+        );
+        assert_eq!(
+            dump(b"0123456789abcdefg", 16),
+            "00000000: 3031 3233 3435 3637 3839 6162 6364 6566  0123456789abcdef\n\
+             00000010: 67                                       g\n"
+        );
+    }
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(xxd(&["-p"], &[0xff, 0x00, 0x10]), b"ff0010\n");
+    }
+
+    #[test]
+    fn test_revert_is_lossless() {
+        let bytes: Vec<u8> = (0..=255).collect();
+
+        assert_eq!(xxd(&["-r"], &xxd(&[], &bytes)), bytes);
+        assert_eq!(xxd(&["-r", "-p"], &xxd(&["-p"], &bytes)), bytes);
+    }
+
+    #[test]
+    fn test_invalid_option() {
+        let args = vec![String::from("-x")];
+        assert_eq!(
+            parse_options(&args),
+            Err(String::from("invalid option -- 'x'"))
+        );
+    }
+}
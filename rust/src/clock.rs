@@ -0,0 +1,134 @@
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::time::{Duration, Instant, SystemTime};
+
+// Times spent by the shell since some point in time, `time` reports the difference
+// between two readings
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Times {
+    pub real: Duration,
+    pub user: Duration,
+    pub sys: Duration,
+}
+
+impl Times {
+    // the times that passed since an earlier reading
+    pub fn since(&self, earlier: &Times) -> Times {
+        Times {
+            real: self.real.saturating_sub(earlier.real),
+            user: self.user.saturating_sub(earlier.user),
+            sys: self.sys.saturating_sub(earlier.sys),
+        }
+    }
+}
+
+// Source of time for the shell. `std::time::Instant` doesn't exist in wasm,
+// so every host provides its own clock.
+pub trait Clock: Debug {
+    fn now(&self) -> Times;
+    // the time of day, eg: when a file was modified
+    fn time(&self) -> SystemTime;
+}
+
+// The clock of the system. Commands run inside the shell process, so user and sys
+// are the cpu times of the process, they are only known on linux.
+#[derive(Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Times {
+        let (user, sys) = process_cpu_times().unwrap_or_default();
+
+        Times {
+            real: self.start.elapsed(),
+            user,
+            sys,
+        }
+    }
+
+    fn time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+// utime and stime of /proc/self/stat, they are counted in ticks of 1/100 second
+fn process_cpu_times() -> Option<(Duration, Duration)> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // the name of the process is in parentheses and can contain spaces
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let ticks = |i: usize| -> Option<Duration> {
+        let ticks: u64 = fields.get(i)?.parse().ok()?;
+        Some(Duration::from_millis(ticks * 10))
+    };
+
+    Some((ticks(11)?, ticks(12)?))
+}
+
+// A clock that only moves when it's told to, so output of `time` is predictable
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    times: Cell<Times>,
+}
+
+impl ManualClock {
+    pub fn advance(&self, times: Times) {
+        let now = self.times.get();
+
+        self.times.set(Times {
+            real: now.real + times.real,
+            user: now.user + times.user,
+            sys: now.sys + times.sys,
+        });
+    }
+}
+
+// the time of day starts at the epoch
+impl Clock for ManualClock {
+    fn now(&self) -> Times {
+        self.times.get()
+    }
+
+    fn time(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + self.times.get().real
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::default();
+        let start = clock.now();
+
+        clock.advance(Times {
+            real: Duration::from_millis(1500),
+            user: Duration::from_millis(20),
+            sys: Duration::ZERO,
+        });
+        clock.advance(Times {
+            real: Duration::from_millis(500),
+            ..Times::default()
+        });
+
+        assert_eq!(
+            clock.now().since(&start),
+            Times {
+                real: Duration::from_secs(2),
+                user: Duration::from_millis(20),
+                sys: Duration::ZERO,
+            }
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use std::convert::TryFrom;
 
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, PartialEq, Default, Clone)]
 pub struct Command {
     pub keyword: String,
     pub args: Vec<String>,
@@ -19,6 +19,18 @@ impl TryFrom<&String> for Command {
     }
 }
 
+// a command from its words, eg: for the tests of builtins
+// >> Command::new("ls", &["-a", "/"])
+#[cfg(test)]
+impl Command {
+    pub fn new(keyword: &str, args: &[&str]) -> Command {
+        Command {
+            keyword: String::from(keyword),
+            args: args.iter().map(|arg| String::from(*arg)).collect(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
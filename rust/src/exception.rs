@@ -1,4 +1,5 @@
-use std::fmt::Display;
+use std::fmt::{Display, Formatter};
+
 use crate::parser::token::Token;
 
 #[derive(Debug, PartialEq)]
@@ -11,3 +12,34 @@ pub enum Exception {
     ConversionNotImplemented(String),
     Unexpected(String)
 }
+
+// the message the shell prints after "shell: ", eg: "syntax error near unexpected token `|'"
+impl Display for Exception {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Exception::AsyncIsUnSupported => write!(f, "background jobs with `&' are not supported"),
+            Exception::CommandHasNoCharacters => write!(f, "syntax error: empty command"),
+            Exception::TokenCannotBeParsed(token) => {
+                write!(f, "syntax error near unexpected token `{}'", token)
+            }
+            Exception::TokensCannotBeParsed(message) => write!(f, "{}", message),
+            Exception::TokenIsNotALogicalExpr(token) => write!(f, "{} is not a logical operator", token),
+            Exception::ConversionNotImplemented(token) => write!(f, "{} is not supported", token),
+            Exception::Unexpected(message) => write!(f, "{}", message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let exception = Exception::TokenCannotBeParsed(Token::Pipeline);
+        assert_eq!(exception.to_string(), "syntax error near unexpected token `|'");
+
+        let exception = Exception::TokensCannotBeParsed(String::from("parse error near `)'"));
+        assert_eq!(exception.to_string(), "parse error near `)'");
+    }
+}
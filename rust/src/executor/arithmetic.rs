@@ -0,0 +1,583 @@
+use crate::shell_state::ShellState;
+
+// An expression that can't be evaluated, the message starts with the expression
+// >> evaluate("1 / 0")
+// Err(ArithmeticError("1 / 0: division by 0"))
+#[derive(Debug, PartialEq)]
+pub struct ArithmeticError(pub String);
+
+// variables can contain expressions themselves, this stops `A=A` from looping forever
+const MAX_DEPTH: usize = 100;
+
+// longest operators first, so "<<=" is not read as "<" followed by "<="
+const OPERATORS: [&str; 39] = [
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "&=", "^=", "|=", "+", "-", "*", "/", "%", "<", ">", "&", "|", "^", "!", "~",
+    "=", "?", ":", ",", "(", ")",
+];
+
+const ASSIGNMENT_OPERATORS: [&str; 11] = [
+    "=", "+=", "-=", "*=", "/=", "%=", "<<=", ">>=", "&=", "^=", "|=",
+];
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Number(String),
+    Name(String),
+    Operator(&'static str),
+}
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+    Number(i64),
+    Variable(String),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    // `=` or a compound assignment like `+=`
+    Assign(&'static str, String, Box<Expr>),
+    // `++a`, `a--`, the value of a postfix increment is the value before it
+    Increment {
+        name: String,
+        delta: i64,
+        prefix: bool,
+    },
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+// Evaluates an integer expression with the operators and precedence of C.
+// Variables can be used without `$`, unset or empty variables are 0.
+// >> evaluate("x = 2 ** 3, x > 5 ? 0x10 : 8#17")
+// Ok(16)
+pub fn evaluate(expression: &str, shell_state: &mut ShellState) -> Result<i64, ArithmeticError> {
+    evaluate_at_depth(expression, shell_state, 0)
+        .map_err(|message| ArithmeticError(format!("{}: {}", expression.trim(), message)))
+}
+
+fn evaluate_at_depth(
+    expression: &str,
+    shell_state: &mut ShellState,
+    depth: usize,
+) -> Result<i64, String> {
+    if depth > MAX_DEPTH {
+        return Err(String::from("expression recursion level exceeded"));
+    }
+
+    let tokens = tokenize(expression)?;
+    if tokens.is_empty() {
+        return Ok(0);
+    }
+
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+    };
+    let expr = parser.comma()?;
+
+    match parser.peek() {
+        Some(token) => Err(format!(
+            "syntax error in expression (error token is \"{}\")",
+            token_text(token)
+        )),
+        None => Evaluator { shell_state, depth }.evaluate(&expr),
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let char = chars[i];
+
+        if char.is_whitespace() {
+            i += 1;
+        } else if char.is_ascii_digit() {
+            // base literals like "16#ff" or "64#@_" are read as one number
+            let end = (i..chars.len())
+                .find(|j| !(chars[*j].is_ascii_alphanumeric() || "#@_".contains(chars[*j])))
+                .unwrap_or(chars.len());
+            tokens.push(Token::Number(chars[i..end].iter().collect()));
+            i = end;
+        } else if char.is_ascii_alphabetic() || char == '_' {
+            let end = (i..chars.len())
+                .find(|j| !(chars[*j].is_ascii_alphanumeric() || chars[*j] == '_'))
+                .unwrap_or(chars.len());
+            tokens.push(Token::Name(chars[i..end].iter().collect()));
+            i = end;
+        } else {
+            let rest: String = chars[i..].iter().collect();
+            let operator = OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(*operator))
+                .ok_or_else(|| {
+                    format!(
+                        "syntax error: invalid arithmetic operator (error token is \"{}\")",
+                        rest
+                    )
+                })?;
+            tokens.push(Token::Operator(operator));
+            i += operator.chars().count();
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn token_text(token: &Token) -> &str {
+    match token {
+        Token::Number(text) | Token::Name(text) => text,
+        Token::Operator(operator) => operator,
+    }
+}
+
+// >> parse_number("0x1f"), parse_number("017"), parse_number("2#101")
+// Ok(31), Ok(15), Ok(5)
+fn parse_number(text: &str) -> Result<i64, String> {
+    let (base, digits) = match text.split_once('#') {
+        Some((base, digits)) => match base.parse::<u32>() {
+            Ok(base) if (2..=64).contains(&base) => (base, digits),
+            _ => {
+                return Err(format!(
+                    "invalid arithmetic base (error token is \"{}\")",
+                    text
+                ))
+            }
+        },
+        None if text.starts_with("0x") || text.starts_with("0X") => (16, &text[2..]),
+        None if text.len() > 1 && text.starts_with('0') => (8, &text[1..]),
+        None => (10, text),
+    };
+
+    let too_great = || format!("value too great for base (error token is \"{}\")", text);
+    if digits.is_empty() {
+        return Err(too_great());
+    }
+
+    let mut value: i64 = 0;
+    for char in digits.chars() {
+        let digit = match char {
+            '0'..='9' => char as u32 - '0' as u32,
+            'a'..='z' => char as u32 - 'a' as u32 + 10,
+            // below base 37 letters are case insensitive
+            'A'..='Z' if base <= 36 => char as u32 - 'A' as u32 + 10,
+            'A'..='Z' => char as u32 - 'A' as u32 + 36,
+            '@' => 62,
+            '_' => 63,
+            _ => return Err(too_great()),
+        };
+
+        if digit >= base {
+            return Err(too_great());
+        }
+
+        value = value.wrapping_mul(base as i64).wrapping_add(digit as i64);
+    }
+
+    Ok(value)
+}
+
+// binding power of a binary operator and whether it groups to the right
+fn precedence(operator: &str) -> Option<(u8, bool)> {
+    let precedence = match operator {
+        "||" => 1,
+        "&&" => 2,
+        "|" => 3,
+        "^" => 4,
+        "&" => 5,
+        "==" | "!=" => 6,
+        "<" | "<=" | ">" | ">=" => 7,
+        "<<" | ">>" => 8,
+        "+" | "-" => 9,
+        "*" | "/" | "%" => 10,
+        "**" => return Some((11, true)),
+        _ => return None,
+    };
+
+    Some((precedence, false))
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_operator(&self) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Operator(operator)) => Some(operator),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<(), String> {
+        match self.peek_operator() {
+            Some(found) if found == operator => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(format!("syntax error: `{}' expected", operator)),
+        }
+    }
+
+    fn comma(&mut self) -> Result<Expr, String> {
+        let mut left = self.assignment()?;
+
+        while self.peek_operator() == Some(",") {
+            self.position += 1;
+            left = Expr::Binary(",", Box::new(left), Box::new(self.assignment()?));
+        }
+
+        Ok(left)
+    }
+
+    fn assignment(&mut self) -> Result<Expr, String> {
+        if let (Some(Token::Name(name)), Some(Token::Operator(operator))) =
+            (self.peek(), self.tokens.get(self.position + 1))
+        {
+            if ASSIGNMENT_OPERATORS.contains(operator) {
+                self.position += 2;
+                let value = self.assignment()?;
+                return Ok(Expr::Assign(operator, name.clone(), Box::new(value)));
+            }
+        }
+
+        self.conditional()
+    }
+
+    fn conditional(&mut self) -> Result<Expr, String> {
+        let condition = self.binary(1)?;
+
+        if self.peek_operator() != Some("?") {
+            return Ok(condition);
+        }
+
+        self.position += 1;
+        let then = self.comma()?;
+        self.expect(":")?;
+        let otherwise = self.conditional()?;
+
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+
+        while let Some(operator) = self.peek_operator() {
+            let (precedence, right_associative) = match precedence(operator) {
+                Some((precedence, right)) if precedence >= min_precedence => (precedence, right),
+                _ => break,
+            };
+
+            self.position += 1;
+            let next_precedence = match right_associative {
+                true => precedence,
+                false => precedence + 1,
+            };
+            let right = self.binary(next_precedence)?;
+            left = Expr::Binary(operator, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek_operator() {
+            Some(operator @ ("+" | "-" | "!" | "~")) => {
+                self.position += 1;
+                Ok(Expr::Unary(operator, Box::new(self.unary()?)))
+            }
+            Some(operator @ ("++" | "--")) => {
+                self.position += 1;
+                match self.peek() {
+                    Some(Token::Name(name)) => {
+                        self.position += 1;
+                        Ok(Expr::Increment {
+                            name: name.clone(),
+                            delta: if operator == "++" { 1 } else { -1 },
+                            prefix: true,
+                        })
+                    }
+                    _ => Err(String::from(
+                        "syntax error: operand expected (error token is \"++\")",
+                    )),
+                }
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let primary = self.primary()?;
+
+        match (&primary, self.peek_operator()) {
+            (Expr::Variable(name), Some(operator @ ("++" | "--"))) => {
+                self.position += 1;
+                Ok(Expr::Increment {
+                    name: name.clone(),
+                    delta: if operator == "++" { 1 } else { -1 },
+                    prefix: false,
+                })
+            }
+            _ => Ok(primary),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self
+            .peek()
+            .ok_or_else(|| String::from("syntax error: operand expected (error token is \"\")"))?;
+        self.position += 1;
+
+        match token {
+            Token::Number(text) => Ok(Expr::Number(parse_number(text)?)),
+            Token::Name(name) => Ok(Expr::Variable(name.clone())),
+            Token::Operator("(") => {
+                let expr = self.comma()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Operator(operator) => Err(format!(
+                "syntax error: operand expected (error token is \"{}\")",
+                operator
+            )),
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    shell_state: &'a mut ShellState,
+    depth: usize,
+}
+
+impl Evaluator<'_> {
+    fn evaluate(&mut self, expr: &Expr) -> Result<i64, String> {
+        match expr {
+            Expr::Number(value) => Ok(*value),
+            Expr::Variable(name) => self.variable(name),
+            Expr::Unary(operator, operand) => {
+                let value = self.evaluate(operand)?;
+                Ok(match *operator {
+                    "-" => value.wrapping_neg(),
+                    "!" => (value == 0) as i64,
+                    "~" => !value,
+                    _ => value,
+                })
+            }
+            // the right side of `&&` and `||` is only evaluated when it's needed
+            Expr::Binary("&&", left, right) => {
+                Ok((self.evaluate(left)? != 0 && self.evaluate(right)? != 0) as i64)
+            }
+            Expr::Binary("||", left, right) => {
+                Ok((self.evaluate(left)? != 0 || self.evaluate(right)? != 0) as i64)
+            }
+            Expr::Binary(operator, left, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                apply(operator, left, right)
+            }
+            Expr::Assign(operator, name, value) => {
+                let value = self.evaluate(value)?;
+                let value = match operator.strip_suffix('=') {
+                    Some("") | None => value,
+                    Some(operator) => apply(operator, self.variable(name)?, value)?,
+                };
+
+                self.set(name, value);
+                Ok(value)
+            }
+            Expr::Increment {
+                name,
+                delta,
+                prefix,
+            } => {
+                let old = self.variable(name)?;
+                let new = old.wrapping_add(*delta);
+                self.set(name, new);
+                Ok(if *prefix { new } else { old })
+            }
+            Expr::Conditional(condition, then, otherwise) => match self.evaluate(condition)? {
+                0 => self.evaluate(otherwise),
+                _ => self.evaluate(then),
+            },
+        }
+    }
+
+    // the value of a variable is an expression itself
+    fn variable(&mut self, name: &str) -> Result<i64, String> {
+        match self.shell_state.parameter(name) {
+            Some(value) if !value.trim().is_empty() => {
+                evaluate_at_depth(&value, self.shell_state, self.depth + 1)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    fn set(&mut self, name: &str, value: i64) {
+        self.shell_state.set_variable(name, value.to_string());
+    }
+}
+
+fn apply(operator: &str, left: i64, right: i64) -> Result<i64, String> {
+    let value = match operator {
+        "+" => left.wrapping_add(right),
+        "-" => left.wrapping_sub(right),
+        "*" => left.wrapping_mul(right),
+        "/" | "%" if right == 0 => return Err(String::from("division by 0")),
+        "/" => left.wrapping_div(right),
+        "%" => left.wrapping_rem(right),
+        "**" if right < 0 => return Err(String::from("exponent less than 0")),
+        "**" => left.wrapping_pow(right.min(u32::MAX as i64) as u32),
+        "<<" => left.wrapping_shl(right as u32),
+        ">>" => left.wrapping_shr(right as u32),
+        "&" => left & right,
+        "|" => left | right,
+        "^" => left ^ right,
+        "<" => (left < right) as i64,
+        "<=" => (left <= right) as i64,
+        ">" => (left > right) as i64,
+        ">=" => (left >= right) as i64,
+        "==" => (left == right) as i64,
+        "!=" => (left != right) as i64,
+        _ => right, // ","
+    };
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str, state: &mut ShellState) -> i64 {
+        evaluate(expression, state).unwrap()
+    }
+
+    #[test]
+    fn test_precedence() {
+        let mut state = ShellState::default();
+
+        let expected = vec![
+            ("", 0),
+            ("1 + 2 * 3", 7),
+            ("(1 + 2) * 3", 9),
+            ("10 - 4 - 3", 3),
+            ("2 ** 3 ** 2", 512),
+            ("-2 ** 2", 4),
+            ("7 / 2 + 7 % 2", 4),
+            ("-7 / 2", -3),
+            ("1 << 4 | 1", 17),
+            ("6 & 3 ^ 1", 3),
+            ("1 < 2 == 1", 1),
+            ("!0 + ~0", 0),
+            ("1 && 0 || 2", 1),
+            ("0 ? 1 : 2 ? 3 : 4", 3),
+            ("1, 2, 3", 3),
+        ];
+
+        for (expression, result) in expected {
+            assert_eq!(eval(expression, &mut state), result, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn test_numbers() {
+        let mut state = ShellState::default();
+
+        let expected = vec![
+            ("42", 42),
+            ("0x1F", 31),
+            ("017", 15),
+            ("8#17", 15),
+            ("2#101", 5),
+            ("36#z", 35),
+            ("64#_", 63),
+            ("64#A", 36),
+        ];
+
+        for (expression, result) in expected {
+            assert_eq!(eval(expression, &mut state), result, "{}", expression);
+        }
+
+        assert_eq!(
+            evaluate("08", &mut state),
+            Err(ArithmeticError(String::from(
+                "08: value too great for base (error token is \"08\")"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut state = ShellState::default();
+        state.set_variable("A", String::from("5"));
+        state
+            .set_variable("B", String::from("A * 2"));
+
+        assert_eq!(eval("A + B", &mut state), 15);
+        assert_eq!(eval("UNSET + 1", &mut state), 1);
+
+        assert_eq!(eval("C = A += 2", &mut state), 7);
+        assert_eq!(state.parameter("A"), Some(String::from("7")));
+        assert_eq!(state.parameter("C"), Some(String::from("7")));
+
+        assert_eq!(eval("A++", &mut state), 7);
+        assert_eq!(eval("++A", &mut state), 9);
+        assert_eq!(eval("A--, A", &mut state), 8);
+        assert_eq!(eval("A <<= 1", &mut state), 16);
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        let mut state = ShellState::default();
+
+        assert_eq!(eval("0 && (A = 1)", &mut state), 0);
+        assert_eq!(eval("1 || (A = 1)", &mut state), 1);
+        assert_eq!(eval("1 ? 2 : (A = 1)", &mut state), 2);
+        assert_eq!(state.parameter("A"), None);
+
+        assert_eq!(eval("0 && 1 / 0", &mut state), 0);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = ShellState::default();
+        state
+            .set_variable("LOOP", String::from("LOOP"));
+
+        let expected = vec![
+            ("1 / 0", "1 / 0: division by 0"),
+            ("5 % 0", "5 % 0: division by 0"),
+            ("2 ** -1", "2 ** -1: exponent less than 0"),
+            (
+                "1 +",
+                "1 +: syntax error: operand expected (error token is \"\")",
+            ),
+            ("(1", "(1: syntax error: `)' expected"),
+            (
+                "1 2",
+                "1 2: syntax error in expression (error token is \"2\")",
+            ),
+            (
+                "1 $ 2",
+                "1 $ 2: syntax error: invalid arithmetic operator (error token is \"$ 2\")",
+            ),
+            ("LOOP", "LOOP: expression recursion level exceeded"),
+        ];
+
+        for (expression, message) in expected {
+            assert_eq!(
+                evaluate(expression, &mut state),
+                Err(ArithmeticError(String::from(message)))
+            );
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use crate::executor::expansion::{self, ExpansionError};
+use crate::parser::word::{self, Assignment};
+use crate::shell_state::ShellState;
+use crate::variable::{Subscript, Value};
+
+// Sets the variable of an assignment, its value and subscript are expanded first.
+// Returns the assignment as xtrace shows it, eg: "A='b c'".
+pub fn assign(
+    assignment: &Assignment,
+    shell_state: &mut ShellState,
+) -> Result<String, ExpansionError> {
+    let name = assignment.name;
+    let operator = if assignment.append { "+=" } else { "=" };
+
+    match (assignment.subscript, assignment.elements()) {
+        (Some(subscript), Some(_)) => Err(ExpansionError(format!(
+            "{}[{}]: cannot assign list to array member",
+            name, subscript
+        ))),
+        (Some(raw_subscript), None) => {
+            let associative = is_associative(name, shell_state);
+            let subscript = expand_subscript(name, raw_subscript, associative, shell_state)?;
+            let value = expansion::expand_word(assignment.value, shell_state)?.text;
+            let trace = format!(
+                "{}[{}]{}{}",
+                name,
+                raw_subscript,
+                operator,
+                word::quote(&value)
+            );
+
+            let array = shell_state
+                .variables
+                .entry(String::from(name))
+                .or_insert_with(|| Value::Indexed(BTreeMap::new()));
+            let value = match assignment.append {
+                true => array.get(&subscript).cloned().unwrap_or_default() + &value,
+                false => value,
+            };
+
+            match array.set(subscript, value) {
+                Some(()) => Ok(trace),
+                None => Err(bad_subscript(name, raw_subscript)),
+            }
+        }
+        (None, Some(elements)) => {
+            let array = match (assignment.append, shell_state.variables.get(name)) {
+                (true, Some(value)) => value.clone(),
+                (_, Some(Value::Associative(_))) => Value::Associative(BTreeMap::new()),
+                _ => Value::Indexed(BTreeMap::new()),
+            };
+            let array = assign_elements(name, array, &elements, shell_state)?;
+
+            shell_state.variables.insert(String::from(name), array);
+            Ok(format!("{}{}({})", name, operator, elements.join(" ")))
+        }
+        (None, None) => {
+            let value = expansion::expand_word(assignment.value, shell_state)?.text;
+            let trace = format!("{}{}{}", name, operator, word::quote(&value));
+
+            let value = match assignment.append {
+                true => shell_state.parameter(name).unwrap_or_default() + &value,
+                false => value,
+            };
+            shell_state.set_variable(name, value);
+
+            Ok(trace)
+        }
+    }
+}
+
+// The words of `NAME=(...)` are expanded like the arguments of a command, an element
+// with a subscript sets that element and the next ones follow it
+fn assign_elements(
+    name: &str,
+    mut array: Value,
+    elements: &[String],
+    shell_state: &mut ShellState,
+) -> Result<Value, ExpansionError> {
+    for element in elements {
+        if let Some((raw_subscript, value)) = word::split_keyed_element(element) {
+            let associative = array.is_associative();
+            let subscript = expand_subscript(name, raw_subscript, associative, shell_state)?;
+            let value = expansion::expand_word(value, shell_state)?.text;
+
+            array
+                .set(subscript, value)
+                .ok_or_else(|| bad_subscript(name, raw_subscript))?;
+            continue;
+        }
+
+        if array.is_associative() {
+            return Err(ExpansionError(format!(
+                "{}: {}: must use subscript when assigning associative array",
+                name, element
+            )));
+        }
+
+        for value in expansion::expand_words(std::slice::from_ref(element), shell_state)? {
+            array.push(value);
+        }
+    }
+
+    Ok(array)
+}
+
+// The subscript of an associative array is a key, the one of an indexed array
+// an arithmetic expression, eg: `arr[i + 1]`
+pub fn expand_subscript(
+    name: &str,
+    raw: &str,
+    associative: bool,
+    shell_state: &mut ShellState,
+) -> Result<Subscript, ExpansionError> {
+    if raw.is_empty() {
+        return Err(bad_subscript(name, raw));
+    }
+
+    match associative {
+        true => Ok(Subscript::Key(
+            expansion::expand_word(raw, shell_state)?.text,
+        )),
+        false => Ok(Subscript::Index(expansion::evaluate_arithmetic(
+            raw,
+            shell_state,
+        )?)),
+    }
+}
+
+pub fn is_associative(name: &str, shell_state: &ShellState) -> bool {
+    shell_state
+        .variables
+        .get(name)
+        .is_some_and(Value::is_associative)
+}
+
+pub fn bad_subscript(name: &str, subscript: &str) -> ExpansionError {
+    ExpansionError(format!("{}[{}]: bad array subscript", name, subscript))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(raw: &str, shell_state: &mut ShellState) -> Result<String, ExpansionError> {
+        assign(&word::split_assignment(raw).unwrap(), shell_state)
+    }
+
+    fn values(name: &str, shell_state: &ShellState) -> Vec<String> {
+        shell_state.variables[name].values()
+    }
+
+    #[test]
+    fn test_indexed_arrays() {
+        let mut state = ShellState::default();
+        state.set_variable("B", String::from("b c"));
+
+        assert_eq!(
+            run("a=(x $B [5]=y z)", &mut state),
+            Ok(String::from("a=(x $B [5]=y z)"))
+        );
+        assert_eq!(state.variables["a"].keys(), vec!["0", "1", "2", "5", "6"]);
+
+        run("a[1+1]=w", &mut state).unwrap();
+        run("a[-1]+=!", &mut state).unwrap();
+        run("a+=(v)", &mut state).unwrap();
+        assert_eq!(values("a", &state), vec!["x", "b", "w", "y", "z!", "v"]);
+
+        assert_eq!(
+            run("a[-9]=x", &mut state),
+            Err(ExpansionError(String::from("a[-9]: bad array subscript")))
+        );
+        assert_eq!(
+            run("a[0]=(x)", &mut state),
+            Err(ExpansionError(String::from(
+                "a[0]: cannot assign list to array member"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_associative_arrays() {
+        let mut state = ShellState::default();
+        state
+            .variables
+            .insert(String::from("m"), Value::Associative(BTreeMap::new()));
+
+        run("m=([\"a b\"]=1 [$((1 + 1))]=2)", &mut state).unwrap();
+        run("m[c]=3", &mut state).unwrap();
+        run("m+=([\"a b\"]=0)", &mut state).unwrap();
+
+        assert_eq!(state.variables["m"].keys(), vec!["2", "a b", "c"]);
+        assert_eq!(values("m", &state), vec!["2", "0", "3"]);
+        assert_eq!(
+            run("m=(x)", &mut state),
+            Err(ExpansionError(String::from(
+                "m: x: must use subscript when assigning associative array"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        let mut state = ShellState::default();
+
+        assert_eq!(run("A='b c'", &mut state), Ok(String::from("A='b c'")));
+        run("A+=d", &mut state).unwrap();
+        assert_eq!(state.parameter("A"), Some(String::from("b cd")));
+
+        run("A[1]=e", &mut state).unwrap();
+        run("A=f", &mut state).unwrap();
+        assert_eq!(values("A", &state), vec!["f", "e"]);
+    }
+}
@@ -0,0 +1,232 @@
+// Brace expansion, the first expansion of a word. It works on the raw word, so
+// quoted or escaped braces and the braces of `${...}` are left alone.
+// >> expand_braces("proj/{src,tests}")
+// vec!["proj/src", "proj/tests"]
+// >> expand_braces("file{1..3}")
+// vec!["file1", "file2", "file3"]
+pub fn expand_braces(word: &str) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '\'' => i = find(&chars, i + 1, '\''),
+            '"' => i = find_closing_double_quote(&chars, i + 1),
+            '$' if chars.get(i + 1) == Some(&'{') => i = find_closing_brace(&chars, i + 2),
+            '{' => {
+                if let Some((end, alternatives)) = parse_brace(&chars, i) {
+                    let prefix: String = chars[..i].iter().collect();
+                    let suffix: String = chars[end + 1..].iter().collect();
+
+                    // the suffix can contain more braces, and so can every alternative
+                    return alternatives
+                        .iter()
+                        .flat_map(|alternative| {
+                            expand_braces(&format!("{}{}{}", prefix, alternative, suffix))
+                        })
+                        .collect();
+                }
+            }
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    vec![String::from(word)]
+}
+
+// Parses the brace starting at `start`, returns the index of its closing brace and
+// the alternatives. A brace without a top level "," that isn't a sequence is kept as is.
+fn parse_brace(chars: &[char], start: usize) -> Option<(usize, Vec<String>)> {
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut i = start + 1;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '\'' => i = find(chars, i + 1, '\''),
+            '"' => i = find_closing_double_quote(chars, i + 1),
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            '}' => break,
+            ',' if depth == 0 => commas.push(i),
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    if i >= chars.len() {
+        return None;
+    }
+
+    let inner = |from: usize, to: usize| -> String { chars[from..to].iter().collect() };
+
+    if commas.is_empty() {
+        return sequence(&inner(start + 1, i)).map(|items| (i, items));
+    }
+
+    let bounds: Vec<usize> = std::iter::once(start)
+        .chain(commas)
+        .chain(std::iter::once(i))
+        .collect();
+    let alternatives = bounds
+        .windows(2)
+        .map(|bound| inner(bound[0] + 1, bound[1]))
+        .collect();
+
+    Some((i, alternatives))
+}
+
+// `{1..10}`, `{01..10..2}`, `{10..1}` or `{a..e..2}`. Numbers with a leading zero
+// pad every number to the same width.
+fn sequence(inner: &str) -> Option<Vec<String>> {
+    let parts: Vec<&str> = inner.split("..").collect();
+    let (from, to, step) = match parts.as_slice() {
+        [from, to] => (*from, *to, 1),
+        [from, to, step] => (*from, *to, step.parse::<i64>().ok()?),
+        _ => return None,
+    };
+    let step = step.unsigned_abs().max(1) as usize;
+
+    if let (Ok(start), Ok(end)) = (from.parse::<i64>(), to.parse::<i64>()) {
+        let width = match is_zero_padded(from) || is_zero_padded(to) {
+            true => from.len().max(to.len()),
+            false => 0,
+        };
+
+        return Some(
+            range(start, end, step)
+                .map(|number| format!("{:0width$}", number, width = width))
+                .collect(),
+        );
+    }
+
+    match (single_char(from)?, single_char(to)?) {
+        (start, end) if start.is_ascii_alphabetic() && end.is_ascii_alphabetic() => Some(
+            range(start as i64, end as i64, step)
+                .map(|code| (code as u8 as char).to_string())
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+// from `start` to `end` inclusive, counting down when `end` is smaller
+fn range(start: i64, end: i64, step: usize) -> Box<dyn Iterator<Item = i64>> {
+    match start <= end {
+        true => Box::new((start..=end).step_by(step)),
+        false => Box::new((end..=start).rev().step_by(step)),
+    }
+}
+
+fn is_zero_padded(number: &str) -> bool {
+    let digits = number.trim_start_matches('-');
+    digits.len() > 1 && digits.starts_with('0')
+}
+
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(char), None) => Some(char),
+        _ => None,
+    }
+}
+
+// index of the char, or the end when it's missing
+fn find(chars: &[char], start: usize, char: char) -> usize {
+    (start..chars.len())
+        .find(|i| chars[*i] == char)
+        .unwrap_or(chars.len())
+}
+
+fn find_closing_double_quote(chars: &[char], start: usize) -> usize {
+    let mut i = start;
+
+    while i < chars.len() && chars[i] != '"' {
+        if chars[i] == '\\' {
+            i += 1;
+        }
+
+        i += 1;
+    }
+
+    i
+}
+
+// index of the "}" that closes a "${", nested braces are skipped
+fn find_closing_brace(chars: &[char], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' if depth == 0 => return i,
+            '}' => depth -= 1,
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    chars.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(word: &str) -> Vec<String> {
+        expand_braces(word)
+    }
+
+    #[test]
+    fn test_alternatives() {
+        assert_eq!(expand("proj/{src,tests}"), vec!["proj/src", "proj/tests"]);
+        assert_eq!(expand("{a,b}{1,2}"), vec!["a1", "a2", "b1", "b2"]);
+        assert_eq!(expand("a{b,c{d,e}}f"), vec!["abf", "acdf", "acef"]);
+        assert_eq!(expand("x{,y}"), vec!["x", "xy"]);
+    }
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(expand("{1..4}"), vec!["1", "2", "3", "4"]);
+        assert_eq!(expand("{3..1}"), vec!["3", "2", "1"]);
+        assert_eq!(expand("{01..10..3}"), vec!["01", "04", "07", "10"]);
+        assert_eq!(expand("{-1..1}"), vec!["-1", "0", "1"]);
+        assert_eq!(expand("{a..e..2}"), vec!["a", "c", "e"]);
+        assert_eq!(expand("{c..a}"), vec!["c", "b", "a"]);
+        assert_eq!(expand("f{1..2}.txt"), vec!["f1.txt", "f2.txt"]);
+    }
+
+    #[test]
+    fn test_words_without_expansion() {
+        let words = vec![
+            "{}",
+            "{a}",
+            "{a",
+            "a}",
+            "{1..a}",
+            "{ab..c}",
+            "'{a,b}'",
+            "\"{a,b}\"",
+            "\\{a,b}",
+            "${A:-{a,b}}",
+        ];
+
+        for word in words {
+            assert_eq!(expand(word), vec![word], "expanding {}", word);
+        }
+    }
+
+    #[test]
+    fn test_quotes_are_kept_for_later_expansions() {
+        assert_eq!(expand("{'a b',c}"), vec!["'a b'", "c"]);
+        assert_eq!(expand("\"$A\"{1,2}"), vec!["\"$A\"1", "\"$A\"2"]);
+    }
+}
@@ -0,0 +1,183 @@
+use regex::Regex;
+
+use crate::builtin::test;
+use crate::executor::expansion::{self, ExpansionError};
+use crate::parser::ast::LogicalExpressionOp;
+use crate::parser::condition::Condition;
+use crate::pattern::Pattern;
+use crate::shell_state::ShellState;
+use crate::variable::Value;
+
+// Evaluates a `[[ ... ]]` condition. Operands are expanded without word splitting and
+// pathname expansion, the right side of `==` and `!=` is a pattern and of `=~` a regex.
+// `&&` and `||` only evaluate their right side when it's needed, like in a command list.
+pub fn evaluate(
+    condition: &Condition,
+    shell_state: &mut ShellState,
+) -> Result<bool, ExpansionError> {
+    match condition {
+        Condition::Word(word) => Ok(!expand(word, shell_state)?.is_empty()),
+        Condition::Unary { op, operand } => {
+            let operand = expand(operand, shell_state)?;
+            Ok(test::unary_test(op, &operand, shell_state))
+        }
+        Condition::Binary { left, op, right } => {
+            let left = expand(left, shell_state)?;
+            evaluate_binary(&left, op, right, shell_state)
+        }
+        Condition::Not(condition) => Ok(!evaluate(condition, shell_state)?),
+        Condition::Logical { op, left, right } => {
+            let left = evaluate(left, shell_state)?;
+
+            match op {
+                LogicalExpressionOp::And if !left => Ok(false),
+                LogicalExpressionOp::Or if left => Ok(true),
+                _ => evaluate(right, shell_state),
+            }
+        }
+    }
+}
+
+fn evaluate_binary(
+    left: &str,
+    op: &str,
+    right: &str,
+    shell_state: &mut ShellState,
+) -> Result<bool, ExpansionError> {
+    match op {
+        "=" | "==" | "!=" => {
+            let pattern = Pattern::new(&expansion::expand_word(right, shell_state)?.pattern);
+            Ok(pattern.matches(left) == (op != "!="))
+        }
+        "=~" => {
+            let regex = expansion::expand_regex(right, shell_state)?;
+            regex_matches(left, &regex, shell_state)
+        }
+        // integer operands are arithmetic expressions, eg: `[[ $A -gt B + 1 ]]`
+        "-eq" | "-ne" | "-lt" | "-le" | "-gt" | "-ge" => {
+            let left = expansion::evaluate_arithmetic(left, shell_state)?;
+            let right = expansion::evaluate_arithmetic(right, shell_state)?;
+            test::binary_test(&left.to_string(), op, &right.to_string()).map_err(ExpansionError)
+        }
+        op => {
+            let right = expand(right, shell_state)?;
+            test::binary_test(left, op, &right).map_err(ExpansionError)
+        }
+    }
+}
+
+// The match and its groups are stored in BASH_REMATCH, groups that didn't match are empty
+fn regex_matches(
+    text: &str,
+    regex: &str,
+    shell_state: &mut ShellState,
+) -> Result<bool, ExpansionError> {
+    let regex = Regex::new(regex)
+        .map_err(|_| ExpansionError(format!("[[: {}: invalid regular expression", regex)))?;
+
+    let matches: Vec<String> = match regex.captures(text) {
+        Some(captures) => captures
+            .iter()
+            .map(|group| group.map_or(String::new(), |group| String::from(group.as_str())))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let is_match = !matches.is_empty();
+    shell_state
+        .variables
+        .insert(String::from("BASH_REMATCH"), Value::indexed(matches));
+    Ok(is_match)
+}
+
+fn expand(word: &str, shell_state: &mut ShellState) -> Result<String, ExpansionError> {
+    Ok(expansion::expand_word(word, shell_state)?.text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::condition::parse_condition;
+    use crate::parser::word;
+
+    fn check(line: &str, shell_state: &mut ShellState) -> Result<bool, ExpansionError> {
+        let condition = parse_condition(&word::split_words(line)).unwrap();
+        evaluate(&condition, shell_state)
+    }
+
+    fn state() -> ShellState {
+        let mut state = ShellState::default();
+        state
+            .set_variable("FILE", String::from("notes.md"));
+        state
+            .set_variable("SPACED", String::from("a  b"));
+        state
+    }
+
+    #[test]
+    fn test_patterns() {
+        let mut state = state();
+
+        let expected = vec![
+            ("$FILE == *.md", true),
+            ("$FILE == '*.md'", false),
+            ("$FILE != *.txt", true),
+            ("$FILE = notes.[mt][dx]*", true),
+            ("$SPACED == 'a  b'", true),
+            ("-n $SPACED", true),
+            ("-z $UNSET", true),
+            ("$UNSET", false),
+        ];
+
+        for (line, result) in expected {
+            assert_eq!(check(line, &mut state), Ok(result), "[[ {} ]]", line);
+        }
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        let mut state = state();
+
+        let expected = vec![
+            ("a && ! ''", true),
+            ("'' || a", true),
+            ("! ( a && '' )", true),
+            ("1 -lt 2 && FILE < z", true),
+            ("2 -eq 1+1", true),
+        ];
+
+        for (line, result) in expected {
+            assert_eq!(check(line, &mut state), Ok(result), "[[ {} ]]", line);
+        }
+
+        // the right side is not evaluated, so the error isn't either
+        assert_eq!(check("'' && ${A:?unset}", &mut state), Ok(false));
+    }
+
+    #[test]
+    fn test_regex() {
+        let mut state = state();
+
+        assert_eq!(
+            check("$FILE =~ ^([a-z]+)\\.(md|txt)$", &mut state),
+            Ok(true)
+        );
+        assert_eq!(
+            state.array_parameter("BASH_REMATCH"),
+            Some(Value::indexed(vec![
+                String::from("notes.md"),
+                String::from("notes"),
+                String::from("md")
+            ]))
+        );
+
+        assert_eq!(check("a.b =~ 'a.b'", &mut state), Ok(true));
+        assert_eq!(check("axb =~ 'a.b'", &mut state), Ok(false));
+        assert_eq!(
+            state.array_parameter("BASH_REMATCH"),
+            Some(Value::indexed(Vec::new()))
+        );
+
+        assert!(check("a =~ (x", &mut state).is_err());
+    }
+}
@@ -0,0 +1,548 @@
+use crate::executor::arithmetic::{self, ArithmeticError};
+use crate::executor::{brace, glob, parameter};
+use crate::parser::word::{self, WordPart};
+use crate::pattern;
+use crate::shell_state::ShellState;
+
+// An expansion that failed, the command it belongs to is not executed
+#[derive(Debug, PartialEq)]
+pub struct ExpansionError(pub String);
+
+// A word after parameter expansion and word splitting.
+// The pattern is the same text with quoted characters escaped, so only
+// unquoted characters act as glob characters.
+#[derive(Debug, Default)]
+pub struct Field {
+    pub text: String,
+    pub pattern: String,
+    pub quoted: bool,
+}
+
+// Expands the words of a command in to the fields the command runs with:
+// brace expansion, tilde expansion, parameter expansion, word splitting,
+// pathname expansion and quote removal
+pub fn expand_words(
+    words: &[String],
+    shell_state: &mut ShellState,
+) -> Result<Vec<String>, ExpansionError> {
+    let mut result = Vec::new();
+
+    for word in words.iter().flat_map(|word| brace::expand_braces(word)) {
+        for field in split_fields(&word::parse_word(&word), shell_state)? {
+            result.extend(expand_field_pathname(field, shell_state)?);
+        }
+    }
+
+    Ok(result)
+}
+
+// Expands a word to a single field without word splitting and pathname expansion,
+// as used for assignments and the words inside `${...}`
+pub fn expand_word(word: &str, shell_state: &mut ShellState) -> Result<Field, ExpansionError> {
+    let mut field = Field::default();
+
+    for part in word::parse_word(word) {
+        push_part(&mut field, &part, shell_state)?;
+    }
+
+    Ok(field)
+}
+
+// Expands a word to a regular expression, quoted parts match literally
+// >> expand_regex("'a.b'.*")
+// "a\\.b.*"
+pub fn expand_regex(word: &str, shell_state: &mut ShellState) -> Result<String, ExpansionError> {
+    let mut result = String::new();
+
+    for part in word::parse_word(word) {
+        let mut field = Field::default();
+        push_part(&mut field, &part, shell_state)?;
+
+        match field.quoted {
+            true => result.push_str(&regex::escape(&field.text)),
+            false => result.push_str(&field.text),
+        }
+    }
+
+    Ok(result)
+}
+
+// Expands the body of a here-document, the result is used as is
+pub fn expand_here_doc(body: &str, shell_state: &mut ShellState) -> Result<String, ExpansionError> {
+    let mut field = Field::default();
+
+    for part in word::parse_here_doc(body) {
+        push_part(&mut field, &part, shell_state)?;
+    }
+
+    Ok(field.text)
+}
+
+fn split_fields(
+    parts: &[WordPart],
+    shell_state: &mut ShellState,
+) -> Result<Vec<Field>, ExpansionError> {
+    let mut fields = Vec::new();
+    let mut field = Field::default();
+    // `"${A[@]}"` of an empty array is no field, even though it's quoted
+    let mut is_empty_list = false;
+
+    for part in parts {
+        match part {
+            // unquoted values are split on the chars of IFS in to separate fields,
+            // every element of `$@` or an array is split on its own
+            WordPart::Parameter {
+                expression,
+                quoted: false,
+            } => {
+                let values = match parameter::expand_fields(expression, shell_state)? {
+                    Some(values) => values,
+                    None => vec![parameter::expand(expression, shell_state)?],
+                };
+                let ifs = shell_state
+                    .parameter("IFS")
+                    .unwrap_or_else(|| String::from(DEFAULT_IFS));
+
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        push_field(&mut fields, std::mem::take(&mut field));
+                    }
+
+                    let pieces = split_value(value, &ifs);
+                    for (j, (piece, delimited)) in pieces.into_iter().enumerate() {
+                        match (j, delimited) {
+                            (0, _) => {}
+                            // `a::b` has an empty field between the colons
+                            (_, true) => fields.push(std::mem::take(&mut field)),
+                            (_, false) => push_field(&mut fields, std::mem::take(&mut field)),
+                        }
+
+                        field.text.push_str(&piece);
+                        field.pattern.push_str(&piece);
+                    }
+                }
+            }
+            WordPart::Parameter {
+                expression,
+                quoted: true,
+            } => match parameter::expand_fields(expression, shell_state)? {
+                Some(values) => {
+                    is_empty_list |= values.is_empty();
+
+                    for (i, value) in values.iter().enumerate() {
+                        if i > 0 {
+                            push_field(&mut fields, std::mem::take(&mut field));
+                        }
+
+                        push_quoted(&mut field, value);
+                    }
+                }
+                None => push_part(&mut field, part, shell_state)?,
+            },
+            part => push_part(&mut field, part, shell_state)?,
+        }
+    }
+
+    if !(is_empty_list && field.text.is_empty()) {
+        push_field(&mut fields, field);
+    }
+    Ok(fields)
+}
+
+fn push_part(
+    field: &mut Field,
+    part: &WordPart,
+    shell_state: &mut ShellState,
+) -> Result<(), ExpansionError> {
+    match part {
+        WordPart::Literal(text) => {
+            field.text.push_str(text);
+            field.pattern.push_str(text);
+        }
+        WordPart::Quoted(text) => push_quoted(field, text),
+        // the directory is used as is, it's not split or matched as a pattern
+        WordPart::Tilde(user) => match tilde_value(user, shell_state) {
+            Some(dir) => push_quoted(field, &dir),
+            None => push_quoted(field, &format!("~{}", user)),
+        },
+        // the expression is expanded first, eg: `$(( $A + 1 ))`. Like bash an error drops
+        // the rest of the line.
+        WordPart::Arithmetic(expression) => {
+            let value = evaluate_arithmetic(expression, shell_state).inspect_err(|_| {
+                shell_state.aborted = true;
+            })?;
+            field.text.push_str(&value.to_string());
+            field.pattern.push_str(&value.to_string());
+        }
+        WordPart::Parameter { expression, quoted } => {
+            let value = parameter::expand(expression, shell_state)?;
+
+            match quoted {
+                true => push_quoted(field, &value),
+                false => {
+                    field.text.push_str(&value);
+                    field.pattern.push_str(&value);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn evaluate_arithmetic(
+    expression: &str,
+    shell_state: &mut ShellState,
+) -> Result<i64, ExpansionError> {
+    let expression = expand_word(expression, shell_state)?.text;
+    arithmetic::evaluate(&expression, shell_state)
+        .map_err(|ArithmeticError(message)| ExpansionError(message))
+}
+
+fn push_quoted(field: &mut Field, text: &str) {
+    field.text.push_str(text);
+    field.pattern.push_str(&pattern::escape(text));
+    field.quoted = true;
+}
+
+// empty fields are removed unless they were quoted, eg: `""`
+fn push_field(fields: &mut Vec<Field>, field: Field) {
+    if !field.text.is_empty() || field.quoted {
+        fields.push(field);
+    }
+}
+
+// IFS when it's unset, an empty IFS doesn't split at all
+const DEFAULT_IFS: &str = " \t\n";
+
+// Splits a value on the chars of IFS like POSIX: whitespace of IFS around the pieces is
+// removed, other IFS chars each end a piece even if it's empty. Every piece tells if it
+// follows such a char.
+// >> split_value(" a :b::c", " :")
+// vec![("", false), ("a", false), ("b", true), ("", true), ("c", true)]
+fn split_value(value: &str, ifs: &str) -> Vec<(String, bool)> {
+    let is_space = |char: char| ifs.contains(char) && DEFAULT_IFS.contains(char);
+    let is_delimiter = |char: char| ifs.contains(char) && !DEFAULT_IFS.contains(char);
+
+    let mut pieces = vec![(String::new(), false)];
+    let mut chars = value.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        if !is_space(char) && !is_delimiter(char) {
+            pieces.last_mut().unwrap().0.push(char);
+            continue;
+        }
+
+        let mut delimited = is_delimiter(char);
+        while let Some(&next) = chars.peek() {
+            match next {
+                next if is_space(next) => {}
+                next if is_delimiter(next) && !delimited => delimited = true,
+                _ => break,
+            }
+            chars.next();
+        }
+
+        pieces.push((String::new(), delimited));
+    }
+
+    pieces
+}
+
+fn expand_field_pathname(
+    field: Field,
+    shell_state: &ShellState,
+) -> Result<Vec<String>, ExpansionError> {
+    if shell_state.options.noglob || !pattern::has_special_chars(&field.pattern) {
+        return Ok(vec![field.text]);
+    }
+
+    let paths = glob::expand_pathname(&field.pattern, shell_state);
+    if !paths.is_empty() {
+        return Ok(paths);
+    }
+
+    if shell_state.shopt.failglob {
+        return Err(ExpansionError(format!("no match: {}", field.text)));
+    }
+
+    match shell_state.shopt.nullglob {
+        true => Ok(Vec::new()),
+        false => Ok(vec![field.text]),
+    }
+}
+
+// `~` is HOME, `~+` the current directory and `~-` the previous one.
+// `~user` is the home of another user, see `home_of`.
+fn tilde_value(user: &str, shell_state: &ShellState) -> Option<String> {
+    match user {
+        "" => shell_state.parameter("HOME"),
+        "+" => shell_state.parameter("PWD"),
+        "-" => shell_state.parameter("OLDPWD"),
+        user if shell_state.parameter("USER").as_deref() == Some(user) => {
+            shell_state.parameter("HOME")
+        }
+        user => home_of(user, shell_state),
+    }
+}
+
+// natively the home of a user is the 6th field of its line in /etc/passwd
+// >> home_of("root")
+// Some("/root")
+#[cfg(unix)]
+fn home_of(user: &str, shell_state: &ShellState) -> Option<String> {
+    let passwd = shell_state.fs.read(std::path::Path::new("/etc/passwd")).ok()?;
+
+    String::from_utf8_lossy(&passwd).lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields.as_slice() {
+            [name, _, _, _, _, home, ..] if *name == user => Some(String::from(*home)),
+            _ => None,
+        }
+    })
+}
+
+// the browser has no users, a user only has a home when "/home/<user>" exists
+#[cfg(not(unix))]
+fn home_of(user: &str, shell_state: &ShellState) -> Option<String> {
+    let home = format!("/home/{}", user);
+    match shell_state.fs.is_dir(&shell_state.resolve_path(&home)) {
+        true => Some(home),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exit_status::ExitStatus;
+    use crate::filesystem::{FileSystem, MemoryFileSystem};
+    use std::path::Path;
+    use std::rc::Rc;
+
+    fn expand(words: &[&str], state: &ShellState) -> Result<Vec<String>, ExpansionError> {
+        let words: Vec<String> = words.iter().map(|word| String::from(*word)).collect();
+        expand_words(&words, &mut state.clone())
+    }
+
+    fn state_with_files(paths: &[&str]) -> ShellState {
+        let fs = MemoryFileSystem::default();
+        for path in paths {
+            fs.write(Path::new(path), b"").unwrap();
+        }
+
+        ShellState::new(Rc::new(fs), String::from("/"))
+    }
+
+    #[test]
+    fn test_expand_parameters() {
+        let mut state = ShellState::default();
+        state.output.status = ExitStatus::NOT_FOUND;
+        state.pipe_status = vec![ExitStatus::SUCCESS, ExitStatus::FAILURE];
+
+        let expected = vec![
+            ("foo", "foo"),
+            ("$?", "127"),
+            ("${?}", "127"),
+            ("status:$?!", "status:127!"),
+            ("$PIPESTATUS", "0"),
+            ("${PIPESTATUS[1]}", "1"),
+            ("\"${PIPESTATUS[*]}\"", "0 1"),
+            ("${PIPESTATUS[-1]}", "1"),
+            ("${PIPESTATUS[5]}x", "x"),
+            ("$UNKNOWN-a", "-a"),
+            ("$", "$"),
+            ("a$-", "a"),
+        ];
+
+        for (word, result) in expected {
+            assert_eq!(
+                expand(&[word], &state),
+                Ok(vec![String::from(result)]),
+                "expanding {}",
+                word
+            );
+        }
+    }
+
+    #[test]
+    fn test_quote_removal() {
+        let state = ShellState::default();
+
+        assert_eq!(
+            expand(&["'a b'", "\"c d\"", "e\\ f", "\"\"", "'it'\\''s'"], &state),
+            Ok(vec![
+                String::from("a b"),
+                String::from("c d"),
+                String::from("e f"),
+                String::new(),
+                String::from("it's")
+            ])
+        );
+    }
+
+    #[test]
+    fn test_unquoted_parameters_are_split() {
+        let state = ShellState {
+            pipe_status: vec![ExitStatus::SUCCESS, ExitStatus::FAILURE],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            expand(&["${PIPESTATUS[@]}"], &state),
+            Ok(vec![String::from("0"), String::from("1")])
+        );
+        assert_eq!(
+            expand(&["\"${PIPESTATUS[*]}\""], &state),
+            Ok(vec![String::from("0 1")])
+        );
+        assert_eq!(expand(&["$EMPTY"], &state), Ok(vec![]));
+        assert_eq!(expand(&["\"$EMPTY\""], &state), Ok(vec![String::new()]));
+    }
+
+    #[test]
+    fn test_split_on_ifs() {
+        let mut state = ShellState::default();
+        state.set_variable("X", String::from(" a  b\t"));
+        state.set_variable("Y", String::from("p:q r"));
+        state.set_variable("Z", String::from(":a :: b:"));
+        state.positional_parameters = vec![String::from("1:2"), String::from("3 4")];
+        let fields = |fields: &[&str]| Ok(fields.iter().map(|f| String::from(*f)).collect());
+
+        assert_eq!(expand(&["$X"], &state), fields(&["a", "b"]));
+        assert_eq!(expand(&["-$X-"], &state), fields(&["-", "a", "b", "-"]));
+        assert_eq!(expand(&["$Y"], &state), fields(&["p:q", "r"]));
+
+        state.set_variable("IFS", String::from(":"));
+        assert_eq!(expand(&["$X"], &state), fields(&[" a  b\t"]));
+        assert_eq!(expand(&["$Y"], &state), fields(&["p", "q r"]));
+        assert_eq!(expand(&["$@"], &state), fields(&["1", "2", "3 4"]));
+
+        state.set_variable("IFS", String::from(" :"));
+        assert_eq!(expand(&["$Y"], &state), fields(&["p", "q", "r"]));
+        assert_eq!(expand(&["$Z"], &state), fields(&["", "a", "", "b"]));
+
+        state.set_variable("IFS", String::new());
+        assert_eq!(expand(&["$Y"], &state), fields(&["p:q r"]));
+    }
+
+    #[test]
+    fn test_globs() {
+        let state = state_with_files(&["/a.md", "/b.md"]);
+
+        assert_eq!(
+            expand(&["*.md"], &state),
+            Ok(vec![String::from("a.md"), String::from("b.md")])
+        );
+        assert_eq!(expand(&["'*.md'"], &state), Ok(vec![String::from("*.md")]));
+        assert_eq!(
+            expand(&["\"*\".md"], &state),
+            Ok(vec![String::from("*.md")])
+        );
+        assert_eq!(expand(&["\\*.md"], &state), Ok(vec![String::from("*.md")]));
+        assert_eq!(expand(&["*.rs"], &state), Ok(vec![String::from("*.rs")]));
+    }
+
+    #[test]
+    fn test_braces_before_globs() {
+        let state = state_with_files(&["/a.md", "/b.rs", "/c.txt"]);
+
+        assert_eq!(
+            expand(&["*.{md,rs}", "x{1..2}"], &state),
+            Ok(vec![
+                String::from("a.md"),
+                String::from("b.rs"),
+                String::from("x1"),
+                String::from("x2")
+            ])
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut state = ShellState::default();
+        state.set_variable("A", String::from("4"));
+
+        assert_eq!(
+            expand(&["$(( A * 2 ))", "\"$(($A + 1))\"", "x$((1<<3))"], &state),
+            Ok(vec![
+                String::from("8"),
+                String::from("5"),
+                String::from("x8")
+            ])
+        );
+        assert_eq!(
+            expand(&["$((A / 0))"], &state),
+            Err(ExpansionError(String::from("A / 0: division by 0")))
+        );
+    }
+
+    #[test]
+    fn test_tilde() {
+        let mut state = state_with_files(&[]);
+        state.set_variable("HOME", String::from("/home/me"));
+        state.set_variable("USER", String::from("me"));
+        state.set_variable("OLDPWD", String::from("/tmp"));
+        state.current_dir = String::from("/work");
+
+        let expected = vec![
+            ("~", "/home/me"),
+            ("~/a b", "/home/me/a b"),
+            ("~me/x", "/home/me/x"),
+            ("~+", "/work"),
+            ("~-/y", "/tmp/y"),
+            ("~nobody", "~nobody"),
+            ("'~'", "~"),
+            ("a~", "a~"),
+        ];
+
+        for (word, result) in expected {
+            assert_eq!(
+                expand(&[word], &state),
+                Ok(vec![String::from(result)]),
+                "expanding {}",
+                word
+            );
+        }
+
+        assert_eq!(
+            expand(&["~/{a,b}"], &state),
+            Ok(vec![String::from("/home/me/a"), String::from("/home/me/b")])
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tilde_of_users() {
+        let state = ShellState::default();
+        state.fs.create_dir(Path::new("/etc")).unwrap();
+        state
+            .fs
+            .write(
+                Path::new("/etc/passwd"),
+                b"root:x:0:0:root:/root:/bin/bash\nbob:x:1000:1000:Bob:/users/bob:/bin/sh\n",
+            )
+            .unwrap();
+
+        assert_eq!(expand(&["~root/a"], &state), Ok(vec![String::from("/root/a")]));
+        assert_eq!(expand(&["~bob"], &state), Ok(vec![String::from("/users/bob")]));
+        assert_eq!(expand(&["~nobody"], &state), Ok(vec![String::from("~nobody")]));
+    }
+
+    #[test]
+    fn test_glob_options() {
+        let mut state = state_with_files(&["/a.md"]);
+
+        state.shopt.nullglob = true;
+        assert_eq!(expand(&["*.rs", "a"], &state), Ok(vec![String::from("a")]));
+
+        state.shopt.failglob = true;
+        assert_eq!(
+            expand(&["*.rs"], &state),
+            Err(ExpansionError(String::from("no match: *.rs")))
+        );
+
+        state.options.noglob = true;
+        assert_eq!(expand(&["*.md", "*.rs"], &state), Ok(vec![String::from("*.md"), String::from("*.rs")]));
+    }
+}
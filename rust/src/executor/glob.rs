@@ -0,0 +1,252 @@
+use crate::filesystem::FileType;
+use crate::pattern::{self, Pattern};
+use crate::shell_state::ShellState;
+
+// A path that matched so far, as the user typed it and as it's found in the filesystem
+#[derive(Debug, Clone)]
+struct Candidate {
+    display: String,
+    is_dir: bool,
+}
+
+// Expands a pattern to the sorted paths it matches in the filesystem of the shell.
+// Every "/" separated part is matched against the entries of a directory, files
+// starting with a "." are only matched when the part starts with a "." or dotglob is set.
+// >> expand_pathname("src/*.rs")
+// vec!["src/lib.rs", "src/main.rs"]
+pub fn expand_pathname(pattern: &str, shell_state: &ShellState) -> Vec<String> {
+    let mut candidates = vec![Candidate {
+        display: String::from(if pattern.starts_with('/') { "/" } else { "" }),
+        is_dir: true,
+    }];
+
+    let segments: Vec<&str> = pattern.split('/').collect();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let is_last = i == segments.len() - 1;
+
+        candidates = match *segment {
+            // a trailing slash only matches directories
+            "" if is_last && i > 0 => candidates
+                .into_iter()
+                .filter(|candidate| candidate.is_dir)
+                .map(|candidate| join(&candidate, "", true))
+                .collect(),
+            "" => candidates,
+            // like bash, a last "**" also matches the directory it's in, eg: "src/"
+            "**" if shell_state.shopt.globstar && is_last => candidates
+                .iter()
+                .filter(|candidate| candidate.is_dir)
+                .flat_map(|candidate| {
+                    let mut matches = vec![join(candidate, "", true)];
+                    matches.extend(descendants(candidate, true, shell_state));
+                    matches
+                })
+                .collect(),
+            "**" if shell_state.shopt.globstar => candidates
+                .iter()
+                .flat_map(|candidate| descendants(candidate, false, shell_state))
+                .collect(),
+            segment if pattern::has_special_chars(segment) => {
+                let pattern = Pattern::new(segment);
+                candidates
+                    .iter()
+                    .flat_map(|candidate| matching_entries(candidate, &pattern, shell_state))
+                    .collect()
+            }
+            segment => candidates
+                .iter()
+                .filter_map(|candidate| {
+                    let name = pattern::unescape(segment);
+                    let path = shell_state.resolve_path(&join(candidate, &name, false).display);
+                    let metadata = shell_state.fs.metadata(&path).ok()?;
+                    Some(join(
+                        candidate,
+                        &name,
+                        metadata.file_type == FileType::Directory,
+                    ))
+                })
+                .collect(),
+        };
+    }
+
+    let mut paths: Vec<String> = candidates
+        .into_iter()
+        .map(|candidate| candidate.display)
+        .filter(|display| !display.is_empty())
+        .collect();
+
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+fn join(candidate: &Candidate, name: &str, is_dir: bool) -> Candidate {
+    let display = match candidate.display.as_str() {
+        "" => String::from(name),
+        display if display.ends_with('/') => format!("{}{}", display, name),
+        display => format!("{}/{}", display, name),
+    };
+
+    Candidate { display, is_dir }
+}
+
+// entries of the directory of a candidate as candidates
+fn entries(candidate: &Candidate, shell_state: &ShellState) -> Vec<Candidate> {
+    if !candidate.is_dir {
+        return Vec::new();
+    }
+
+    let dir = shell_state.resolve_path(&candidate.display);
+    let names = shell_state.fs.read_dir(&dir).unwrap_or_default();
+
+    names
+        .iter()
+        .map(|name| join(candidate, name, shell_state.fs.is_dir(&dir.join(name))))
+        .collect()
+}
+
+fn is_hidden(candidate: &Candidate) -> bool {
+    candidate
+        .display
+        .rsplit('/')
+        .next()
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+fn matching_entries(
+    candidate: &Candidate,
+    pattern: &Pattern,
+    shell_state: &ShellState,
+) -> Vec<Candidate> {
+    let include_hidden = shell_state.shopt.dotglob || pattern.starts_with_dot();
+
+    entries(candidate, shell_state)
+        .into_iter()
+        .filter(|entry| include_hidden || !is_hidden(entry))
+        .filter(|entry| {
+            let name = entry.display.rsplit('/').next().unwrap_or("");
+            pattern.matches(name)
+        })
+        .collect()
+}
+
+// "**" matches the directory itself and every directory below it,
+// as last part of a pattern it also matches the files
+fn descendants(
+    candidate: &Candidate,
+    include_files: bool,
+    shell_state: &ShellState,
+) -> Vec<Candidate> {
+    let mut result = Vec::new();
+
+    if !include_files {
+        result.push(candidate.clone());
+    }
+
+    for entry in entries(candidate, shell_state) {
+        if is_hidden(&entry) && !shell_state.shopt.dotglob {
+            continue;
+        }
+
+        if entry.is_dir {
+            result.extend(descendants(&entry, include_files, shell_state));
+            if include_files {
+                result.push(entry);
+            }
+        } else if include_files {
+            result.push(entry);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{FileSystem, MemoryFileSystem};
+    use std::path::Path;
+    use std::rc::Rc;
+
+    fn state_with_files(paths: &[&str]) -> ShellState {
+        let fs = MemoryFileSystem::default();
+
+        for path in paths {
+            let path = Path::new(path);
+            fs.create_dir_all(path.parent().unwrap());
+            fs.write(path, b"").unwrap();
+        }
+
+        ShellState::new(Rc::new(fs), String::from("/"))
+    }
+
+    fn expand(pattern: &str, state: &ShellState) -> Vec<String> {
+        expand_pathname(pattern, state)
+    }
+
+    #[test]
+    fn test_star() {
+        let state = state_with_files(&["/b.md", "/a.md", "/c.txt", "/.hidden.md"]);
+
+        assert_eq!(expand("*.md", &state), vec!["a.md", "b.md"]);
+        assert_eq!(expand("/*.txt", &state), vec!["/c.txt"]);
+        assert_eq!(expand("*.rs", &state), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_hidden_files() {
+        let mut state = state_with_files(&["/a.md", "/.hidden.md"]);
+
+        assert_eq!(expand(".*.md", &state), vec![".hidden.md"]);
+
+        state.shopt.dotglob = true;
+        assert_eq!(expand("*.md", &state), vec![".hidden.md", "a.md"]);
+    }
+
+    #[test]
+    fn test_question_mark_and_class() {
+        let state = state_with_files(&["/file1", "/file2", "/file10", "/filea"]);
+
+        assert_eq!(expand("file?", &state), vec!["file1", "file2", "filea"]);
+        assert_eq!(
+            expand("file[0-9]*", &state),
+            vec!["file1", "file10", "file2"]
+        );
+    }
+
+    #[test]
+    fn test_directories() {
+        let mut state = state_with_files(&["/src/a.rs", "/src/b/c.rs", "/tests/d.rs", "/README"]);
+        state.current_dir = String::from("/");
+
+        assert_eq!(expand("*/*.rs", &state), vec!["src/a.rs", "tests/d.rs"]);
+        assert_eq!(expand("*/", &state), vec!["src/", "tests/"]);
+        assert_eq!(expand("src/b/*", &state), vec!["src/b/c.rs"]);
+
+        state.current_dir = String::from("/src");
+        assert_eq!(expand("../*/d.rs", &state), vec!["../tests/d.rs"]);
+    }
+
+    #[test]
+    fn test_globstar() {
+        let mut state = state_with_files(&["/a.rs", "/src/b.rs", "/src/x/c.rs", "/src/.git/d.rs"]);
+
+        assert_eq!(expand("**/*.rs", &state), vec!["src/b.rs"]);
+
+        state.shopt.globstar = true;
+        assert_eq!(
+            expand("**/*.rs", &state),
+            vec!["a.rs", "src/b.rs", "src/x/c.rs"]
+        );
+        assert_eq!(
+            expand("src/**", &state),
+            vec!["src/", "src/b.rs", "src/x", "src/x/c.rs"]
+        );
+        assert_eq!(expand("src/x/**", &state), vec!["src/x/", "src/x/c.rs"]);
+        assert_eq!(
+            expand("**", &state),
+            vec!["a.rs", "src", "src/b.rs", "src/x", "src/x/c.rs"]
+        );
+    }
+}
@@ -1,104 +1,203 @@
+use crate::exit_status::ExitStatus;
+use crate::filesystem;
 use crate::shell_state::ShellState;
 use chrono::prelude::*;
 use chrono::{DateTime, Utc};
-use std::fs::{File, OpenOptions};
-use std::io::prelude::*;
-use std::io::Error;
+use std::io::{Error, ErrorKind};
 use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
 
 const HISTORY_PATH: &str = "history.txt";
 
 #[derive(Debug, PartialEq)]
 pub struct History {
-    input: String,
+    input: Vec<u8>,
     date: DateTime<Utc>,
 }
 
-fn get_path() -> PathBuf {
-    PathBuf::from(HISTORY_PATH)
+// the history is kept in HOME, wherever the shell is
+fn get_path(shell_state: &ShellState) -> PathBuf {
+    let home = shell_state
+        .parameter("HOME")
+        .unwrap_or_else(|| String::from("/"));
+    shell_state.resolve_path(&home).join(HISTORY_PATH)
 }
 
-fn read_file() -> Result<String, Error> {
-    let mut file = File::open(get_path())?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-
-    Ok(contents)
+// no history file yet is an empty history
+fn read_file(shell_state: &ShellState) -> Result<Vec<u8>, Error> {
+    match shell_state.fs.read(&get_path(shell_state)) {
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        result => result,
+    }
 }
 
-pub fn run(shell_state: &mut ShellState) {
-    let read_file = read_file().expect("Reading of history file failed");
-    let history_list: Vec<String> = read_file
-        .lines()
-        .map(|line| decode_line(&String::from(line)))
-        .map(format_history)
-        .collect();
+// Lists the history, a line that can't be decoded is reported and skipped
+pub fn run(shell_state: &mut ShellState) -> ExitStatus {
+    let contents = match read_file(shell_state) {
+        Ok(contents) => contents,
+        Err(error) => {
+            write_error(&error, shell_state);
+            return ExitStatus::FAILURE;
+        }
+    };
+
+    let mut status = ExitStatus::SUCCESS;
+    for (number, line) in contents.split(|byte| *byte == b'\n').enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+
+        match decode_line(line) {
+            Some(history) => shell_state.output.write_stdout(format_history(history)),
+            None => {
+                shell_state.output.write_stderr(format!(
+                    "history: {}: line {}: malformed entry\n",
+                    get_path(shell_state).display(),
+                    number + 1
+                ));
+                status = ExitStatus::FAILURE;
+            }
+        }
+    }
 
-    shell_state.output.set_stdout(history_list.join("\n"));
+    status
 }
 
-pub fn format_history(history: History) -> String {
+pub fn format_history(history: History) -> Vec<u8> {
     let History { input, date } = history;
 
-    format!("{} {}", date, input)
+    let mut line = format!("{} ", date).into_bytes();
+    line.extend(input);
+    line.push(b'\n');
+    line
 }
 
-fn decode_line(history: &String) -> History {
-    let result = history.split_once(";");
-
-    let (date, command) = result.unwrap();
-    let date = date.parse().unwrap();
+fn decode_line(history: &[u8]) -> Option<History> {
+    let separator = history.iter().position(|byte| *byte == b';')?;
+    let (date, command) = (&history[..separator], &history[separator + 1..]);
+    let date = std::str::from_utf8(date).ok()?.parse().ok()?;
 
-    History {
-        input: String::from(command),
-        date: Utc.timestamp(date, 0),
-    }
+    Some(History {
+        input: command.to_vec(),
+        date: Utc.timestamp_opt(date, 0).single()?,
+    })
 }
 
-pub fn append(cmd: &String) -> Result<(), Error> {
-    if !get_path().exists() {
-        File::create(HISTORY_PATH)?;
-    }
+pub fn append(cmd: &str, shell_state: &ShellState) -> Result<(), Error> {
+    let mut contents = read_file(shell_state)?;
+
+    let timestamp = shell_state
+        .clock
+        .time()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64);
+
+    contents.extend(encode_to_line(&timestamp, cmd).into_bytes());
+    contents.push(b'\n');
 
-    let timestamp = Utc::now().timestamp();
+    shell_state.fs.write(&get_path(shell_state), &contents)
+}
 
-    let mut file = OpenOptions::new()
-        .write(true)
-        .append(true)
-        .open(HISTORY_PATH)
-        .unwrap();
+// Records a line the user typed. The input of `read` and the body of a here-document
+// are no commands, they aren't recorded.
+pub fn record(line: &str, shell_state: &mut ShellState) {
+    let is_input = shell_state.suspension.is_some() || shell_state.is_reading_here_doc();
+    if is_input || line.trim().is_empty() {
+        return;
+    }
 
-    writeln!(file, "{}", encode_to_line(&timestamp, cmd))?;
+    if let Err(error) = append(line, shell_state) {
+        write_error(&error, shell_state);
+    }
+}
 
-    Ok(())
+fn write_error(error: &Error, shell_state: &mut ShellState) {
+    shell_state.output.write_stderr(format!(
+        "history: {}: {}\n",
+        get_path(shell_state).display(),
+        filesystem::describe_error(error)
+    ));
 }
 
 // Every line is expecting following format: "{timestamp};{command}"
-fn encode_to_line(timestamp: &i64, cmd: &String) -> String {
+fn encode_to_line(timestamp: &i64, cmd: &str) -> String {
     format!("{};{}", timestamp, cmd)
 }
 
 #[cfg(test)]
 mod history_tests {
     use super::*;
+    use std::path::Path;
 
     #[test]
     fn test_decode_line() {
         assert_eq!(
-            decode_line(&String::from("1626441404;echo eee")),
-            History {
-                input: String::from("echo eee"),
-                date: Utc.timestamp(1626441404, 0),
-            }
+            decode_line(b"1626441404;echo eee"),
+            Some(History {
+                input: b"echo eee".to_vec(),
+                date: Utc.timestamp_opt(1626441404, 0).unwrap(),
+            })
         );
+        assert_eq!(decode_line(b"echo eee"), None);
+        assert_eq!(decode_line(b"yesterday;echo eee"), None);
     }
 
     #[test]
     fn test_encode_line() {
         let timestamp: i64 = 1626441404;
         assert_eq!(
-            encode_to_line(&timestamp, &String::from("echo foo bar")),
+            encode_to_line(&timestamp, "echo foo bar"),
             String::from("1626441404;echo foo bar")
         );
     }
+
+    #[test]
+    fn test_run_without_history_file() {
+        let mut state = ShellState::default();
+
+        assert_eq!(run(&mut state), ExitStatus::SUCCESS);
+        assert_eq!(state.output.stdout(), b"");
+    }
+
+    #[test]
+    fn test_run_with_malformed_line() {
+        let mut state = ShellState::default();
+        state
+            .fs
+            .write(Path::new("/history.txt"), b"0;echo a\nbroken\n0;echo \xff\n")
+            .unwrap();
+
+        assert_eq!(run(&mut state), ExitStatus::FAILURE);
+        assert_eq!(
+            state.output.stdout(),
+            b"1970-01-01 00:00:00 UTC echo a\n1970-01-01 00:00:00 UTC echo \xff\n"
+        );
+        assert_eq!(
+            state.output.stderr(),
+            b"history: /history.txt: line 2: malformed entry\n"
+        );
+    }
+
+    #[test]
+    fn test_record() {
+        let mut state = ShellState::default();
+        state.fs.create_dir(Path::new("/home")).unwrap();
+        state.set_variable("HOME", String::from("/home"));
+
+        record("echo a", &mut state);
+        state.current_dir = String::from("/tmp");
+        record("echo b", &mut state);
+        record(" ", &mut state);
+
+        run(&mut state);
+
+        assert_eq!(
+            state.fs.read(Path::new("/home/history.txt")).unwrap(),
+            b"0;echo a\n0;echo b\n"
+        );
+        assert_eq!(
+            state.output.stdout(),
+            b"1970-01-01 00:00:00 UTC echo a\n1970-01-01 00:00:00 UTC echo b\n"
+        );
+    }
 }
@@ -1,190 +1,1547 @@
-use crate::{builtin, log};
+use crate::builtin::{self, CommandType};
+use crate::clock::Times;
 use crate::command::Command;
 use crate::exception::Exception;
+use crate::exit_status::ExitStatus;
+use crate::filesystem;
+use crate::parser::alias;
 use crate::parser::ast;
+use crate::parser::ast::{AstItem, CaseItem, CaseTerminator, LogicalExpressionOp};
+use crate::parser::here_doc::PendingHereDocs;
+use crate::parser::redirection::Redirection;
 use crate::parser::token;
-use crate::parser::token::Token;
-use crate::parser::token::Token::{And, Or, Raw};
-use crate::shell_state::ShellState;
-use std::convert::TryFrom;
+use crate::parser::word;
+use crate::pattern::Pattern;
+use crate::shell_state::{ShellOutput, ShellState};
+use crate::sink::Stream;
+use crate::variable::Value;
+use std::io;
+use std::mem;
+use std::path::PathBuf;
 
+pub mod arithmetic;
+pub mod assignment;
+mod brace;
+mod condition;
+mod expansion;
+mod glob;
+mod parameter;
+mod time;
 pub mod history;
+pub mod resume;
+pub mod trap;
 
-pub fn run(raw_line: &String, shell_state: &mut ShellState) -> Result<(), Exception> {
-    // history::append(raw_line).expect("History should be appendable");
-    let tokens = token::tokenize_raw_line(raw_line);
+use expansion::ExpansionError;
+pub use parameter::is_name;
+use resume::{Frame, Input};
 
-    let a = ast::parse_to_ast(tokens.as_slice());
+// commands whose arguments can be assignments, eg: `declare -a arr=(a b)`
+const DECLARATION_COMMANDS: [&str; 2] = ["declare", "typeset"];
+
+// Output of the executed line is appended to `shell_state.output`,
+// the host drains it from there.
+// A line with here-documents runs once the lines with their bodies are passed in too,
+// a compound command that isn't closed runs once the line that closes it is passed in.
+// A line that waits for input of `read` continues with the next line passed in,
+// see `resume::provide_input`.
+pub fn run(raw_line: &str, shell_state: &mut ShellState) -> Result<(), Exception> {
+    if shell_state.exit.is_some() {
+        return Ok(());
+    }
+
+    if shell_state.suspension.is_some() {
+        return resume::provide_input(Input::Line(String::from(raw_line)), shell_state);
+    }
+
+    let mut pending = match shell_state.pending_here_docs.take() {
+        Some(mut pending) => {
+            pending.push_line(raw_line);
+            pending
+        }
+        None => {
+            let line = match shell_state.open_command.take() {
+                Some(open_command) => format!("{}\n{}", open_command, raw_line),
+                None => String::from(raw_line),
+            };
+            if token::is_open(&line) {
+                shell_state.open_command = Some(line);
+                return Ok(());
+            }
+
+            let tokens = token::tokenize_raw_line(&line);
+            let tokens = alias::expand_aliases(tokens, &shell_state.aliases);
+            // eg: a line with only a comment
+            if tokens.is_empty() {
+                return Ok(());
+            }
+            PendingHereDocs::new(ast::parse_to_ast(tokens.as_slice())?)
+        }
+    };
+
+    if !pending.is_complete() {
+        shell_state.pending_here_docs = Some(pending);
+        return Ok(());
+    }
+
+    evaluate(&pending.into_ast(), shell_state);
+    shell_state.aborted = false;
+
+    if shell_state.exit.is_some() {
+        trap::run_exit_trap(shell_state);
+    }
 
-    log(format!("{:?}", &a));
-    shell_state.output.clear();
     Ok(())
 }
 
-// fn run_with_pipelines(tokens: Vec<Token>, shell_state: &mut ShellState) {
-//     let mut iter = group_by_pipeline(tokens).iter().peekable();
-//
-//     while Some(group) = iter.next() {
-//         evaluate_tokens(group, shell_state);
-//     }
-//
-//     dbg!(tokens.clone());
-//     dbg!(group_by_pipeline(tokens.clone()).windows(2));
-//     for group in group_by_pipeline(tokens).windows(2) {
-//         dbg!(&group[0]);
-//         dbg!(&group[1]);
-//
-//         // evaluate_tokens(group.clone(), shell_state);
-//     }
-// }
-
-fn evaluate_tokens(tokens: Vec<Token>, shell_state: &mut ShellState) {
-    let mut skip_next = false;
-    for token in tokens {
-        if skip_next {
-            skip_next = false;
+// Runs a script line by line, as if the lines were typed in to the shell.
+// Here-documents that are still open at the end of the script end there.
+pub fn run_script(script: &str, shell_state: &mut ShellState) -> Result<(), Exception> {
+    let mut lines = script.lines();
+
+    while let Some(line) = lines.next() {
+        if shell_state.exit.is_some() {
+            break;
+        }
+
+        let is_blank = token::tokenize_raw_line(line).is_empty();
+
+        if is_blank && shell_state.pending_here_docs.is_none() {
             continue;
         }
 
-        match token {
-            Raw(cmd) => {
-                if execute_command(&cmd, shell_state).is_err() {
-                    shell_state
-                        .output
-                        .set_stderr(127, format!("shell: command not found: {}", &cmd));
+        run(line, shell_state)?;
+
+        let rest = || Frame::Lines(lines.by_ref().map(String::from).collect());
+        if resume::defer(shell_state, rest) {
+            return Ok(());
+        }
+    }
+
+    end_of_input(shell_state);
+    Ok(())
+}
+
+// Runs a line as a part of the running one, eg: for `eval`. Here-documents in it can't
+// read the lines after it, so their bodies are empty. When it waits for input, the running
+// line waits too.
+pub fn run_within(line: &str, shell_state: &mut ShellState) -> Result<(), Exception> {
+    let tokens = alias::expand_aliases(token::tokenize_raw_line(line), &shell_state.aliases);
+    if tokens.is_empty() {
+        return Ok(());
+    }
+
+    let ast = ast::parse_to_ast(tokens.as_slice())?;
+
+    // like bash an error that drops the rest of the line only drops the rest of this one
+    evaluate(&PendingHereDocs::new(ast).into_ast(), shell_state);
+    shell_state.aborted = false;
+    Ok(())
+}
+
+// Runs a line while another one runs like `run_within`, but it can't wait for input,
+// eg: for a trap
+pub fn run_nested(line: &str, shell_state: &mut ShellState) -> Result<(), Exception> {
+    shell_state.nested += 1;
+    let result = run_within(line, shell_state);
+    shell_state.nested -= 1;
+    result
+}
+
+// Runs the rc file when a session starts, SHELLRC names it and by default it's ~/.shellrc.
+// There doesn't have to be an rc file.
+pub fn run_rc_file(shell_state: &mut ShellState) -> Result<(), Exception> {
+    let path = match (shell_state.parameter("SHELLRC"), shell_state.parameter("HOME")) {
+        (Some(path), _) => shell_state.resolve_path(&path),
+        (None, Some(home)) => shell_state.resolve_path(&home).join(".shellrc"),
+        (None, None) => return Ok(()),
+    };
+
+    match shell_state.fs.read(&path) {
+        Ok(script) => run_script(&String::from_utf8_lossy(&script), shell_state),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(error) => {
+            write_expansion_error(file_error(&path.to_string_lossy(), &error), shell_state);
+            Ok(())
+        }
+    }
+}
+
+// Called when the input of the shell ends, here-documents that are still open end here.
+// A compound command that isn't closed is a syntax error.
+pub fn end_of_input(shell_state: &mut ShellState) {
+    if shell_state.open_command.take().is_some() {
+        shell_state
+            .output
+            .write_stderr("shell: syntax error: unexpected end of file\n");
+        shell_state.output.status = ExitStatus::USAGE;
+    }
+
+    if let Some(mut pending) = shell_state.pending_here_docs.take() {
+        let delimiter = pending.delimiter().unwrap_or_default();
+        shell_state.output.write_stderr(format!(
+            "shell: here-document delimited by end-of-file (wanted `{}')\n",
+            delimiter
+        ));
+        evaluate(&pending.into_ast(), shell_state);
+    }
+}
+
+// evaluates the tree depth-first, once the shell exits or the line is aborted nothing
+// more runs
+fn evaluate(ast: &AstItem, shell_state: &mut ShellState) {
+    if shell_state.exit.is_some() || shell_state.aborted {
+        return;
+    }
+
+    match ast {
+        AstItem::Command { keyword, args } => {
+            if !shell_state.running_trap {
+                let command = std::iter::once(keyword).chain(args).cloned().collect::<Vec<_>>();
+                shell_state.set_variable("BASH_COMMAND", command.join(" "));
+                trap::run_trap("DEBUG", shell_state);
+            }
+
+            let status = execute_simple_command(keyword, args, shell_state);
+            if shell_state.suspension.is_none() {
+                finish_command(status, shell_state);
+            }
+        }
+        AstItem::Arithmetic(expression) => {
+            let status = match expansion::evaluate_arithmetic(expression, shell_state) {
+                Ok(value) => ExitStatus::from(value != 0),
+                Err(error) => write_expansion_error(error, shell_state),
+            };
+            finish_command(status, shell_state);
+        }
+        AstItem::Conditional(condition) => {
+            let status = match condition::evaluate(condition, shell_state) {
+                Ok(result) => ExitStatus::from(result),
+                Err(error) => {
+                    write_expansion_error(error, shell_state);
+                    ExitStatus::USAGE
+                }
+            };
+            finish_command(status, shell_state);
+        }
+        AstItem::Case { word, items } => {
+            let result = expansion::expand_word(word, shell_state).and_then(|field| {
+                evaluate_case_items(&field.text, items, false, ExitStatus::SUCCESS, shell_state)
+            });
+
+            if let Err(error) = result {
+                let status = write_expansion_error(error, shell_state);
+                finish_command(status, shell_state);
+            }
+        }
+        AstItem::Negated(item) => {
+            evaluate_tested(item, shell_state);
+            if resume::defer(shell_state, || Frame::Negate) {
+                return;
+            }
+            shell_state.output.status = ExitStatus::from(!shell_state.output.is_ok());
+        }
+        AstItem::Timed { item, posix } => {
+            let start = shell_state.clock.now();
+            evaluate(item, shell_state);
+
+            let posix = *posix;
+            if !resume::defer(shell_state, || Frame::Timed { start, posix }) {
+                report_time(&start, posix, shell_state);
+            }
+        }
+        AstItem::Subshell(list) => {
+            evaluate_subshell(list, shell_state);
+            if shell_state.suspension.is_none() {
+                check_status(shell_state);
+            }
+        }
+        AstItem::Group(list) => evaluate(list, shell_state),
+        AstItem::Redirected { item, redirections } => {
+            if let Err(error) = evaluate_redirected(item, redirections, shell_state) {
+                let status = write_expansion_error(error, shell_state);
+                finish_command(status, shell_state);
+            }
+        }
+        AstItem::Script(items) => {
+            for (i, item) in items.iter().enumerate() {
+                evaluate(item, shell_state);
+
+                let rest = || Frame::Evaluate(AstItem::Script(items[i + 1..].to_vec()));
+                if resume::defer(shell_state, rest) {
+                    return;
                 }
             }
-            And => {
-                if !shell_state.output.is_ok() {
-                    skip_next = true;
+        }
+        AstItem::LogicalExpression { op, left, right } => {
+            evaluate_tested(left, shell_state);
+
+            let frame = || Frame::Logical {
+                op: op.clone(),
+                right: right.as_ref().clone(),
+            };
+            if !resume::defer(shell_state, frame) {
+                evaluate_right(op, right, shell_state);
+            }
+        }
+        AstItem::Pipeline(items) => {
+            let mut stdin = None;
+            let mut statuses = Vec::new();
+
+            // every command of a pipeline runs in a subshell, eg: `x=1 | true` doesn't set x.
+            // They can't wait for input.
+            shell_state.nested += 1;
+            for (i, item) in items.iter().enumerate() {
+                shell_state.output.stdin = stdin.take();
+
+                if i == items.len() - 1 {
+                    evaluate_subshell(item, shell_state);
+                } else {
+                    stdin = Some(evaluate_pipe_stage(item, shell_state));
                 }
+
+                statuses.push(shell_state.output.status);
+            }
+            shell_state.nested -= 1;
+
+            if shell_state.options.pipefail {
+                let failed = statuses.iter().rev().find(|status| !status.is_success());
+                shell_state.output.status = failed.copied().unwrap_or(ExitStatus::SUCCESS);
+            }
+
+            shell_state.output.stdin = None;
+            shell_state.pipe_status = statuses;
+            check_status(shell_state);
+        }
+    }
+}
+
+// Evaluates an item whose status is tested, so a failure doesn't exit with errexit
+fn evaluate_tested(ast: &AstItem, shell_state: &mut ShellState) {
+    shell_state.status_tested += 1;
+    evaluate(ast, shell_state);
+    shell_state.status_tested -= 1;
+    resume::defer(shell_state, || Frame::Tested);
+}
+
+// The right item of `&&` runs when the left one succeeded, the one of `||` when it failed
+fn evaluate_right(op: &LogicalExpressionOp, right: &AstItem, shell_state: &mut ShellState) {
+    let should_continue = match op {
+        LogicalExpressionOp::And => shell_state.output.is_ok(),
+        LogicalExpressionOp::Or => !shell_state.output.is_ok(),
+    };
+
+    if should_continue {
+        evaluate(right, shell_state);
+    }
+}
+
+// Evaluates an item that can't wait for input, eg: because its output is captured.
+// `read` gets no input from the host in it.
+fn evaluate_nested(ast: &AstItem, shell_state: &mut ShellState) {
+    shell_state.nested += 1;
+    evaluate(ast, shell_state);
+    shell_state.nested -= 1;
+}
+
+// The status of a command becomes `$?`, then the ERR trap and errexit apply
+fn finish_command(status: ExitStatus, shell_state: &mut ShellState) {
+    shell_state.output.status = status;
+    shell_state.pipe_status = vec![status];
+    check_status(shell_state);
+}
+
+// `time` writes how long its pipeline took to stderr, an empty TIMEFORMAT turns it off
+fn report_time(start: &Times, posix: bool, shell_state: &mut ShellState) {
+    let times = shell_state.clock.now().since(start);
+    let format = match posix {
+        true => String::from(time::POSIX_FORMAT),
+        false => shell_state
+            .parameter("TIMEFORMAT")
+            .unwrap_or_else(|| String::from(time::DEFAULT_FORMAT)),
+    };
+
+    if !format.is_empty() {
+        let report = time::format_times(&format, &times);
+        shell_state.output.write_stderr(report + "\n");
+    }
+}
+
+// When a command fails and its status isn't tested the ERR trap runs,
+// then errexit makes the shell exit. A shell that exits already doesn't run it.
+fn check_status(shell_state: &mut ShellState) {
+    let status = shell_state.output.status;
+    if status.is_success() || shell_state.status_tested > 0 || shell_state.exit.is_some() {
+        return;
+    }
+
+    trap::run_trap("ERR", shell_state);
+
+    if shell_state.options.errexit {
+        shell_state.exit.get_or_insert(status);
+    }
+}
+
+// Runs the body of the first item with a matching pattern, the terminator of that item
+// decides whether the case ends there. The status is the one of the last body that ran,
+// or the given one when none ran, eg: 0 for a new case.
+fn evaluate_case_items(
+    text: &str,
+    items: &[CaseItem],
+    mut fall_through: bool,
+    mut status: ExitStatus,
+    shell_state: &mut ShellState,
+) -> Result<(), ExpansionError> {
+    for (i, item) in items.iter().enumerate() {
+        if !fall_through && !case_item_matches(text, item, shell_state)? {
+            continue;
+        }
+
+        status = match &item.body {
+            Some(body) => {
+                evaluate(body, shell_state);
+                shell_state.output.status
+            }
+            None => ExitStatus::SUCCESS,
+        };
+
+        let is_break = item.terminator == CaseTerminator::Break;
+        fall_through = item.terminator == CaseTerminator::FallThrough;
+
+        let rest = || Frame::Case {
+            text: String::from(text),
+            items: match is_break {
+                true => Vec::new(),
+                false => items[i + 1..].to_vec(),
+            },
+            fall_through,
+        };
+        if resume::defer(shell_state, rest) {
+            return Ok(());
+        }
+
+        if is_break {
+            break;
+        }
+    }
+
+    shell_state.output.status = status;
+    shell_state.pipe_status = vec![status];
+    Ok(())
+}
+
+fn case_item_matches(
+    text: &str,
+    item: &CaseItem,
+    shell_state: &mut ShellState,
+) -> Result<bool, ExpansionError> {
+    for pattern in &item.patterns {
+        let pattern = expansion::expand_word(pattern, shell_state)?.pattern;
+
+        if Pattern::new(&pattern).matches(text) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+// where a stream of a redirected item goes
+#[derive(Debug, PartialEq, Clone)]
+enum Destination {
+    File(PathBuf),
+    Stream(Stream),
+}
+
+// Input redirections replace the stdin of the item, the last one wins. Files of output
+// redirections are created or emptied before the item runs and receive its output after.
+// Output redirections apply from left to right, so `> file 2>&1` writes both streams to
+// the file and `2>&1 > file` only stdout.
+fn evaluate_redirected(
+    item: &AstItem,
+    redirections: &[Redirection],
+    shell_state: &mut ShellState,
+) -> Result<(), ExpansionError> {
+    let mut stdin = None;
+    let mut outputs = [Stream::Stdout, Stream::Stderr].map(|stream| (stream, Destination::Stream(stream)));
+
+    for redirection in redirections {
+        match redirection {
+            Redirection::HereDoc { body, expand, .. } => {
+                let body = body.as_deref().unwrap_or_default();
+                let text = match expand {
+                    true => expansion::expand_here_doc(body, shell_state)?,
+                    false => String::from(body),
+                };
+                stdin = Some(text.into_bytes());
+            }
+            Redirection::HereString(word) => {
+                let text = expansion::expand_word(word, shell_state)?.text + "\n";
+                stdin = Some(text.into_bytes());
             }
-            Or => {
-                if shell_state.output.is_ok() {
-                    skip_next = true;
+            Redirection::Input(target) => {
+                let target = expansion::expand_word(target, shell_state)?.text;
+                let contents = shell_state
+                    .fs
+                    .read(&shell_state.resolve_path(&target))
+                    .map_err(|error| file_error(&target, &error))?;
+                stdin = Some(contents);
+            }
+            Redirection::Output {
+                stream,
+                target,
+                append,
+                clobber,
+            } => {
+                let target = expansion::expand_word(target, shell_state)?.text;
+                let path = shell_state.resolve_path(&target);
+
+                // noclobber only protects files, eg: `> /dev/null` still works
+                let is_file = shell_state.fs.exists(&path) && !shell_state.fs.is_dir(&path);
+                if shell_state.options.noclobber && !append && !clobber && is_file {
+                    return Err(ExpansionError(format!(
+                        "{}: cannot overwrite existing file",
+                        target
+                    )));
+                }
+
+                if !append || !shell_state.fs.exists(&path) {
+                    shell_state
+                        .fs
+                        .write(&path, b"")
+                        .map_err(|error| file_error(&target, &error))?;
                 }
+
+                set_destination(&mut outputs, *stream, Destination::File(path));
             }
-            _ => (),
+            Redirection::Duplicate { stream, target } => {
+                let destination = destination(&outputs, *target);
+                set_destination(&mut outputs, *stream, destination);
+            }
+        }
+    }
+
+    let outer_stdin = match stdin {
+        Some(stdin) => Some(shell_state.output.stdin.replace(stdin)),
+        None => None,
+    };
+
+    match outputs.iter().all(|(stream, destination)| *destination == Destination::Stream(*stream)) {
+        true => evaluate_nested(item, shell_state),
+        false => evaluate_to_files(item, &outputs, shell_state),
+    }
+
+    if let Some(outer_stdin) = outer_stdin {
+        shell_state.output.stdin = outer_stdin;
+    }
+
+    Ok(())
+}
+
+fn destination(outputs: &[(Stream, Destination)], stream: Stream) -> Destination {
+    outputs
+        .iter()
+        .find(|(output_stream, _)| *output_stream == stream)
+        .map(|(_, destination)| destination.clone())
+        .expect("Every stream should have a destination")
+}
+
+fn set_destination(outputs: &mut [(Stream, Destination)], stream: Stream, destination: Destination) {
+    for (output_stream, output_destination) in outputs.iter_mut() {
+        if *output_stream == stream {
+            *output_destination = destination.clone();
+        }
+    }
+}
+
+// Runs the item with its own output, the streams that go to files are appended to them
+// and the other streams to the surrounding output
+fn evaluate_to_files(item: &AstItem, outputs: &[(Stream, Destination)], shell_state: &mut ShellState) {
+    let mut item_output = ShellOutput::default();
+    item_output.stdin = shell_state.output.stdin.take();
+    let outer_output = mem::replace(&mut shell_state.output, item_output);
+
+    evaluate_nested(item, shell_state);
+
+    let mut item_output = mem::replace(&mut shell_state.output, outer_output);
+    // both streams can go to the same file, eg: `> file 2>&1`
+    let mut files: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+
+    for chunk in item_output.drain() {
+        match destination(outputs, chunk.stream) {
+            Destination::File(path) => match files.iter_mut().find(|(file, _)| *file == path) {
+                Some((_, contents)) => contents.extend(chunk.bytes),
+                None => files.push((path, chunk.bytes)),
+            },
+            Destination::Stream(stream) => shell_state.output.write(stream, &chunk.bytes),
+        }
+    }
+
+    for (path, bytes) in &files {
+        let mut file = shell_state.fs.read(path).unwrap_or_default();
+        file.extend(bytes);
+
+        if let Err(error) = shell_state.fs.write(path, &file) {
+            let message = file_error(&path.to_string_lossy(), &error);
+            write_expansion_error(message, shell_state);
+        }
+    }
+
+    shell_state.output.status = item_output.status;
+}
+
+fn file_error(target: &str, error: &io::Error) -> ExpansionError {
+    ExpansionError(format!("{}: {}", target, filesystem::describe_error(error)))
+}
+
+// `( list )` runs in a copy of the shell state, so `cd` and assignments in the list
+// don't change the shell. The filesystem is shared, files written in it stay.
+// When the list waits for input, the copy waits in a frame.
+fn evaluate_subshell(ast: &AstItem, shell_state: &mut ShellState) {
+    let outer_output = mem::take(&mut shell_state.output);
+    let mut subshell = shell_state.clone();
+    shell_state.output = outer_output;
+
+    // only ignored signals stay ignored in a subshell, other traps are reset
+    subshell.traps.retain(|_, action| action.is_empty());
+
+    subshell.output.stdin = shell_state.output.stdin.take();
+    subshell.output.status = shell_state.output.status;
+    evaluate(ast, &mut subshell);
+
+    finish_subshell(subshell, shell_state);
+}
+
+// The output of the subshell so far is appended to the surrounding output,
+// once it's done its status is the one of the `( list )`
+fn finish_subshell(mut subshell: ShellState, shell_state: &mut ShellState) {
+    for chunk in subshell.output.drain() {
+        shell_state.output.write(chunk.stream, &chunk.bytes);
+    }
+
+    if subshell.suspension.is_some() {
+        resume::defer_subshell(subshell, shell_state);
+        return;
+    }
+
+    let status = subshell.output.status;
+    shell_state.output.status = status;
+    shell_state.pipe_status = vec![status];
+}
+
+// Runs a stage of a pipeline in a subshell with its own output. Its stdout is returned so it can
+// become the stdin of the next stage, stderr is not part of the pipe and is appended
+// to the surrounding output.
+fn evaluate_pipe_stage(ast: &AstItem, shell_state: &mut ShellState) -> Vec<u8> {
+    let mut stage_output = ShellOutput::default();
+    stage_output.stdin = shell_state.output.stdin.take();
+    let outer_output = mem::replace(&mut shell_state.output, stage_output);
+
+    evaluate_subshell(ast, shell_state);
+
+    let mut stage_output = mem::replace(&mut shell_state.output, outer_output);
+    let mut stdout = Vec::new();
+
+    for chunk in stage_output.drain() {
+        match chunk.stream {
+            Stream::Stdout => stdout.extend(chunk.bytes),
+            Stream::Stderr => shell_state.output.write_stderr(chunk.bytes),
+        }
+    }
+
+    shell_state.output.status = stage_output.status;
+    stdout
+}
+
+// Expands the words of the command before running it, a command that expands to nothing
+// does nothing. Leading `NAME=value` words are assignments, without a command they set
+// shell variables, otherwise they only apply while the command runs.
+fn execute_simple_command(keyword: &str, args: &[String], shell_state: &mut ShellState) -> ExitStatus {
+    let words: Vec<String> = std::iter::once(keyword.to_string())
+        .chain(args.iter().cloned())
+        .collect();
+    let assignment_count = words
+        .iter()
+        .take_while(|word| word::split_assignment(word).is_some())
+        .count();
+    let (assignments, words) = words.split_at(assignment_count);
+
+    let fields = match expand_command_words(words, shell_state) {
+        Ok(fields) => fields,
+        Err(error) => return write_expansion_error(error, shell_state),
+    };
+
+    // assignments are done from left to right, so a value can use the ones before it
+    let mut previous = Vec::new();
+    for raw in assignments {
+        let assignment = word::split_assignment(raw).expect("Assignments should be split before");
+        let old_value = shell_state.variables.get(assignment.name).cloned();
+
+        match assignment::assign(&assignment, shell_state) {
+            Ok(traced) => trace(&[traced], shell_state),
+            Err(error) => return write_expansion_error(error, shell_state),
+        }
+        previous.push((String::from(assignment.name), old_value));
+    }
+
+    let quoted: Vec<String> = fields.iter().map(|field| word::quote(field)).collect();
+    trace(&quoted, shell_state);
+
+    let mut fields = fields.into_iter();
+    let keyword = match fields.next() {
+        Some(keyword) => keyword,
+        None => return ExitStatus::SUCCESS,
+    };
+
+    let command = Command {
+        keyword,
+        args: fields.collect(),
+    };
+    let status = execute_command(&command, shell_state);
+
+    // a command that waits for input runs again once the line resumes
+    match shell_state.suspension.is_some() {
+        true => resume::defer_command(command, previous, shell_state),
+        false => restore_variables(previous, shell_state),
+    }
+
+    status
+}
+
+// The variables of the assignments in front of a command get their old values back
+fn restore_variables(previous: Vec<(String, Option<Value>)>, shell_state: &mut ShellState) {
+    for (name, value) in previous.into_iter().rev() {
+        match value {
+            Some(value) => shell_state.variables.insert(name, value),
+            None => shell_state.variables.remove(&name),
+        };
+    }
+}
+
+// `declare` expands the assignments in its arguments itself, like the ones in front of a command
+fn expand_command_words(
+    words: &[String],
+    shell_state: &mut ShellState,
+) -> Result<Vec<String>, ExpansionError> {
+    if !words.first().is_some_and(|keyword| DECLARATION_COMMANDS.contains(&keyword.as_str())) {
+        return expansion::expand_words(words, shell_state);
+    }
+
+    let mut fields = Vec::new();
+    for word in words {
+        match word::split_assignment(word) {
+            Some(_) => fields.push(word.clone()),
+            None => fields.extend(expansion::expand_words(std::slice::from_ref(word), shell_state)?),
         }
     }
+
+    Ok(fields)
+}
+
+// xtrace writes the words of a command to stderr before it runs, after PS4
+fn trace(words: &[String], shell_state: &mut ShellState) {
+    if !shell_state.options.xtrace || words.is_empty() {
+        return;
+    }
+
+    let prompt = match shell_state.parameter("PS4") {
+        Some(ps4) => expansion::expand_word(&ps4, shell_state).map_or(ps4, |field| field.text),
+        None => String::from("+ "),
+    };
+
+    shell_state
+        .output
+        .write_stderr(format!("{}{}\n", prompt, words.join(" ")));
+}
+
+fn write_expansion_error(error: ExpansionError, shell_state: &mut ShellState) -> ExitStatus {
+    let ExpansionError(message) = error;
+    shell_state
+        .output
+        .write_stderr(format!("shell: {}\n", message));
+    ExitStatus::FAILURE
 }
 
-fn execute_command(raw_cmd: &String, shell_state: &mut ShellState) -> Result<(), ()> {
-    let cmd = Command::try_from(raw_cmd)?;
-    builtin::evaluate(&cmd, shell_state)
+// Runs a command whose words are expanded already
+pub fn execute_command(cmd: &Command, shell_state: &mut ShellState) -> ExitStatus {
+    if let Some(CommandType::Builtin(builtin)) = builtin::resolve_command(&cmd.keyword, shell_state) {
+        return builtin::run(builtin, cmd, shell_state);
+    }
+
+    // a keyword with a slash is a path, it's never looked up as a command
+    if !cmd.keyword.contains('/') {
+        shell_state
+            .output
+            .write_stderr(format!("shell: command not found: {}\n", &cmd.keyword));
+        return ExitStatus::NOT_FOUND;
+    }
+
+    // files can't be executed yet, so any existing path is not executable
+    let (message, status) = match shell_state.fs.read(&shell_state.resolve_path(&cmd.keyword)) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            ("no such file or directory", ExitStatus::NOT_FOUND)
+        }
+        Err(error) if error.kind() == io::ErrorKind::IsADirectory => {
+            ("is a directory", ExitStatus::NOT_EXECUTABLE)
+        }
+        _ => ("permission denied", ExitStatus::NOT_EXECUTABLE),
+    };
+
+    shell_state
+        .output
+        .write_stderr(format!("shell: {}: {}\n", message, &cmd.keyword));
+    status
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser::token::Token::{And, Or, Raw, Semicolon};
+    use crate::shell_state::OutputChunk;
+
+    // output of the line, drained from the state like a host would do
+    fn run_line(line: &str, state: &mut ShellState) -> ShellOutput {
+        run(line, state).unwrap();
+
+        let mut output = ShellOutput::default();
+        output.status = state.output.status;
+        for chunk in state.output.drain() {
+            output.write(chunk.stream, &chunk.bytes);
+        }
+
+        output
+    }
 
     #[test]
     fn or_token_should_only_eval_first_cmd_when_first_succeeds() {
         let mut state = ShellState::default();
 
-        evaluate_tokens(
-            vec![
-                Raw(String::from("true")),
-                Or,
-                Raw(String::from("UNKNOWN_COMMAND")),
-            ],
-            &mut state,
-        );
+        let output = run_line("true || UNKNOWN_COMMAND", &mut state);
 
-        assert_eq!(state.output.code, Some(0));
+        assert_eq!(output.status, ExitStatus::SUCCESS);
+        assert_eq!(output.stderr(), b"");
     }
 
     #[test]
     fn or_token_should_eval_second_cmd_when_first_fails() {
         let mut state = ShellState::default();
 
-        evaluate_tokens(
-            vec![
-                Raw(String::from("UNKNOWN_COMMAND")),
-                Or,
-                Raw(String::from("true")),
-            ],
-            &mut state,
-        );
+        let output = run_line("UNKNOWN_COMMAND || true", &mut state);
 
-        assert_eq!(state.output.code, Some(0));
+        assert_eq!(output.status, ExitStatus::SUCCESS);
     }
 
     #[test]
     fn and_token_should_eval_second_cmd_when_first_cmd_succeeds() {
         let mut state = ShellState::default();
 
-        evaluate_tokens(
-            vec![Raw(String::from("true")), And, Raw(String::from("false"))],
-            &mut state,
-        );
+        let output = run_line("true && false", &mut state);
 
-        assert_eq!(state.output.code, Some(1));
+        assert_eq!(output.status, ExitStatus::FAILURE);
     }
 
     #[test]
     fn and_token_should_not_eval_second_cmd_when_first_cmd_fails() {
         let mut state = ShellState::default();
 
-        evaluate_tokens(
-            vec![Raw(String::from("false")), And, Raw(String::from("true"))],
-            &mut state,
-        );
+        let output = run_line("false && true", &mut state);
 
-        assert_eq!(state.output.code, Some(1));
+        assert_eq!(output.status, ExitStatus::FAILURE);
     }
 
     #[test]
     fn semicolon_terminator_should_eval_when_first_cmd_fails() {
         let mut state = ShellState::default();
 
-        evaluate_tokens(
-            vec![
-                Raw(String::from("false")),
-                Semicolon,
-                Raw(String::from("true")),
-            ],
-            &mut state,
-        );
+        let output = run_line("false; true", &mut state);
 
-        assert_eq!(state.output.code, Some(0));
+        assert_eq!(output.status, ExitStatus::SUCCESS);
     }
 
     #[test]
     fn semicolon_terminator_should_eval_when_first_cmd_succeeds() {
         let mut state = ShellState::default();
 
-        evaluate_tokens(
-            vec![
-                Raw(String::from("true")),
-                Semicolon,
-                Raw(String::from("echo foo")),
-            ],
-            &mut state,
-        );
+        let output = run_line("true; echo foo", &mut state);
 
-        assert_eq!(state.output.code, Some(0));
-        assert_eq!(state.output.stdout, Some(String::from("foo")));
+        assert_eq!(output.status, ExitStatus::SUCCESS);
+        assert_eq!(output.stdout(), b"foo\n");
+    }
+
+    #[test]
+    fn output_of_every_command_is_kept() {
+        let mut state = ShellState::default();
+
+        let output = run_line("echo a; echo b", &mut state);
+
+        assert_eq!(output.stdout(), b"a\nb\n");
     }
 
     #[test]
     fn evaluate_should_always_evaluate_all_tokens() {
         let mut state = ShellState::default();
 
-        evaluate_tokens(
+        let output = run_line("false && true || false; UNKNOWN COMMAND", &mut state);
+
+        assert_eq!(output.status, ExitStatus::NOT_FOUND);
+    }
+
+    #[test]
+    fn output_keeps_the_order_of_commands() {
+        let mut state = ShellState::default();
+
+        let output = run_line("echo a; UNKNOWN; echo b", &mut state);
+
+        let chunks: Vec<OutputChunk> = output.chunks().cloned().collect();
+
+        assert_eq!(
+            chunks,
             vec![
-                Raw(String::from("false")),
-                And,
-                Raw(String::from("true")),
-                Or,
-                Raw(String::from("false")),
-                Semicolon,
-                Raw(String::from("UNKNOWN COMMAND")),
-            ],
+                OutputChunk {
+                    stream: Stream::Stdout,
+                    bytes: b"a\n".to_vec(),
+                },
+                OutputChunk {
+                    stream: Stream::Stderr,
+                    bytes: b"shell: command not found: UNKNOWN\n".to_vec(),
+                },
+                OutputChunk {
+                    stream: Stream::Stdout,
+                    bytes: b"b\n".to_vec(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn pipeline_only_prints_the_stdout_of_the_last_command() {
+        let mut state = ShellState::default();
+
+        let output = run_line("echo a | UNKNOWN | echo b", &mut state);
+
+        assert_eq!(output.stdout(), b"b\n");
+        assert_eq!(output.stderr(), b"shell: command not found: UNKNOWN\n");
+    }
+
+    #[test]
+    fn status_of_the_last_command_is_available_as_parameter() {
+        let mut state = ShellState::default();
+
+        let output = run_line("false; echo $?; echo $?", &mut state);
+
+        assert_eq!(output.stdout(), b"1\n0\n");
+    }
+
+    #[test]
+    fn pipe_status_has_the_status_of_every_stage() {
+        let mut state = ShellState::default();
+
+        run_line("true | false | UNKNOWN", &mut state);
+        assert_eq!(
+            state.pipe_status,
+            vec![ExitStatus::SUCCESS, ExitStatus::FAILURE, ExitStatus::NOT_FOUND]
+        );
+
+        let output = run_line("echo ${PIPESTATUS[@]} $?", &mut state);
+        assert_eq!(output.stdout(), b"0 1 127 127\n");
+        assert_eq!(state.pipe_status, vec![ExitStatus::SUCCESS]);
+    }
+
+    #[test]
+    fn pipeline_stages_run_in_subshells() {
+        let mut state = ShellState::default();
+        state.fs.create_dir(std::path::Path::new("/tmp")).unwrap();
+
+        let output = run_line(
+            "cd /tmp; cd / | true; pwd; x=1 | true; echo \"[$x]\"; echo one | read v; echo \"[$v]\"",
             &mut state,
         );
 
-        assert_eq!(state.output.code, Some(127));
+        assert_eq!(output.stdout(), b"/tmp\n[]\n[]\n");
+        assert_eq!(state.exit, None);
+    }
+
+    #[test]
+    fn path_that_is_not_executable() {
+        let mut state = ShellState::default();
+        state.fs.write(std::path::Path::new("/file"), b"").unwrap();
+
+        let output = run_line("./file", &mut state);
+        assert_eq!(output.status, ExitStatus::NOT_EXECUTABLE);
+        assert_eq!(output.stderr(), b"shell: permission denied: ./file\n");
+
+        let output = run_line("/missing", &mut state);
+        assert_eq!(output.status, ExitStatus::NOT_FOUND);
+        assert_eq!(output.stderr(), b"shell: no such file or directory: /missing\n");
+    }
+
+    #[test]
+    fn arguments_are_expanded_as_globs() {
+        let mut state = ShellState::default();
+        state.fs.write(std::path::Path::new("/a.md"), b"a\n").unwrap();
+        state.fs.write(std::path::Path::new("/b.md"), b"b\n").unwrap();
+
+        let output = run_line("cat *.md; echo '*.md'", &mut state);
+        assert_eq!(output.stdout(), b"a\nb\n*.md\n");
+
+        state.shopt.failglob = true;
+        let output = run_line("echo *.rs", &mut state);
+        assert_eq!(output.stderr(), b"shell: no match: *.rs\n");
+        assert_eq!(output.status, ExitStatus::FAILURE);
+    }
+
+    #[test]
+    fn assignments_set_variables() {
+        let mut state = ShellState::default();
+
+        let output = run_line("A='a b' B=$A; echo $B ${C:-$A}", &mut state);
+        assert_eq!(output.stdout(), b"a b a b\n");
+
+        let output = run_line("A=1 echo $A; echo ${A%b}", &mut state);
+        assert_eq!(output.stdout(), b"a b\na\n");
+
+        let output = run_line("echo ${UNSET:?must be set}", &mut state);
+        assert_eq!(output.stderr(), b"shell: UNSET: must be set\n");
+        assert_eq!(output.status, ExitStatus::FAILURE);
+    }
+
+    #[test]
+    fn arithmetic_commands_and_expansions() {
+        let mut state = ShellState::default();
+
+        let output = run_line("(( i = 2 )); (( i++ )) && echo $i $(( i * 2 ))", &mut state);
+        assert_eq!(output.stdout(), b"3 6\n");
+
+        let output = run_line("(( i - 3 )) || echo zero", &mut state);
+        assert_eq!(output.stdout(), b"zero\n");
+
+        let output = run_line("(( 1 / 0 )); echo $(( 1 % 0 ))", &mut state);
+        assert_eq!(
+            output.stderr(),
+            b"shell: 1 / 0: division by 0\nshell: 1 % 0: division by 0\n"
+        );
+        assert_eq!(output.status, ExitStatus::FAILURE);
+    }
+
+    #[test]
+    fn conditional_commands() {
+        let mut state = ShellState::default();
+        state.fs.write(std::path::Path::new("/a.md"), b"").unwrap();
+
+        let line = "F=a.md; [[ -f $F && $F == *.md ]] && [ -e \"$F\" ] && test $F != b && echo yes";
+        let output = run_line(line, &mut state);
+        assert_eq!(output.stdout(), b"yes\n");
+
+        let output = run_line("[[ $F =~ ^(.)\\.md$ ]] && echo ${BASH_REMATCH[1]}", &mut state);
+        assert_eq!(output.stdout(), b"a\n");
+
+        let output = run_line("[ 1 -gt 0 || [[ 1 -eq 2 ]]", &mut state);
+        assert_eq!(output.stderr(), b"[: missing `]'\n");
+        assert_eq!(output.status, ExitStatus::FAILURE);
+    }
+
+    #[test]
+    fn case_commands() {
+        let mut state = ShellState::default();
+
+        let line = "F=notes.md; case $F in *.txt) echo text;; *.md|*.rst) echo docs;; *) echo other;; esac";
+        let output = run_line(line, &mut state);
+        assert_eq!(output.stdout(), b"docs\n");
+
+        let line = "case a in a) echo 1;& b) echo 2;;& a) echo 3;; *) echo 4;; esac | cat";
+        let output = run_line(line, &mut state);
+        assert_eq!(output.stdout(), b"1\n2\n3\n");
+
+        let output = run_line("false; case x in '*') echo star;; esac", &mut state);
+        assert_eq!(output.stdout(), b"");
+        assert_eq!(output.status, ExitStatus::SUCCESS);
+
+        let output = run_line("case x in x) false;; esac || echo failed", &mut state);
+        assert_eq!(output.stdout(), b"failed\n");
+    }
+
+    #[test]
+    fn here_documents_and_here_strings() {
+        let mut state = ShellState::default();
+
+        run("A=world; cat <<EOF | cat <<-'END' && cat <<< \"$A  !\"", &mut state).unwrap();
+        for line in ["hello $A", "\\$A $((1 + 1))", "EOF", "\t$A", "\tEND"] {
+            assert!(state.is_reading_here_doc());
+            run(line, &mut state).unwrap();
+        }
+
+        assert!(!state.is_reading_here_doc());
+        assert_eq!(state.output.stdout(), b"$A\nworld  !\n");
+        state.output.clear();
+
+        // redirections are expanded before the assignments of the command apply
+        let output = run_line("cat <<<${A:?unset}; B=1 cat <<<$B", &mut state);
+        assert_eq!(output.stdout(), b"world\n\n");
+    }
+
+    #[test]
+    fn scripts_with_here_documents() {
+        let mut state = ShellState::default();
+        let script = "A=1\n\ncat <<EOF\nline $A\n\n  indented\nEOF\n# done\ncat <<EOF\nopen";
+
+        run_script(script, &mut state).unwrap();
+
+        assert_eq!(state.output.stdout(), b"line 1\n\n  indented\nopen\n");
+        assert_eq!(
+            state.output.stderr(),
+            b"shell: here-document delimited by end-of-file (wanted `EOF')\n"
+        );
+    }
+
+    #[test]
+    fn compound_commands_over_lines() {
+        let mut state = ShellState::default();
+        let script = "set -- b\n\
+                      case $1 in\n\
+                      \x20 # the first letters\n\
+                      \x20 a) echo a ;;\n\
+                      \x20 b)\n\
+                      \x20   echo b\n\
+                      \x20   echo bb\n\
+                      \x20   ;;\n\
+                      esac\n\
+                      {\n\
+                      \x20 echo in group\n\
+                      } > out\n\
+                      cat out";
+
+        run_script(script, &mut state).unwrap();
+        assert_eq!(state.output.stdout(), b"b\nbb\nin group\n");
+        assert_eq!(state.output.stderr(), b"");
+
+        let mut state = ShellState::default();
+        for line in ["{", "  echo a", "  (echo b"] {
+            let output = run_line(line, &mut state);
+            assert_eq!(output.stdout(), b"");
+            assert!(state.is_continuing_command());
+        }
+        let output = run_line(") }", &mut state);
+        assert_eq!(output.stdout(), b"a\nb\n");
+        assert!(!state.is_continuing_command());
+
+        let mut state = ShellState::default();
+        run_script("echo start\ncase a in\n  a) echo a;;", &mut state).unwrap();
+        assert_eq!(state.output.stdout(), b"start\n");
+        assert_eq!(state.output.stderr(), b"shell: syntax error: unexpected end of file\n");
+        assert_eq!(state.output.status, ExitStatus::USAGE);
+    }
+
+    #[test]
+    fn lines_without_commands() {
+        let mut state = ShellState::default();
+
+        for line in ["# note", "", "   "] {
+            let output = run_line(line, &mut state);
+            assert_eq!(output.stderr(), b"", "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn command_resolution() {
+        let mut state = ShellState::default();
+        state.define_default_aliases();
+
+        let output = run_line("A='ll; echo $?'; eval $A; command ll; builtin cd / && type pwd", &mut state);
+        assert_eq!(
+            output.stdout(),
+            b"drwxr-xr-x 0 Jan  1 00:00 .\ndrwxr-xr-x 0 Jan  1 00:00 ..\n0\n\
+              pwd is a shell builtin\n"
+        );
+        assert_eq!(output.stderr(), b"shell: command not found: ll\n");
+
+        let output = run_line("eval 'cat <<EOF'; eval 'echo \"(\"'; (exec echo a); exec echo b; echo c", &mut state);
+        assert_eq!(output.stdout(), b"(\na\nb\n");
+        assert_eq!(state.exit, Some(ExitStatus::SUCCESS));
+    }
+
+    #[test]
+    fn errexit() {
+        let mut state = ShellState::default();
+
+        let line = "set -e; false && true; ! true; false || echo a; false | true; true && false; echo b";
+        let output = run_line(line, &mut state);
+        assert_eq!(output.stdout(), b"a\n");
+        assert_eq!(output.status, ExitStatus::FAILURE);
+        assert_eq!(state.exit, Some(ExitStatus::FAILURE));
+
+        // nothing runs once the shell exited
+        let output = run_line("echo c", &mut state);
+        assert_eq!(output.stdout(), b"");
+
+        let mut state = ShellState::default();
+        let script = "set -e\n(false; echo d) || echo e\n{ false; echo f; } | cat\n(exit_with_127)\necho g";
+        run_script(script, &mut state).unwrap();
+        assert_eq!(state.output.stdout(), b"d\n");
+        assert_eq!(state.exit, Some(ExitStatus::NOT_FOUND));
+    }
+
+    #[test]
+    fn pipefail_and_nounset() {
+        let mut state = ShellState::default();
+
+        let output = run_line("false | true; echo $?; set -o pipefail; false | true | true; echo $?", &mut state);
+        assert_eq!(output.stdout(), b"0\n1\n");
+
+        // an interactive shell goes on, a script exits before the ERR trap runs
+        state.interactive = true;
+        let output = run_line("set -u; echo $UNSET; echo ${UNSET:-a} $?", &mut state);
+        assert_eq!(output.stdout(), b"a 1\n");
+        assert_eq!(output.stderr(), b"shell: UNSET: unbound variable\n");
+
+        let mut state = ShellState::default();
+        run_script("trap 'echo trapped' ERR EXIT\nset -u\necho $UNSET\necho after", &mut state).unwrap();
+        assert_eq!(state.output.stdout(), b"trapped\n");
+        assert_eq!(state.exit, Some(ExitStatus::FAILURE));
+
+        let mut state = ShellState::default();
+        run_script("echo ${EMPTY:?is empty}\necho after", &mut state).unwrap();
+        assert_eq!(state.output.stderr(), b"shell: EMPTY: is empty\n");
+        assert_eq!(state.output.stdout(), b"");
+        assert_eq!(state.exit, Some(ExitStatus::FAILURE));
+    }
+
+    #[test]
+    fn xtrace() {
+        let mut state = ShellState::default();
+
+        let output = run_line("set -x; A='a b' echo $A \"it's\" | cat; B=; set +x", &mut state);
+        assert_eq!(output.stdout(), b"it's\n");
+        assert_eq!(
+            output.stderr(),
+            b"+ A='a b'\n+ echo 'it'\\''s'\n+ cat\n+ B=''\n+ set +x\n"
+        );
+
+        let output = run_line("PS4='$? > '; set -x; false; true", &mut state);
+        assert_eq!(output.stderr(), b"0 > false\n1 > true\n");
+    }
+
+    #[test]
+    fn noclobber_and_noglob() {
+        let mut state = ShellState::default();
+
+        let output = run_line("set -C; echo a > out; echo b > out; echo c >> out; cat out", &mut state);
+        assert_eq!(output.stdout(), b"a\nc\n");
+        assert_eq!(output.stderr(), b"shell: out: cannot overwrite existing file\n");
+
+        let output = run_line("echo d >| out; cat out; set -f; echo *", &mut state);
+        assert_eq!(output.stdout(), b"d\n*\n");
+    }
+
+    #[test]
+    fn traps() {
+        let mut state = ShellState::default();
+
+        let output = run_line("trap 'echo bye $?' EXIT; set -e; echo a; false; echo b", &mut state);
+        assert_eq!(output.stdout(), b"a\nbye 1\n");
+        assert_eq!(output.status, ExitStatus::FAILURE);
+
+        let mut state = ShellState::default();
+        run_script("trap 'echo cleanup' 0\necho c", &mut state).unwrap();
+        trap::run_exit_trap(&mut state);
+        trap::run_exit_trap(&mut state);
+        assert_eq!(state.output.stdout(), b"c\ncleanup\n");
+
+        let mut state = ShellState::default();
+        let line = "trap 'echo err $?' ERR; false; false && true; ! true; false | true; (exit 3) || true; missing";
+        let output = run_line(line, &mut state);
+        assert_eq!(output.stdout(), b"err 1\nerr 127\n");
+
+        let output = run_line("trap 'echo \"> $BASH_COMMAND\"' DEBUG; echo $((1 + 1)); trap - DEBUG", &mut state);
+        assert_eq!(output.stdout(), b"> echo $((1 + 1))\n2\n> trap - DEBUG\n");
+
+        state.fs.write(std::path::Path::new("/lib.sh"), b"echo in $1").unwrap();
+        let output = run_line("trap 'echo returned $1' RETURN; . /lib.sh a", &mut state);
+        assert_eq!(output.stdout(), b"in a\nreturned a\n");
+
+        run_line("trap 'echo interrupted $?' INT", &mut state);
+        run_line("cat <<EOF", &mut state);
+        trap::interrupt(&mut state);
+        let output = run_line("echo $?", &mut state);
+        assert_eq!(output.stdout(), b"interrupted 130\n130\n");
+        assert!(!state.is_reading_here_doc());
+    }
+
+    #[test]
+    fn read_input() {
+        let mut state = ShellState::default();
+
+        let line = "echo before; IFS=: read -p 'Name: ' first last && echo \"$first|$last\"; echo after";
+        let output = run_line(line, &mut state);
+        assert_eq!(output.stdout(), b"before\n");
+        assert_eq!(output.stderr(), b"Name: ");
+        assert!(state.input_request().is_some());
+
+        let output = run_line("Ada:King: Lovelace", &mut state);
+        assert_eq!(output.stdout(), b"Ada|King: Lovelace\nafter\n");
+        assert_eq!(state.parameter("IFS"), None);
+        assert!(state.input_request().is_none());
+
+        let line = "case x in x) ! read a; echo $?;& y) time -p read b c;; esac; echo $b$c";
+        run_line(line, &mut state);
+        let output = run_line("1", &mut state);
+        assert_eq!(output.stdout(), b"1\n");
+        resume::provide_input(Input::Line(String::from("2 3")), &mut state).unwrap();
+        let output = run_line("true", &mut state);
+        assert_eq!(output.stdout(), b"23\n");
+        assert!(output.stderr().starts_with(b"real 0.00\n"));
+
+        // input redirections and pipelines can't wait, there the input ends
+        let output = run_line("read a <<< 'x y'; echo $a; echo z | read d; echo \"[$d]\"", &mut state);
+        assert_eq!(output.stdout(), b"x y\n[]\n");
+
+        let output = run_line("set -e; read -t 5 a; echo $? $a; read b", &mut state);
+        assert_eq!(state.input_request().and_then(|request| request.timeout), Some(std::time::Duration::from_secs(5)));
+        resume::provide_input(Input::TimedOut, &mut state).unwrap();
+        assert_eq!(state.output.stdout(), b"");
+        assert_eq!(state.exit, Some(ExitStatus::from_signal(14)));
+        assert_eq!(output.stdout(), b"");
+
+        let mut state = ShellState::default();
+        run_script("echo 1\nread -r a || echo end\necho $a 2", &mut state).unwrap();
+        resume::provide_input(Input::End, &mut state).unwrap();
+        assert_eq!(state.output.stdout(), b"1\nend\n2\n");
+
+        run_line("trap 'echo interrupted' INT; read a; echo not run", &mut state);
+        trap::interrupt(&mut state);
+        let output = run_line("echo $?", &mut state);
+        assert_eq!(output.stdout(), b"interrupted\n130\n");
+    }
+
+    #[test]
+    fn read_input_in_nested_lines() {
+        let mut state = ShellState::default();
+        state.fs.write(std::path::Path::new("/ask.sh"), b"read -p 'Name? ' name\necho hi $name $1").unwrap();
+
+        let output = run_line(". /ask.sh x; echo $? $#; FOO=1 eval 'read a; echo got $a' && echo $FOO", &mut state);
+        assert_eq!(output.stderr(), b"Name? ");
+        let output = run_line("Ada", &mut state);
+        assert_eq!(output.stdout(), b"hi Ada x\n0 0\n");
+        let output = run_line("b", &mut state);
+        assert_eq!(output.stdout(), b"got b\n\n");
+        assert!(state.input_request().is_none());
+
+        let output = run_line("x=1; (echo before; read x; echo in $x; exec false; echo not run); echo out $x $?", &mut state);
+        assert_eq!(output.stdout(), b"before\n");
+        let output = run_line("2", &mut state);
+        assert_eq!(output.stdout(), b"in 2\nout 1 1\n");
+        assert_eq!(state.exit, None);
+    }
+
+    #[test]
+    fn script_arguments() {
+        let mut state = ShellState::default();
+
+        let output = run_line("set -- a b; shift $((OPTIND - 1)); echo $OPTIND $#", &mut state);
+        assert_eq!(output.stdout(), b"1 2\n");
+
+        let script = "getopts vo: opt; echo $opt\n\
+                      getopts vo: opt; echo $opt $OPTARG\n\
+                      getopts vo: opt || shift $((OPTIND - 1)); echo $# $@";
+        state.fs.write(std::path::Path::new("/parse.sh"), script.as_bytes()).unwrap();
+
+        let output = run_line(". /parse.sh -v -oout a b", &mut state);
+        assert_eq!(output.stdout(), b"v\no out\n2 a b\n");
+
+        let output = run_line("set -- -x; OPTIND=1; getopts :v opt; echo $opt $OPTARG", &mut state);
+        assert_eq!(output.stdout(), b"? x\n");
+    }
+
+    #[test]
+    fn arrays() {
+        let mut state = ShellState::default();
+
+        let output = run_line("arr=(a \"b c\" d); arr[5]=e; arr+=(f); set -- \"${arr[@]}\"; echo $# ${#arr[@]} ${!arr[@]}", &mut state);
+        assert_eq!(output.stdout(), b"5 5 0 1 2 5 6\n");
+
+        let output = run_line("echo \"${arr[1]}\" ${arr[-1]} ${#arr[1]} $arr; B=(\"$@\"); echo ${#B[@]}", &mut state);
+        assert_eq!(output.stdout(), b"b c f 3 a\n5\n");
+
+        let line = "declare -A m=([x]=1 [y]=2); m[z]+=3; echo ${!m[@]} \"${m[*]}\"; unset 'm[x]' arr; echo ${#m[@]} ${#arr[@]}";
+        let output = run_line(line, &mut state);
+        assert_eq!(output.stdout(), b"x y z 1 2 3\n2 0\n");
+
+        let output = run_line("E=(); set -- \"${E[@]}\"; echo $# \"${E[@]}x\"; E[x]=1", &mut state);
+        assert_eq!(output.stdout(), b"0 x\n");
+        assert_eq!(output.stderr(), b"");
+
+        let output = run_line("set -x; A=(1 $((1 + 1))); A[-5]=1", &mut state);
+        assert_eq!(output.stderr(), b"+ A=(1 $((1 + 1)))\nshell: A[-5]: bad array subscript\n");
+    }
+
+    #[test]
+    fn rc_files() {
+        let mut state = ShellState::default();
+        state.set_variable("HOME", String::from("/"));
+        run_rc_file(&mut state).unwrap();
+        assert!(state.output.chunks().next().is_none());
+
+        let rc = "alias hi='echo hi'\nGREETING=hello\necho started";
+        state.fs.write(std::path::Path::new("/.shellrc"), rc.as_bytes()).unwrap();
+        run_rc_file(&mut state).unwrap();
+        let output = run_line("hi $GREETING", &mut state);
+        assert_eq!(output.stdout(), b"started\nhi hello\n");
+
+        state.set_variable("SHELLRC", String::from("/missing"));
+        run_rc_file(&mut state).unwrap();
+        let output = run_line("SHELLRC=/ && . /.shellrc", &mut state);
+        assert_eq!(output.stdout(), b"started\n");
+        run_rc_file(&mut state).unwrap();
+        assert_eq!(state.output.stderr(), b"shell: /: Is a directory\n");
+    }
+
+    #[test]
+    fn subshells_and_groups() {
+        let fs = crate::filesystem::MemoryFileSystem::default();
+        fs.create_dir_all(std::path::Path::new("/tmp"));
+        let mut state = ShellState::new(std::rc::Rc::new(fs), String::from("/"));
+
+        let output = run_line("A=1; (cd /tmp && A=2 && pwd; echo $A) | cat; pwd; echo $A", &mut state);
+        assert_eq!(output.stdout(), b"/tmp\n2\n/\n1\n");
+
+        let output = run_line("(true; false) || { echo a; echo b; } | cat", &mut state);
+        assert_eq!(output.stdout(), b"a\nb\n");
+
+        let output = run_line("{ A=3; (false); }; echo $A $?", &mut state);
+        assert_eq!(output.stdout(), b"3 1\n");
+    }
+
+    #[test]
+    fn redirections_to_files() {
+        let mut state = ShellState::default();
+
+        let line = "{ echo a; cat missing; } > out 2> err; echo b >> out; cat < out; cat err";
+        let output = run_line(line, &mut state);
+        assert_eq!(output.stdout(), b"a\nb\ncat: missing: No such file or directory\n");
+        assert_eq!(output.stderr(), b"");
+
+        let output = run_line("echo c > out; cat out; cat < none; echo > /none/x", &mut state);
+        assert_eq!(output.stdout(), b"c\n");
+        assert_eq!(
+            output.stderr(),
+            b"shell: none: No such file or directory\nshell: /none/x: No such file or directory\n"
+        );
+        assert_eq!(output.status, ExitStatus::FAILURE);
+    }
+
+    #[test]
+    fn redirections_of_descriptors() {
+        let mut state = ShellState::default();
+
+        let line = "{ echo a; cat missing; } > out 2>&1; cat out; echo z 1>o3; cat o3";
+        let output = run_line(line, &mut state);
+        assert_eq!(output.stdout(), b"a\ncat: missing: No such file or directory\nz\n");
+
+        let output = run_line("cat missing 2>&1 > out | cat; echo e >&2; echo f 1>&2 2>err; cat out", &mut state);
+        assert_eq!(output.stdout(), b"cat: missing: No such file or directory\n");
+        assert_eq!(output.stderr(), b"e\nf\n");
+    }
+
+    #[test]
+    fn negated_pipelines() {
+        let mut state = ShellState::default();
+
+        let output = run_line("! true | false && echo a; ! ! false || echo b; ! (false)", &mut state);
+        assert_eq!(output.stdout(), b"a\nb\n");
+        assert_eq!(output.status, ExitStatus::SUCCESS);
+
+        let output = run_line("! false; echo $? ${PIPESTATUS[0]}", &mut state);
+        assert_eq!(output.stdout(), b"0 1\n");
+    }
+
+    #[test]
+    fn aliases() {
+        let mut state = ShellState::default();
+
+        // like in bash the alias is used from the next line on
+        let output = run_line("alias say='echo said' e='echo '; say 1", &mut state);
+        assert_eq!(output.stderr(), b"shell: command not found: say\n");
+
+        let output = run_line("say 1 && (e say) | cat; 'say' 2", &mut state);
+        assert_eq!(output.stdout(), b"said 1\necho said\n");
+        assert_eq!(output.stderr(), b"shell: command not found: say\n");
+
+        let output = run_line("unalias say; say 3", &mut state);
+        assert_eq!(output.stdout(), b"said 3\n");
+    }
+
+    // every reading of the clock is a second later than the one before
+    #[derive(Debug, Default)]
+    struct TickingClock(crate::clock::ManualClock);
+
+    impl crate::clock::Clock for TickingClock {
+        fn now(&self) -> crate::clock::Times {
+            let now = self.0.now();
+            self.0.advance(crate::clock::Times {
+                real: std::time::Duration::from_secs(1),
+                user: std::time::Duration::from_millis(250),
+                sys: std::time::Duration::from_millis(5),
+            });
+            now
+        }
+
+        fn time(&self) -> std::time::SystemTime {
+            self.0.time()
+        }
+    }
+
+    #[test]
+    fn timed_pipelines() {
+        let mut state = ShellState {
+            clock: std::rc::Rc::new(TickingClock::default()),
+            ..ShellState::default()
+        };
+
+        let output = run_line("time echo a | cat", &mut state);
+        assert_eq!(output.stdout(), b"a\n");
+        assert_eq!(output.stderr(), b"\nreal\t0m1.000s\nuser\t0m0.250s\nsys\t0m0.005s\n");
+
+        let output = run_line("time -p ! true", &mut state);
+        assert_eq!(output.stderr(), b"real 1.00\nuser 0.25\nsys 0.00\n");
+        assert_eq!(output.status, ExitStatus::FAILURE);
+
+        let output = run_line("TIMEFORMAT='%1R %P'; time { true; }; TIMEFORMAT=; time true", &mut state);
+        assert_eq!(output.stderr(), b"1.0 25.50\n");
+    }
+
+    #[test]
+    fn braces_and_tilde_are_expanded() {
+        let mut state = ShellState::default();
+        state.fs.write(std::path::Path::new("/x"), b"").unwrap();
+        state
+            .set_variable("HOME", String::from("/"));
+
+        let output = run_line("echo a{1..3} {x,y}z; cd ~ && pwd", &mut state);
+        assert_eq!(output.stdout(), b"a1 a2 a3 xz yz\n/\n");
+    }
+
+    #[test]
+    fn usage_error_status() {
+        let mut state = ShellState::default();
+
+        let output = run_line("head -x", &mut state);
+
+        assert_eq!(output.status, ExitStatus::USAGE);
+    }
+
+    #[test]
+    fn arithmetic_error_aborts_the_line() {
+        let mut state = ShellState::default();
+
+        let output = run_line("echo $((5/0)) || echo or; { echo in; }; echo after", &mut state);
+        assert_eq!(output.stdout(), b"");
+        assert_eq!(output.stderr(), b"shell: 5/0: division by 0\n");
+        assert_eq!(output.status, ExitStatus::FAILURE);
+
+        // only the line of the error is dropped, eg: the one `eval` runs
+        let line = "(echo $((1/0))); eval 'echo $((1/0)); echo no'; echo yes";
+        let output = run_line(line, &mut state);
+        assert_eq!(output.stdout(), b"yes\n");
+
+        let mut state = ShellState::default();
+        run_script("echo $((5/0)); echo after\necho next", &mut state).unwrap();
+        assert_eq!(state.output.stdout(), b"next\n");
     }
 }
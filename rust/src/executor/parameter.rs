@@ -0,0 +1,853 @@
+use crate::executor::assignment;
+use crate::executor::expansion::{self, ExpansionError};
+use crate::exit_status::ExitStatus;
+use crate::pattern::Pattern;
+use crate::shell_state::ShellState;
+
+// The operator of a `${NAME<operator>}` expression, words are still unexpanded
+#[derive(Debug, PartialEq)]
+enum Operator<'a> {
+    None,
+    Length,
+    // `${!NAME[@]}`, the indexes or keys of an array
+    Keys,
+    // `-`, `=`, `?` and `+`, with `:` they also apply to an empty value
+    Default {
+        word: &'a str,
+        null: bool,
+    },
+    Assign {
+        word: &'a str,
+        null: bool,
+    },
+    Error {
+        word: &'a str,
+        null: bool,
+    },
+    Alternative {
+        word: &'a str,
+        null: bool,
+    },
+    RemovePrefix {
+        pattern: &'a str,
+        longest: bool,
+    },
+    RemoveSuffix {
+        pattern: &'a str,
+        longest: bool,
+    },
+    Replace {
+        pattern: &'a str,
+        replacement: &'a str,
+        mode: ReplaceMode,
+    },
+    Substring {
+        offset: &'a str,
+        length: Option<&'a str>,
+    },
+    Case {
+        pattern: &'a str,
+        upper: bool,
+        all: bool,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum ReplaceMode {
+    First,
+    All,
+    Prefix,
+    Suffix,
+}
+
+// Value of the expression of a `$NAME` or `${...}` parameter, eg: "NAME:-default".
+// Parameters without a value expand to nothing.
+pub fn expand(expression: &str, shell_state: &mut ShellState) -> Result<String, ExpansionError> {
+    let (name, operator) = parse(expression)?;
+    let value = value(name, shell_state)?;
+
+    // nounset leaves out the operators that test whether the parameter is set,
+    // and `$@` which is empty without positional parameters
+    let tests_value = matches!(
+        operator,
+        Operator::Default { .. }
+            | Operator::Assign { .. }
+            | Operator::Error { .. }
+            | Operator::Alternative { .. }
+    );
+    let is_list = matches!(name, "@" | "*") || is_whole_array(name);
+    if shell_state.options.nounset && value.is_none() && !tests_value && !is_list {
+        return Err(fatal(format!("{}: unbound variable", name), shell_state));
+    }
+
+    if let Some(elements) = expand_elements(name, &operator, shell_state)? {
+        return Ok(elements.join(" "));
+    }
+
+    match operator {
+        Operator::None => Ok(value.unwrap_or_default()),
+        Operator::Length => Ok(length(name, value, shell_state).to_string()),
+        Operator::Keys => Ok(keys(name, shell_state).join(" ")),
+        Operator::Default { word, null } => match is_set(&value, null) {
+            true => Ok(value.unwrap_or_default()),
+            false => expand_text(word, shell_state),
+        },
+        Operator::Assign { word, null } => match is_set(&value, null) {
+            true => Ok(value.unwrap_or_default()),
+            false if is_name(name) => {
+                let value = expand_text(word, shell_state)?;
+                shell_state.set_variable(name, value.clone());
+                Ok(value)
+            }
+            false => Err(ExpansionError(format!(
+                "${}: cannot assign in this way",
+                name
+            ))),
+        },
+        Operator::Error { word, null } => match is_set(&value, null) {
+            true => Ok(value.unwrap_or_default()),
+            false => {
+                let message = match expand_text(word, shell_state)? {
+                    message if message.is_empty() => String::from("parameter null or not set"),
+                    message => message,
+                };
+                Err(fatal(format!("{}: {}", name, message), shell_state))
+            }
+        },
+        Operator::Alternative { word, null } => match is_set(&value, null) {
+            true => expand_text(word, shell_state),
+            false => Ok(String::new()),
+        },
+        Operator::RemovePrefix { .. }
+        | Operator::RemoveSuffix { .. }
+        | Operator::Replace { .. }
+        | Operator::Case { .. } => {
+            let change = change(&operator, shell_state)?;
+            Ok(change(&value.unwrap_or_default()))
+        }
+        Operator::Substring { offset, length } => {
+            let (offset, length) = range(offset, length, shell_state)?;
+            let chars: Vec<char> = value.unwrap_or_default().chars().collect();
+            Ok(slice(&chars, offset, length).iter().collect())
+        }
+    }
+}
+
+// a value changed by an operator, eg: `${NAME^^}`
+type Change = Box<dyn Fn(&str) -> String>;
+
+// The operators that change a value, they change each element of a list on its own.
+// Their words are expanded once. Other operators don't change the value.
+fn change(operator: &Operator, shell_state: &mut ShellState) -> Result<Change, ExpansionError> {
+    Ok(match *operator {
+        Operator::RemovePrefix { pattern, longest } => {
+            let pattern = expand_pattern(pattern, shell_state)?;
+            Box::new(move |value| remove_prefix(value, &pattern, longest))
+        }
+        Operator::RemoveSuffix { pattern, longest } => {
+            let pattern = expand_pattern(pattern, shell_state)?;
+            Box::new(move |value| remove_suffix(value, &pattern, longest))
+        }
+        Operator::Replace {
+            pattern,
+            replacement,
+            mode,
+        } => {
+            let pattern = expand_pattern(pattern, shell_state)?;
+            let replacement = expand_text(replacement, shell_state)?;
+            Box::new(move |value| replace(value, &pattern, &replacement, mode))
+        }
+        Operator::Case {
+            pattern,
+            upper,
+            all,
+        } => {
+            let pattern = match pattern {
+                "" => Pattern::new("?"),
+                pattern => expand_pattern(pattern, shell_state)?,
+            };
+            Box::new(move |value| change_case(value, &pattern, upper, all))
+        }
+        _ => Box::new(|value: &str| String::from(value)),
+    })
+}
+
+// offset and length are arithmetic expressions, eg: `${NAME:1:${#NAME}-2}`
+fn range(
+    offset: &str,
+    length: Option<&str>,
+    shell_state: &mut ShellState,
+) -> Result<(i64, Option<i64>), ExpansionError> {
+    let offset = expansion::evaluate_arithmetic(offset, shell_state)?;
+    let length = match length {
+        Some(length) => Some(expansion::evaluate_arithmetic(length, shell_state)?),
+        None => None,
+    };
+
+    Ok((offset, length))
+}
+
+// Splits an expression in to the name of the parameter and its operator
+// >> parse("FILE%.*")
+// ("FILE", RemoveSuffix { pattern: ".*", longest: false })
+fn parse(expression: &str) -> Result<(&str, Operator<'_>), ExpansionError> {
+    let bad_substitution = || ExpansionError(format!("${{{}}}: bad substitution", expression));
+
+    if let Some(name) = expression.strip_prefix('!') {
+        if is_whole_array(name) && name_length(name) == name.len() {
+            return Ok((name, Operator::Keys));
+        }
+    }
+
+    if let Some(name) = expression.strip_prefix('#') {
+        if !name.is_empty() {
+            return match name_length(name) {
+                length if length == name.len() => Ok((name, Operator::Length)),
+                _ => Err(bad_substitution()),
+            };
+        }
+    }
+
+    let name_end = name_length(expression);
+    if name_end == 0 {
+        return Err(bad_substitution());
+    }
+
+    let (name, rest) = expression.split_at(name_end);
+    let operator = match rest {
+        "" => Operator::None,
+        rest if rest.starts_with(":-") => default(&rest[2..], true),
+        rest if rest.starts_with(":=") => assign(&rest[2..], true),
+        rest if rest.starts_with(":?") => error(&rest[2..], true),
+        rest if rest.starts_with(":+") => alternative(&rest[2..], true),
+        rest if rest.starts_with(':') => {
+            let (offset, length) = split_unquoted(&rest[1..], ':');
+            Operator::Substring { offset, length }
+        }
+        rest if rest.starts_with('-') => default(&rest[1..], false),
+        rest if rest.starts_with('=') => assign(&rest[1..], false),
+        rest if rest.starts_with('?') => error(&rest[1..], false),
+        rest if rest.starts_with('+') => alternative(&rest[1..], false),
+        rest if rest.starts_with("##") => Operator::RemovePrefix {
+            pattern: &rest[2..],
+            longest: true,
+        },
+        rest if rest.starts_with('#') => Operator::RemovePrefix {
+            pattern: &rest[1..],
+            longest: false,
+        },
+        rest if rest.starts_with("%%") => Operator::RemoveSuffix {
+            pattern: &rest[2..],
+            longest: true,
+        },
+        rest if rest.starts_with('%') => Operator::RemoveSuffix {
+            pattern: &rest[1..],
+            longest: false,
+        },
+        rest if rest.starts_with('/') => {
+            let (mode, rest) = match &rest[1..] {
+                rest if rest.starts_with('/') => (ReplaceMode::All, &rest[1..]),
+                rest if rest.starts_with('#') => (ReplaceMode::Prefix, &rest[1..]),
+                rest if rest.starts_with('%') => (ReplaceMode::Suffix, &rest[1..]),
+                rest => (ReplaceMode::First, rest),
+            };
+            let (pattern, replacement) = split_unquoted(rest, '/');
+            Operator::Replace {
+                pattern,
+                replacement: replacement.unwrap_or(""),
+                mode,
+            }
+        }
+        rest if rest.starts_with("^^") || rest.starts_with(",,") => Operator::Case {
+            pattern: &rest[2..],
+            upper: rest.starts_with('^'),
+            all: true,
+        },
+        rest if rest.starts_with('^') || rest.starts_with(',') => Operator::Case {
+            pattern: &rest[1..],
+            upper: rest.starts_with('^'),
+            all: false,
+        },
+        _ => return Err(bad_substitution()),
+    };
+
+    Ok((name, operator))
+}
+
+// An error that makes a shell that isn't interactive exit with status 1, eg: a script
+fn fatal(message: String, shell_state: &mut ShellState) -> ExpansionError {
+    if !shell_state.interactive {
+        shell_state.exit.get_or_insert(ExitStatus::FAILURE);
+    }
+
+    ExpansionError(message)
+}
+
+fn default(word: &str, null: bool) -> Operator<'_> {
+    Operator::Default { word, null }
+}
+
+fn assign(word: &str, null: bool) -> Operator<'_> {
+    Operator::Assign { word, null }
+}
+
+fn error(word: &str, null: bool) -> Operator<'_> {
+    Operator::Error { word, null }
+}
+
+fn alternative(word: &str, null: bool) -> Operator<'_> {
+    Operator::Alternative { word, null }
+}
+
+// length of the parameter name at the start of an expression: a special parameter,
+// a name or a name with a subscript, eg: "?", "HOME" or "PIPESTATUS[1]"
+fn name_length(expression: &str) -> usize {
+    let mut chars = expression.char_indices();
+
+    let end = match chars.next() {
+        Some((_, '?' | '$' | '#' | '!' | '@' | '*' | '-')) => return 1,
+        Some((_, char)) if char.is_ascii_digit() => {
+            return expression
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(expression.len())
+        }
+        Some((_, char)) if char.is_ascii_alphabetic() || char == '_' => expression
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(expression.len()),
+        _ => return 0,
+    };
+
+    match expression[end..].starts_with('[') {
+        true => match expression[end..].find(']') {
+            Some(close) => end + close + 1,
+            None => end,
+        },
+        false => end,
+    }
+}
+
+// a name of a variable, eg: "_count2"
+pub fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// `:` operators treat an empty value the same as an unset one
+fn is_set(value: &Option<String>, null: bool) -> bool {
+    match value {
+        Some(value) => !(null && value.is_empty()),
+        None => false,
+    }
+}
+
+// `"$@"`, `"${NAME[@]}"` and `"${!NAME[@]}"` expand to a field per element,
+// there are no fields for an unset or empty array
+// >> "${@:2}" or "${arr[@]/a/A}"
+pub fn expand_fields(
+    expression: &str,
+    shell_state: &mut ShellState,
+) -> Result<Option<Vec<String>>, ExpansionError> {
+    let (name, operator) = parse(expression)?;
+    if name != "@" && !name.ends_with("[@]") {
+        return Ok(None);
+    }
+
+    match operator {
+        Operator::Keys => Ok(Some(keys(name, shell_state))),
+        operator => expand_elements(name, &operator, shell_state),
+    }
+}
+
+// The elements of `$@`, `$*` or a whole array once an operator that applies to each of
+// them did, eg: `${arr[@]#?}`. A substring of the list is a slice of its elements.
+// None for other parameters and operators.
+fn expand_elements(
+    name: &str,
+    operator: &Operator,
+    shell_state: &mut ShellState,
+) -> Result<Option<Vec<String>>, ExpansionError> {
+    let array_name = match split_subscript(name) {
+        Some((array_name, "@" | "*")) => array_name,
+        None if matches!(name, "@" | "*") => name,
+        _ => return Ok(None),
+    };
+    let elements = shell_state
+        .array_parameter(array_name)
+        .map(|array| array.values())
+        .unwrap_or_default();
+
+    match *operator {
+        Operator::None => Ok(Some(elements)),
+        Operator::RemovePrefix { .. }
+        | Operator::RemoveSuffix { .. }
+        | Operator::Replace { .. }
+        | Operator::Case { .. } => {
+            let change = change(operator, shell_state)?;
+            Ok(Some(elements.iter().map(|element| change(element)).collect()))
+        }
+        Operator::Substring { offset, length } => {
+            let (mut offset, length) = range(offset, length, shell_state)?;
+            // the positional parameters start at `$1`, there is no `$0` before them
+            if array_name == name && offset > 0 {
+                offset -= 1;
+            }
+            Ok(Some(slice(&elements, offset, length)))
+        }
+        _ => Ok(None),
+    }
+}
+
+// Splits `NAME[SUBSCRIPT]` in to the name and the subscript
+fn split_subscript(name: &str) -> Option<(&str, &str)> {
+    let (name, subscript) = name.split_once('[')?;
+    Some((name, subscript.strip_suffix(']')?))
+}
+
+fn is_whole_array(name: &str) -> bool {
+    matches!(split_subscript(name), Some((_, "@" | "*")))
+}
+
+// Value of `NAME` or `NAME[SUBSCRIPT]`, a subscript of `@` or `*` is every element
+fn value(name: &str, shell_state: &mut ShellState) -> Result<Option<String>, ExpansionError> {
+    let (name, raw_subscript) = match split_subscript(name) {
+        Some(split) => split,
+        None => return Ok(shell_state.parameter(name)),
+    };
+
+    if let "@" | "*" = raw_subscript {
+        let array = shell_state.array_parameter(name);
+        return Ok(array.map(|array| array.values().join(" ")));
+    }
+
+    let associative = assignment::is_associative(name, shell_state);
+    let subscript = assignment::expand_subscript(name, raw_subscript, associative, shell_state)?;
+    let array = shell_state.array_parameter(name);
+    Ok(array.and_then(|array| array.get(&subscript).cloned()))
+}
+
+// `${#NAME}` is the amount of characters, `${#NAME[@]}` and `${#@}` the amount of elements
+fn length(name: &str, value: Option<String>, shell_state: &ShellState) -> usize {
+    let array_name = match split_subscript(name) {
+        Some((name, "@" | "*")) => name,
+        None if matches!(name, "@" | "*") => name,
+        _ => return value.unwrap_or_default().chars().count(),
+    };
+
+    shell_state
+        .array_parameter(array_name)
+        .map_or(0, |array| array.len())
+}
+
+fn keys(name: &str, shell_state: &ShellState) -> Vec<String> {
+    let name = split_subscript(name).map_or(name, |(name, _)| name);
+    shell_state
+        .array_parameter(name)
+        .map(|array| array.keys())
+        .unwrap_or_default()
+}
+
+fn expand_text(word: &str, shell_state: &mut ShellState) -> Result<String, ExpansionError> {
+    Ok(expansion::expand_word(word, shell_state)?.text)
+}
+
+// quoted parts of a pattern match literally
+fn expand_pattern(word: &str, shell_state: &mut ShellState) -> Result<Pattern, ExpansionError> {
+    Ok(Pattern::new(
+        &expansion::expand_word(word, shell_state)?.pattern,
+    ))
+}
+
+// Splits on the first separator that is not quoted or escaped
+fn split_unquoted(text: &str, separator: char) -> (&str, Option<&str>) {
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, char) in text.char_indices() {
+        match (char, quote) {
+            _ if escaped => escaped = false,
+            ('\\', Some('\'')) => (),
+            ('\\', _) => escaped = true,
+            (c, Some(q)) if c == q => quote = None,
+            ('\'' | '"', None) => quote = Some(char),
+            (c, None) if c == separator => return (&text[..i], Some(&text[i + 1..])),
+            _ => (),
+        }
+    }
+
+    (text, None)
+}
+
+// byte offsets of every char boundary, including the end
+fn boundaries(value: &str) -> Vec<usize> {
+    value
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(value.len()))
+        .collect()
+}
+
+fn remove_prefix(value: &str, pattern: &Pattern, longest: bool) -> String {
+    let mut ends = boundaries(value);
+    if longest {
+        ends.reverse();
+    }
+
+    match ends.into_iter().find(|end| pattern.matches(&value[..*end])) {
+        Some(end) => String::from(&value[end..]),
+        None => String::from(value),
+    }
+}
+
+fn remove_suffix(value: &str, pattern: &Pattern, longest: bool) -> String {
+    let mut starts = boundaries(value);
+    if !longest {
+        starts.reverse();
+    }
+
+    match starts
+        .into_iter()
+        .find(|start| pattern.matches(&value[*start..]))
+    {
+        Some(start) => String::from(&value[..start]),
+        None => String::from(value),
+    }
+}
+
+// `${NAME/pattern/replacement}`, every match is the longest match at its position
+fn replace(value: &str, pattern: &Pattern, replacement: &str, mode: ReplaceMode) -> String {
+    let bounds = boundaries(value);
+    let longest_match = |start: usize| {
+        bounds
+            .iter()
+            .rev()
+            .filter(|end| **end >= start)
+            .find(|end| pattern.matches(&value[start..**end]))
+            .copied()
+    };
+
+    match mode {
+        ReplaceMode::Prefix => match longest_match(0) {
+            Some(end) => format!("{}{}", replacement, &value[end..]),
+            None => String::from(value),
+        },
+        ReplaceMode::Suffix => match bounds
+            .iter()
+            .find(|start| pattern.matches(&value[**start..]))
+        {
+            Some(start) => format!("{}{}", &value[..*start], replacement),
+            None => String::from(value),
+        },
+        ReplaceMode::First | ReplaceMode::All => {
+            let mut result = String::new();
+            let mut i = 0;
+            let mut replaced = false;
+
+            while i < bounds.len() {
+                let start = bounds[i];
+                let found = match replaced && mode == ReplaceMode::First {
+                    true => None,
+                    // an empty match would replace between every character
+                    false => longest_match(start).filter(|end| *end > start),
+                };
+
+                match found {
+                    Some(end) => {
+                        result.push_str(replacement);
+                        replaced = true;
+                        i = bounds
+                            .iter()
+                            .position(|bound| *bound == end)
+                            .unwrap_or(i + 1);
+                    }
+                    None if start < value.len() => {
+                        result.push_str(&value[start..bounds[i + 1]]);
+                        i += 1;
+                    }
+                    None => break,
+                }
+            }
+
+            result
+        }
+    }
+}
+
+// `${NAME:offset:length}` of the chars of a value or the elements of a list, a negative
+// offset counts from the end and a negative length is an offset from the end
+fn slice<T: Clone>(items: &[T], offset: i64, length: Option<i64>) -> Vec<T> {
+    let len = items.len() as i64;
+
+    let start = match offset < 0 {
+        true => (len + offset).max(0),
+        false => offset.min(len),
+    };
+    let end = match length {
+        None => len,
+        Some(length) if length < 0 => (len + length).max(start),
+        Some(length) => (start + length).min(len),
+    };
+
+    items[start as usize..end as usize].to_vec()
+}
+
+fn change_case(value: &str, pattern: &Pattern, upper: bool, all: bool) -> String {
+    let mut result = String::new();
+
+    for (i, char) in value.chars().enumerate() {
+        let applies = (all || i == 0) && pattern.matches(&char.to_string());
+
+        match (applies, upper) {
+            (true, true) => result.extend(char.to_uppercase()),
+            (true, false) => result.extend(char.to_lowercase()),
+            (false, _) => result.push(char),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variable::Value;
+
+    fn state() -> ShellState {
+        let mut state = ShellState::default();
+        for (name, value) in [
+            ("FILE", "archive.tar.gz"),
+            ("PATH_", "/usr/local/bin"),
+            ("EMPTY", ""),
+            ("NAME", "hello world"),
+        ] {
+            state.set_variable(name, String::from(value));
+        }
+
+        state
+    }
+
+    fn expand_ok(expression: &str, state: &mut ShellState) -> String {
+        expand(expression, state).unwrap()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let mut state = state();
+
+        let expected = vec![
+            ("UNSET:-a", "a"),
+            ("EMPTY:-a", "a"),
+            ("EMPTY-a", ""),
+            ("FILE:-a", "archive.tar.gz"),
+            ("UNSET:+a", ""),
+            ("EMPTY+a", "a"),
+            ("EMPTY:+a", ""),
+            ("FILE:+'a b'", "a b"),
+            ("UNSET:-$FILE", "archive.tar.gz"),
+            ("UNSET:-\"${EMPTY:-x}\"", "x"),
+        ];
+
+        for (expression, result) in expected {
+            assert_eq!(expand_ok(expression, &mut state), result, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn test_assign_default() {
+        let mut state = state();
+
+        assert_eq!(expand_ok("NEW:=value", &mut state), "value");
+        assert_eq!(state.parameter("NEW"), Some(String::from("value")));
+        assert_eq!(expand_ok("NEW:=other", &mut state), "value");
+
+        assert_eq!(
+            expand("1:=x", &mut state),
+            Err(ExpansionError(String::from(
+                "$1: cannot assign in this way"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_error_when_unset() {
+        let mut state = state();
+
+        assert_eq!(
+            expand("UNSET:?is required", &mut state),
+            Err(ExpansionError(String::from("UNSET: is required")))
+        );
+        assert_eq!(
+            expand("EMPTY:?", &mut state),
+            Err(ExpansionError(String::from(
+                "EMPTY: parameter null or not set"
+            )))
+        );
+        assert_eq!(expand_ok("EMPTY?", &mut state), "");
+    }
+
+    #[test]
+    fn test_nounset() {
+        let mut state = state();
+        state.options.nounset = true;
+
+        assert_eq!(
+            expand("UNSET", &mut state),
+            Err(ExpansionError(String::from("UNSET: unbound variable")))
+        );
+        assert!(expand("#UNSET", &mut state).is_err());
+        assert!(expand("1", &mut state).is_err());
+
+        for expression in ["EMPTY", "UNSET:-a", "UNSET+a", "UNSET:=b", "@", "ARRAY[@]"] {
+            assert!(expand(expression, &mut state).is_ok(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn test_length() {
+        let mut state = state();
+        state.pipe_status = vec![Default::default(); 3];
+
+        assert_eq!(expand_ok("#FILE", &mut state), "14");
+        assert_eq!(expand_ok("#UNSET", &mut state), "0");
+        assert_eq!(expand_ok("#PIPESTATUS[@]", &mut state), "3");
+
+        state.positional_parameters = vec![String::from("one"); 3];
+        assert_eq!(expand_ok("#@", &mut state), "3");
+        assert_eq!(expand_ok("#*", &mut state), "3");
+    }
+
+    #[test]
+    fn test_operators_on_elements() {
+        let mut state = state();
+        state.variables.insert(
+            String::from("arr"),
+            Value::indexed(vec![String::from("ab"), String::from("ba c")]),
+        );
+        state.positional_parameters = ["one", "two", "three"].map(String::from).to_vec();
+
+        let expected = vec![
+            ("arr[@]^^", vec!["AB", "BA C"]),
+            ("arr[@]/a/A", vec!["Ab", "bA c"]),
+            ("arr[@]#?", vec!["b", "a c"]),
+            ("arr[@]%%' '*", vec!["ab", "ba"]),
+            ("arr[@]:1", vec!["ba c"]),
+            ("@:2", vec!["two", "three"]),
+            ("@: -1", vec!["three"]),
+            ("@:1:2", vec!["one", "two"]),
+            ("@#t", vec!["one", "wo", "hree"]),
+        ];
+
+        for (expression, fields) in expected {
+            assert_eq!(
+                expand_fields(expression, &mut state),
+                Ok(Some(fields.into_iter().map(String::from).collect())),
+                "{}",
+                expression
+            );
+        }
+
+        assert_eq!(expand_ok("*:2", &mut state), "two three");
+        assert_eq!(expand_ok("arr[*]^", &mut state), "Ab Ba c");
+        assert_eq!(expand_fields("arr[*]^", &mut state), Ok(None));
+    }
+
+    #[test]
+    fn test_remove_prefix_and_suffix() {
+        let mut state = state();
+
+        let expected = vec![
+            ("FILE#*.", "tar.gz"),
+            ("FILE##*.", "gz"),
+            ("FILE%.*", "archive.tar"),
+            ("FILE%%.*", "archive"),
+            ("FILE#nomatch", "archive.tar.gz"),
+            ("PATH_##*/", "bin"),
+            ("PATH_%/*", "/usr/local"),
+            ("FILE%'.gz'", "archive.tar"),
+            ("FILE%\".*\"", "archive.tar.gz"),
+        ];
+
+        for (expression, result) in expected {
+            assert_eq!(expand_ok(expression, &mut state), result, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut state = state();
+
+        let expected = vec![
+            ("NAME/o/0", "hell0 world"),
+            ("NAME//o/0", "hell0 w0rld"),
+            ("NAME/#hello/bye", "bye world"),
+            ("NAME/#world/bye", "hello world"),
+            ("NAME/%world/all", "hello all"),
+            ("NAME//[lo]", "he wrd"),
+            ("NAME/l*o", "herld"),
+            ("PATH_//\\//:", ":usr:local:bin"),
+        ];
+
+        for (expression, result) in expected {
+            assert_eq!(expand_ok(expression, &mut state), result, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn test_substring() {
+        let mut state = state();
+
+        let expected = vec![
+            ("NAME:6", "world"),
+            ("NAME:0:5", "hello"),
+            ("NAME: -5", "world"),
+            ("NAME: -5:2", "wo"),
+            ("NAME:2:-2", "llo wor"),
+            ("NAME:20", ""),
+        ];
+
+        for (expression, result) in expected {
+            assert_eq!(expand_ok(expression, &mut state), result, "{}", expression);
+        }
+
+        assert_eq!(expand_ok("NAME:1+1:${#NAME}-8", &mut state), "llo");
+        assert_eq!(
+            expand("NAME:1/0", &mut state),
+            Err(ExpansionError(String::from("1/0: division by 0")))
+        );
+    }
+
+    #[test]
+    fn test_case() {
+        let mut state = state();
+
+        let expected = vec![
+            ("NAME^", "Hello world"),
+            ("NAME^^", "HELLO WORLD"),
+            ("NAME^^[lo]", "heLLO wOrLd"),
+            ("FILE,,", "archive.tar.gz"),
+        ];
+
+        for (expression, result) in expected {
+            assert_eq!(expand_ok(expression, &mut state), result, "{}", expression);
+        }
+
+        state
+            .set_variable("UP", String::from("ABC"));
+        assert_eq!(expand_ok("UP,", &mut state), "aBC");
+        assert_eq!(expand_ok("UP,,", &mut state), "abc");
+    }
+
+    #[test]
+    fn test_bad_substitution() {
+        let mut state = state();
+
+        for expression in ["", "FILE!", "%x", "#FILE%x"] {
+            assert_eq!(
+                expand(expression, &mut state),
+                Err(ExpansionError(format!(
+                    "${{{}}}: bad substitution",
+                    expression
+                )))
+            );
+        }
+    }
+}
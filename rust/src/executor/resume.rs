@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+use crate::builtin::source;
+use crate::clock::Times;
+use crate::command::Command;
+use crate::exception::Exception;
+use crate::executor::{self, trap};
+use crate::exit_status::ExitStatus;
+use crate::parser::ast::{AstItem, CaseItem, LogicalExpressionOp};
+use crate::shell_state::ShellState;
+use crate::variable::Value;
+
+// A line can't block until the host has input, eg: in the browser. `read` suspends the
+// line instead: while the executor returns, every item that still had something to do
+// leaves a frame. The host gives the input with `provide_input`, then the frames run.
+#[derive(Debug, Clone)]
+pub struct Suspension {
+    pub request: InputRequest,
+    // the innermost frame first, it's the command that waits
+    frames: Vec<Frame>,
+    // `status_tested` when the line suspended, the frames of tested items lower it again
+    status_tested: usize,
+    // the command that waits runs again as this one, see `await_input_retrying`
+    retry: Option<Command>,
+    // the command that waits is done once the frames of its lines ran, see `defer_lines`
+    done: bool,
+}
+
+// How the host should get the input, eg: `read -s -t 5`
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputRequest {
+    // the typed text isn't shown, eg: for a password
+    pub silent: bool,
+    // without input after this time the host gives `Input::TimedOut`
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    // a line without its line break
+    Line(String),
+    // the input ended, eg: Ctrl-D
+    End,
+    TimedOut,
+}
+
+// what's left to do of an item once the line resumes
+#[derive(Debug, Clone)]
+pub enum Frame {
+    // the command that waits runs again with the input, then the assignments in front of it
+    // get their old values back
+    Command {
+        command: Command,
+        previous: Vec<(String, Option<Value>)>,
+    },
+    // the items of a list after the one that waited
+    Evaluate(AstItem),
+    // `left && right` or `left || right` that waited in the left item
+    Logical {
+        op: LogicalExpressionOp,
+        right: AstItem,
+    },
+    // the status of the items before this frame is tested, see `evaluate_tested`
+    Tested,
+    // `! pipeline`
+    Negate,
+    // `time pipeline` reports once the pipeline is done
+    Timed {
+        start: Times,
+        posix: bool,
+    },
+    // the items of a case after the body that waited
+    Case {
+        text: String,
+        items: Vec<CaseItem>,
+        fall_through: bool,
+    },
+    // the lines of a script after the one that waited
+    Lines(Vec<String>),
+    // a command whose lines waited, eg: `eval read a`, the assignments in front of it
+    // get their old values back once they ran
+    Finish(Vec<(String, Option<Value>)>),
+    // a sourced script that waited, the RETURN trap runs and the positional parameters
+    // of the caller are set again once it ran
+    Sourced {
+        name: String,
+        path: String,
+        saved: Option<Vec<String>>,
+    },
+    // `( list )` that waited, it resumes in its copy of the shell state
+    Subshell(Box<ShellState>),
+}
+
+// Suspends the running line, see `Suspension`
+pub fn await_input(request: InputRequest, shell_state: &mut ShellState) {
+    shell_state.suspension = Some(Suspension {
+        request,
+        frames: Vec::new(),
+        status_tested: shell_state.status_tested,
+        retry: None,
+        done: false,
+    });
+}
+
+// Suspends the running line like `await_input`, but once it resumes the command runs
+// again as `command`, eg: `rm -i` without the files it's done with
+pub fn await_input_retrying(request: InputRequest, command: Command, shell_state: &mut ShellState) {
+    await_input(request, shell_state);
+    if let Some(suspension) = &mut shell_state.suspension {
+        suspension.retry = Some(command);
+    }
+}
+
+// the frame of a command that waits, it runs again once the line resumes
+pub fn defer_command(
+    command: Command,
+    previous: Vec<(String, Option<Value>)>,
+    shell_state: &mut ShellState,
+) {
+    let frame = match &mut shell_state.suspension {
+        Some(suspension) if suspension.done => {
+            suspension.done = false;
+            Frame::Finish(previous)
+        }
+        Some(suspension) => Frame::Command {
+            command: suspension.retry.take().unwrap_or(command),
+            previous,
+        },
+        None => return,
+    };
+
+    defer(shell_state, || frame);
+}
+
+// A command that runs lines, eg: `eval`, leaves the frame for what it does after them
+// when they wait. It doesn't run again once the line resumes, it's done with the frames.
+// Returns whether the line waits.
+pub fn defer_lines(shell_state: &mut ShellState, frame: impl FnOnce() -> Option<Frame>) -> bool {
+    let suspension = match &mut shell_state.suspension {
+        Some(suspension) => suspension,
+        None => return false,
+    };
+
+    suspension.frames.extend(frame());
+    suspension.done = true;
+    true
+}
+
+// A subshell that waits suspends the line around it too, it resumes in its own state
+pub fn defer_subshell(subshell: ShellState, shell_state: &mut ShellState) {
+    let request = match &subshell.suspension {
+        Some(suspension) => suspension.request.clone(),
+        None => return,
+    };
+
+    await_input(request, shell_state);
+    defer(shell_state, || Frame::Subshell(Box::new(subshell)));
+}
+
+// When the line waits for input, the frame is what the item does once it resumes.
+// Returns whether the line waits.
+pub fn defer(shell_state: &mut ShellState, frame: impl FnOnce() -> Frame) -> bool {
+    match &mut shell_state.suspension {
+        Some(suspension) => {
+            suspension.frames.push(frame());
+            true
+        }
+        None => false,
+    }
+}
+
+// Continues the line that waits with the input of the host. When it waits again,
+// the frames that are left follow the ones of the new wait.
+pub fn provide_input(input: Input, shell_state: &mut ShellState) -> Result<(), Exception> {
+    let suspension = match shell_state.suspension.take() {
+        Some(suspension) => suspension,
+        None => return Ok(()),
+    };
+
+    shell_state.input = Some(input);
+    shell_state.status_tested = suspension.status_tested;
+    let mut frames = suspension.frames.into_iter();
+    let mut result = Ok(());
+
+    while let Some(frame) = frames.next() {
+        if shell_state.exit.is_some() {
+            break;
+        }
+        // the lines after the one that waited run even when it was aborted
+        if matches!(frame, Frame::Lines(_)) {
+            shell_state.aborted = false;
+        }
+
+        result = resume(frame, shell_state);
+
+        if let Some(suspension) = &mut shell_state.suspension {
+            suspension.frames.extend(frames);
+            return result;
+        }
+        if result.is_err() {
+            break;
+        }
+    }
+
+    shell_state.input = None;
+    shell_state.status_tested = 0;
+    shell_state.aborted = false;
+    if shell_state.exit.is_some() {
+        trap::run_exit_trap(shell_state);
+    }
+
+    result
+}
+
+fn resume(frame: Frame, shell_state: &mut ShellState) -> Result<(), Exception> {
+    match frame {
+        Frame::Command { command, previous } => {
+            let status = executor::execute_command(&command, shell_state);
+            match shell_state.suspension.is_some() {
+                true => defer_command(command, previous, shell_state),
+                false => {
+                    executor::restore_variables(previous, shell_state);
+                    executor::finish_command(status, shell_state);
+                }
+            }
+        }
+        Frame::Evaluate(item) => executor::evaluate(&item, shell_state),
+        Frame::Logical { op, right } => executor::evaluate_right(&op, &right, shell_state),
+        Frame::Tested => shell_state.status_tested -= 1,
+        Frame::Negate => {
+            shell_state.output.status = ExitStatus::from(!shell_state.output.is_ok());
+        }
+        Frame::Timed { start, posix } => executor::report_time(&start, posix, shell_state),
+        Frame::Case {
+            text,
+            items,
+            fall_through,
+        } => {
+            let status = shell_state.output.status;
+            if let Err(error) =
+                executor::evaluate_case_items(&text, &items, fall_through, status, shell_state)
+            {
+                let status = executor::write_expansion_error(error, shell_state);
+                executor::finish_command(status, shell_state);
+            }
+        }
+        Frame::Lines(lines) => executor::run_script(&lines.join("\n"), shell_state)?,
+        Frame::Finish(previous) => {
+            executor::restore_variables(previous, shell_state);
+            let status = shell_state.output.status;
+            executor::finish_command(status, shell_state);
+        }
+        Frame::Sourced { name, path, saved } => {
+            source::finish(&name, &path, Ok(()), saved, shell_state);
+        }
+        Frame::Subshell(mut subshell) => {
+            let input = shell_state.input.take().unwrap_or(Input::End);
+            let result = provide_input(input, &mut subshell);
+            executor::finish_subshell(*subshell, shell_state);
+            if shell_state.suspension.is_none() {
+                executor::check_status(shell_state);
+            }
+            result?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::clock::Times;
+use std::time::Duration;
+
+// the format of bash when TIMEFORMAT is not set
+pub const DEFAULT_FORMAT: &str = "\nreal\t%3lR\nuser\t%3lU\nsys\t%3lS";
+// the format of `time -p`
+pub const POSIX_FORMAT: &str = "real %2R\nuser %2U\nsys %2S";
+
+// Formats the times of `time` like TIMEFORMAT in bash. `%[p][l]R`, `%[p][l]U` and `%[p][l]S`
+// are the real, user and sys time with p decimals, l writes them in minutes and seconds.
+// `%P` is the cpu percentage and `%%` a "%".
+// >> format_times("real %2lR", times)
+// "real 1m5.25s"
+pub fn format_times(format: &str, times: &Times) -> String {
+    let chars: Vec<char> = format.chars().collect();
+    let mut result = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != '%' {
+            result.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        let mut end = i + 1;
+        let precision = match chars.get(end).and_then(|char| char.to_digit(10)) {
+            Some(digit) => {
+                end += 1;
+                digit.min(3) as usize
+            }
+            None => 3,
+        };
+        let long = chars.get(end) == Some(&'l');
+        if long {
+            end += 1;
+        }
+
+        let duration = match chars.get(end) {
+            Some('R') => Some(times.real),
+            Some('U') => Some(times.user),
+            Some('S') => Some(times.sys),
+            _ => None,
+        };
+
+        match (chars.get(end), duration) {
+            (_, Some(duration)) => result.push_str(&format_duration(duration, precision, long)),
+            (Some('P'), _) => result.push_str(&cpu_percentage(times)),
+            (Some('%'), _) if end == i + 1 => result.push('%'),
+            // an unknown format is written as is
+            _ => {
+                result.extend(&chars[i..(end + 1).min(chars.len())]);
+            }
+        }
+
+        i = end + 1;
+    }
+
+    result
+}
+
+// the fraction is cut off, not rounded, eg: 1.999 with one decimal is "1.9"
+fn format_duration(duration: Duration, precision: usize, long: bool) -> String {
+    let millis = duration.as_millis();
+    let seconds = millis / 1000;
+    let fraction = match precision {
+        0 => String::new(),
+        precision => {
+            let digits = (millis % 1000) / 10u128.pow(3 - precision as u32);
+            format!(".{:0width$}", digits, width = precision)
+        }
+    };
+
+    match long {
+        true => format!("{}m{}{}s", seconds / 60, seconds % 60, fraction),
+        false => format!("{}{}", seconds, fraction),
+    }
+}
+
+fn cpu_percentage(times: &Times) -> String {
+    let real = times.real.as_secs_f64();
+    let cpu = (times.user + times.sys).as_secs_f64();
+
+    match real > 0.0 {
+        true => format!("{:.2}", cpu * 100.0 / real),
+        false => String::from("0.00"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times() -> Times {
+        Times {
+            real: Duration::from_millis(65_259),
+            user: Duration::from_millis(1_999),
+            sys: Duration::from_millis(10),
+        }
+    }
+
+    #[test]
+    fn test_formats() {
+        let expected = vec![
+            (DEFAULT_FORMAT, "\nreal\t1m5.259s\nuser\t0m1.999s\nsys\t0m0.010s"),
+            (POSIX_FORMAT, "real 65.25\nuser 1.99\nsys 0.01"),
+            ("%R %0R %1U %9lS", "65.259 65 1.9 0m0.010s"),
+            ("%P%% cpu", "3.08% cpu"),
+            ("%x %l %", "%x %l %"),
+            ("", ""),
+        ];
+
+        for (format, result) in expected {
+            assert_eq!(format_times(format, &times()), result, "format {:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_no_time_passed() {
+        assert_eq!(format_times("%R %lU %P", &Times::default()), "0.000 0m0.000s 0.00");
+    }
+}
@@ -0,0 +1,141 @@
+use crate::executor;
+use crate::exit_status::{ExitStatus, SIGINT};
+use crate::shell_state::ShellState;
+
+// The signals a trap can be set for, in the order `trap` lists them.
+// EXIT, DEBUG, ERR and RETURN are not real signals, the shell raises them itself.
+pub const SIGNALS: [&str; 5] = ["EXIT", "INT", "DEBUG", "ERR", "RETURN"];
+
+// Name of a signal given to `trap`, signals can be written with a "SIG" prefix,
+// in lowercase or as their number
+// >> signal_name("sigint")
+// Some("INT")
+pub fn signal_name(spec: &str) -> Option<&'static str> {
+    let spec = spec.to_ascii_uppercase();
+    let name = match spec.as_str() {
+        "0" => "EXIT",
+        "2" => "INT",
+        spec => spec.strip_prefix("SIG").unwrap_or(spec),
+    };
+
+    SIGNALS.iter().find(|signal| **signal == name).copied()
+}
+
+// Runs the action of the trap for a signal. `$?` is the same before and after it, and
+// no trap is raised by the commands of an action. The action can change its own trap,
+// eg: `trap - ERR` makes it run once.
+pub fn run_trap(signal: &str, shell_state: &mut ShellState) {
+    if shell_state.running_trap {
+        return;
+    }
+
+    let action = match shell_state.traps.get(signal) {
+        Some(action) => action.clone(),
+        None => return,
+    };
+    // the EXIT trap only runs once
+    if signal == "EXIT" {
+        shell_state.traps.remove(signal);
+    }
+
+    let status = shell_state.output.status;
+    shell_state.running_trap = true;
+    if let Err(exception) = executor::run_nested(&action, shell_state) {
+        shell_state
+            .output
+            .write_stderr(format!("trap: {}\n", exception));
+    }
+    shell_state.running_trap = false;
+    shell_state.output.status = status;
+}
+
+// Runs the EXIT trap, when the shell exits or its input ends
+pub fn run_exit_trap(shell_state: &mut ShellState) {
+    let exit = shell_state.exit.take();
+    run_trap("EXIT", shell_state);
+    shell_state.exit = exit;
+}
+
+// The host cancels what the shell is doing, eg: Ctrl-C at the prompt. An unfinished
+// here-document or a line that waits for input is dropped and the status becomes the one
+// of SIGINT, unless it is ignored.
+pub fn interrupt(shell_state: &mut ShellState) {
+    if shell_state
+        .traps
+        .get("INT")
+        .is_some_and(|action| action.is_empty())
+    {
+        return;
+    }
+
+    shell_state.pending_here_docs = None;
+    shell_state.open_command = None;
+    shell_state.suspension = None;
+    shell_state.output.status = ExitStatus::from_signal(SIGINT);
+    run_trap("INT", shell_state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_names() {
+        let expected = vec![
+            ("EXIT", Some("EXIT")),
+            ("0", Some("EXIT")),
+            ("int", Some("INT")),
+            ("SIGINT", Some("INT")),
+            ("2", Some("INT")),
+            ("err", Some("ERR")),
+            ("SIGEXIT", Some("EXIT")),
+            ("HUP", None),
+            ("", None),
+        ];
+
+        for (spec, name) in expected {
+            assert_eq!(signal_name(spec), name, "signal {}", spec);
+        }
+    }
+
+    #[test]
+    fn test_status_is_kept() {
+        let mut state = ShellState::default();
+        state
+            .traps
+            .insert(String::from("ERR"), String::from("echo $?; false"));
+        state.output.status = ExitStatus::NOT_FOUND;
+
+        run_trap("ERR", &mut state);
+
+        assert_eq!(state.output.stdout(), b"127\n");
+        assert_eq!(state.output.status, ExitStatus::NOT_FOUND);
+        assert!(state.traps.contains_key("ERR"));
+    }
+
+    #[test]
+    fn test_action_that_resets_its_trap() {
+        let mut state = ShellState::default();
+        state
+            .traps
+            .insert(String::from("ERR"), String::from("echo once; trap - ERR"));
+
+        run_trap("ERR", &mut state);
+        run_trap("ERR", &mut state);
+
+        assert_eq!(state.output.stdout(), b"once\n");
+        assert!(state.traps.is_empty());
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let mut state = ShellState::default();
+        interrupt(&mut state);
+        assert_eq!(state.output.status, ExitStatus::from_signal(SIGINT));
+
+        let mut state = ShellState::default();
+        state.traps.insert(String::from("INT"), String::new());
+        interrupt(&mut state);
+        assert_eq!(state.output.status, ExitStatus::SUCCESS);
+    }
+}
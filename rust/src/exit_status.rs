@@ -0,0 +1,94 @@
+use std::fmt::{Display, Formatter};
+
+// Signal numbers as used by POSIX, a command terminated by a signal exits with 128 + signal
+pub const SIGINT: u8 = 2;
+// `read -t` times out like an alarm ends a process
+pub const SIGALRM: u8 = 14;
+
+// Status of a finished command, always in the range 0-255 like a real process.
+// Every command has a status, a shell that hasn't run anything yet reports success.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ExitStatus(u8);
+
+impl ExitStatus {
+    pub const SUCCESS: ExitStatus = ExitStatus(0);
+    pub const FAILURE: ExitStatus = ExitStatus(1);
+    // wrong usage of a builtin, eg: an invalid option
+    pub const USAGE: ExitStatus = ExitStatus(2);
+    // command was found but can't be executed
+    pub const NOT_EXECUTABLE: ExitStatus = ExitStatus(126);
+    pub const NOT_FOUND: ExitStatus = ExitStatus(127);
+
+    // Statuses wrap around like they do for a process
+    // >> ExitStatus::new(256)
+    // ExitStatus(0)
+    // >> ExitStatus::new(-1)
+    // ExitStatus(255)
+    pub fn new(code: i64) -> Self {
+        ExitStatus(code.rem_euclid(256) as u8)
+    }
+
+    pub fn from_signal(signal: u8) -> Self {
+        ExitStatus(128u8.saturating_add(signal))
+    }
+
+    pub fn code(self) -> u8 {
+        self.0
+    }
+
+    pub fn is_success(self) -> bool {
+        self.0 == 0
+    }
+
+    // the signal that terminated the command, if it was terminated by one
+    pub fn signal(self) -> Option<u8> {
+        match self.0 {
+            129..=255 => Some(self.0 - 128),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for ExitStatus {
+    fn from(success: bool) -> Self {
+        match success {
+            true => ExitStatus::SUCCESS,
+            false => ExitStatus::FAILURE,
+        }
+    }
+}
+
+impl Display for ExitStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_wraps_around() {
+        assert_eq!(ExitStatus::new(0), ExitStatus::SUCCESS);
+        assert_eq!(ExitStatus::new(255).code(), 255);
+        assert_eq!(ExitStatus::new(256), ExitStatus::SUCCESS);
+        assert_eq!(ExitStatus::new(-1).code(), 255);
+        assert_eq!(ExitStatus::new(-255), ExitStatus::FAILURE);
+    }
+
+    #[test]
+    fn test_signal() {
+        let status = ExitStatus::from_signal(SIGINT);
+
+        assert_eq!(status.code(), 130);
+        assert_eq!(status.signal(), Some(SIGINT));
+        assert_eq!(ExitStatus::NOT_FOUND.signal(), None);
+    }
+
+    #[test]
+    fn test_from_bool() {
+        assert_eq!(ExitStatus::from(true), ExitStatus::SUCCESS);
+        assert_eq!(ExitStatus::from(false), ExitStatus::FAILURE);
+    }
+}
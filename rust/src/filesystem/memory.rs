@@ -0,0 +1,473 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crate::clock::{Clock, ManualClock};
+use crate::filesystem::{self, FileSystem, FileType, Metadata};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    File(Vec<u8>),
+    Directory,
+    Symlink(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    node: Node,
+    modified: SystemTime,
+}
+
+// Filesystem that only exists in memory, used in wasm where there is no real filesystem.
+// Every node is stored by its absolute path without links in it, the root directory
+// always exists.
+#[derive(Debug)]
+pub struct MemoryFileSystem {
+    nodes: RefCell<BTreeMap<PathBuf, Entry>>,
+    // the time of day files are modified at
+    clock: Rc<dyn Clock>,
+}
+
+impl Default for MemoryFileSystem {
+    fn default() -> Self {
+        MemoryFileSystem::new(Rc::new(ManualClock::default()))
+    }
+}
+
+impl MemoryFileSystem {
+    pub fn new(clock: Rc<dyn Clock>) -> Self {
+        let mut nodes = BTreeMap::new();
+        nodes.insert(
+            PathBuf::from("/"),
+            Entry {
+                node: Node::Directory,
+                modified: clock.time(),
+            },
+        );
+
+        MemoryFileSystem {
+            nodes: RefCell::new(nodes),
+            clock,
+        }
+    }
+
+    // Creates a directory and all of its parents, used to prepare a tree for the shell
+    pub fn create_dir_all(&self, path: &Path) {
+        let mut nodes = self.nodes.borrow_mut();
+
+        for ancestor in path.ancestors() {
+            nodes.entry(ancestor.to_path_buf()).or_insert(Entry {
+                node: Node::Directory,
+                modified: self.clock.time(),
+            });
+        }
+    }
+
+    fn node(&self, path: &Path) -> Option<Node> {
+        self.nodes.borrow().get(path).map(|entry| entry.node.clone())
+    }
+
+    // the path the node is stored at, see `filesystem::follow_links`
+    fn follow(&self, path: &Path, last: bool) -> io::Result<PathBuf> {
+        filesystem::follow_links(path, last, |path| match self.nodes.borrow().get(path) {
+            Some(Entry {
+                node: Node::Symlink(target),
+                ..
+            }) => Some(target.clone()),
+            _ => None,
+        })
+    }
+
+    fn parent_is_directory(&self, path: &Path) -> io::Result<()> {
+        let parent = path.parent().ok_or(io::ErrorKind::AlreadyExists)?;
+
+        match self.node(parent) {
+            Some(Node::Directory) => Ok(()),
+            Some(_) => Err(io::ErrorKind::NotADirectory.into()),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn has_children(&self, path: &Path) -> bool {
+        let nodes = self.nodes.borrow();
+        let next = nodes.range(path.to_path_buf()..).nth(1);
+
+        next.is_some_and(|(child, _)| child.starts_with(path))
+    }
+
+    // adds or removes an entry of the directory, which modifies the directory
+    fn insert(&self, path: &Path, node: Node) {
+        let modified = self.clock.time();
+        let mut nodes = self.nodes.borrow_mut();
+
+        nodes.insert(path.to_path_buf(), Entry { node, modified });
+        self.touch_parent(&mut nodes, path);
+    }
+
+    fn remove(&self, path: &Path) {
+        let mut nodes = self.nodes.borrow_mut();
+
+        nodes.remove(path);
+        self.touch_parent(&mut nodes, path);
+    }
+
+    fn touch_parent(&self, nodes: &mut BTreeMap<PathBuf, Entry>, path: &Path) {
+        if let Some(parent) = path.parent().and_then(|parent| nodes.get_mut(parent)) {
+            parent.modified = self.clock.time();
+        }
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let path = self.follow(path, true)?;
+
+        match self.node(&path) {
+            Some(Node::File(contents)) => Ok(contents),
+            Some(Node::Directory) => Err(io::ErrorKind::IsADirectory.into()),
+            // a link that is left after following them points to nothing
+            Some(Node::Symlink(_)) | None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let path = self.follow(path, true)?;
+        self.parent_is_directory(&path)?;
+
+        if let Some(Node::Directory) = self.node(&path) {
+            return Err(io::ErrorKind::IsADirectory.into());
+        }
+
+        self.insert(&path, Node::File(contents.to_vec()));
+        Ok(())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        let path = self.follow(path, true)?;
+        let path = path.as_path();
+
+        match self.node(path) {
+            Some(Node::Directory) => (),
+            Some(Node::File(_)) => return Err(io::ErrorKind::NotADirectory.into()),
+            Some(Node::Symlink(_)) | None => return Err(io::ErrorKind::NotFound.into()),
+        }
+
+        // descendants are stored right after their parent since the map is sorted by path
+        let names = self
+            .nodes
+            .borrow()
+            .range(path.to_path_buf()..)
+            .skip(1)
+            .take_while(|(child, _)| child.starts_with(path))
+            .filter(|(child, _)| child.parent() == Some(path))
+            .filter_map(|(child, _)| child.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect();
+
+        Ok(names)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.symlink_metadata(&self.follow(path, true)?)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let path = self.follow(path, false)?;
+        let nodes = self.nodes.borrow();
+        let entry = nodes.get(&path).ok_or(io::ErrorKind::NotFound)?;
+
+        Ok(Metadata {
+            file_type: match entry.node {
+                Node::File(_) => FileType::File,
+                Node::Directory => FileType::Directory,
+                Node::Symlink(_) => FileType::Symlink,
+            },
+            // the length of a link is the length of its target, like lstat(2)
+            len: match &entry.node {
+                Node::File(contents) => contents.len() as u64,
+                Node::Directory => 0,
+                Node::Symlink(target) => target.as_os_str().len() as u64,
+            },
+            modified: entry.modified,
+        })
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let path = self.follow(path, false)?;
+        if self.node(&path).is_some() {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+        self.parent_is_directory(&path)?;
+
+        self.insert(&path, Node::Directory);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let path = self.follow(path, false)?;
+
+        match self.node(&path) {
+            Some(Node::File(_)) | Some(Node::Symlink(_)) => {
+                self.remove(&path);
+                Ok(())
+            }
+            Some(Node::Directory) => Err(io::ErrorKind::IsADirectory.into()),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        let path = self.follow(path, false)?;
+        let path = path.as_path();
+
+        match self.node(path) {
+            Some(Node::Directory) if path.parent().is_none() => {
+                Err(io::ErrorKind::PermissionDenied.into())
+            }
+            Some(Node::Directory) if self.has_children(path) => {
+                Err(io::ErrorKind::DirectoryNotEmpty.into())
+            }
+            Some(Node::Directory) => {
+                self.remove(path);
+                Ok(())
+            }
+            Some(_) => Err(io::ErrorKind::NotADirectory.into()),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (self.follow(from, false)?, self.follow(to, false)?);
+        let (from, to) = (from.as_path(), to.as_path());
+        let node = self.node(from).ok_or(io::ErrorKind::NotFound)?;
+        if from == to {
+            return Ok(());
+        }
+        if to.starts_with(from) {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        self.parent_is_directory(to)?;
+
+        // a link is replaced like a file, it isn't followed
+        match (&node, self.node(to)) {
+            (Node::Directory, Some(Node::Directory)) => self.remove_dir(to)?,
+            (Node::Directory, Some(_)) => return Err(io::ErrorKind::NotADirectory.into()),
+            (_, Some(Node::Directory)) => return Err(io::ErrorKind::IsADirectory.into()),
+            _ => (),
+        }
+
+        // the directory moves with everything in it, they keep their modification time
+        let mut nodes = self.nodes.borrow_mut();
+        let moved: Vec<PathBuf> = nodes
+            .range(from.to_path_buf()..)
+            .take_while(|(path, _)| path.starts_with(from))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in moved {
+            let new_path = match path.strip_prefix(from) {
+                Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest),
+                _ => to.to_path_buf(),
+            };
+            if let Some(entry) = nodes.remove(&path) {
+                nodes.insert(new_path, entry);
+            }
+        }
+        self.touch_parent(&mut nodes, from);
+        self.touch_parent(&mut nodes, to);
+
+        Ok(())
+    }
+
+    fn set_modified(&self, path: &Path, time: SystemTime) -> io::Result<()> {
+        let path = self.follow(path, true)?;
+        let mut nodes = self.nodes.borrow_mut();
+        let entry = nodes.get_mut(&path).ok_or(io::ErrorKind::NotFound)?;
+
+        entry.modified = time;
+        Ok(())
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let link = self.follow(link, false)?;
+        if self.node(&link).is_some() {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+        self.parent_is_directory(&link)?;
+
+        self.insert(&link, Node::Symlink(target.to_path_buf()));
+        Ok(())
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        match self.node(&self.follow(path, false)?) {
+            Some(Node::Symlink(target)) => Ok(target),
+            Some(_) => Err(io::ErrorKind::InvalidInput.into()),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read_binary_file() {
+        let fs = MemoryFileSystem::default();
+        let contents = vec![0, 159, 146, 150, 255];
+
+        fs.write(Path::new("/file"), &contents).unwrap();
+
+        assert_eq!(fs.read(Path::new("/file")).unwrap(), contents);
+    }
+
+    #[test]
+    fn test_read_missing_file() {
+        let fs = MemoryFileSystem::default();
+        let error = fs.read(Path::new("/missing")).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_read_directory() {
+        let fs = MemoryFileSystem::default();
+        fs.create_dir_all(Path::new("/home/user"));
+
+        let error = fs.read(Path::new("/home")).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::IsADirectory);
+    }
+
+    #[test]
+    fn test_read_dir() {
+        let fs = MemoryFileSystem::default();
+        fs.create_dir_all(Path::new("/home/user/docs"));
+        fs.write(Path::new("/home/user/b.txt"), b"").unwrap();
+        fs.write(Path::new("/home/user/a.txt"), b"").unwrap();
+        fs.write(Path::new("/home/user/docs/c.txt"), b"").unwrap();
+        fs.create_dir_all(Path::new("/home/users"));
+
+        assert_eq!(
+            fs.read_dir(Path::new("/home/user")).unwrap(),
+            vec!["a.txt", "b.txt", "docs"]
+        );
+        assert_eq!(fs.read_dir(Path::new("/")).unwrap(), vec!["home"]);
+        assert!(fs.read_dir(Path::new("/home/user/a.txt")).is_err());
+    }
+
+    #[test]
+    fn test_metadata() {
+        let fs = MemoryFileSystem::default();
+        fs.write(Path::new("/file"), b"abc").unwrap();
+
+        assert_eq!(fs.metadata(Path::new("/file")).unwrap().len, 3);
+        assert!(fs.is_dir(Path::new("/")));
+        assert!(!fs.is_dir(Path::new("/file")));
+        assert!(!fs.exists(Path::new("/missing")));
+    }
+
+    #[test]
+    fn test_write_needs_an_existing_parent() {
+        let fs = MemoryFileSystem::default();
+        let error = fs.write(Path::new("/missing/file"), b"").unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_create_and_remove_dir() {
+        let fs = MemoryFileSystem::default();
+        fs.create_dir(Path::new("/a")).unwrap();
+        fs.write(Path::new("/a/file"), b"").unwrap();
+
+        let error = |result: io::Result<()>| result.unwrap_err().kind();
+        assert_eq!(error(fs.create_dir(Path::new("/a"))), io::ErrorKind::AlreadyExists);
+        assert_eq!(error(fs.create_dir(Path::new("/b/c"))), io::ErrorKind::NotFound);
+        assert_eq!(error(fs.remove_dir(Path::new("/a"))), io::ErrorKind::DirectoryNotEmpty);
+        assert_eq!(error(fs.remove_file(Path::new("/a"))), io::ErrorKind::IsADirectory);
+        assert_eq!(error(fs.remove_dir(Path::new("/a/file"))), io::ErrorKind::NotADirectory);
+
+        fs.remove_file(Path::new("/a/file")).unwrap();
+        fs.remove_dir(Path::new("/a")).unwrap();
+        assert!(!fs.exists(Path::new("/a")));
+    }
+
+    #[test]
+    fn test_rename() {
+        let fs = MemoryFileSystem::default();
+        fs.create_dir_all(Path::new("/a/b"));
+        fs.create_dir_all(Path::new("/empty"));
+        fs.write(Path::new("/a/b/file"), b"x").unwrap();
+        fs.write(Path::new("/file"), b"y").unwrap();
+
+        fs.rename(Path::new("/a"), Path::new("/empty")).unwrap();
+        assert_eq!(fs.read(Path::new("/empty/b/file")).unwrap(), b"x");
+        assert!(!fs.exists(Path::new("/a")));
+
+        let error = |result: io::Result<()>| result.unwrap_err().kind();
+        let (dir, file) = (Path::new("/empty"), Path::new("/file"));
+        assert_eq!(error(fs.rename(dir, Path::new("/empty/b/c"))), io::ErrorKind::InvalidInput);
+        assert_eq!(error(fs.rename(dir, file)), io::ErrorKind::NotADirectory);
+        assert_eq!(error(fs.rename(file, dir)), io::ErrorKind::IsADirectory);
+
+        fs.rename(file, Path::new("/empty/b/file")).unwrap();
+        assert_eq!(fs.read_dir(Path::new("/empty/b")).unwrap(), vec!["file"]);
+        assert_eq!(fs.read(Path::new("/empty/b/file")).unwrap(), b"y");
+    }
+
+    #[test]
+    fn test_modified() {
+        let clock = Rc::new(ManualClock::default());
+        let fs = MemoryFileSystem::new(clock.clone());
+        let modified = |path: &str| fs.metadata(Path::new(path)).unwrap().modified;
+
+        clock.advance(crate::clock::Times {
+            real: std::time::Duration::from_secs(10),
+            ..Default::default()
+        });
+        fs.write(Path::new("/file"), b"").unwrap();
+        assert_eq!(modified("/file"), clock.time());
+        assert_eq!(modified("/"), clock.time());
+
+        fs.set_modified(Path::new("/file"), SystemTime::UNIX_EPOCH).unwrap();
+        assert_eq!(modified("/file"), SystemTime::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn test_symlinks() {
+        let fs = MemoryFileSystem::default();
+        fs.create_dir_all(Path::new("/home/docs"));
+        fs.write(Path::new("/home/docs/cv.txt"), b"cv").unwrap();
+        fs.symlink(Path::new("home/docs"), Path::new("/docs")).unwrap();
+        fs.symlink(Path::new("cv.txt"), Path::new("/docs/latest")).unwrap();
+        fs.symlink(Path::new("/missing"), Path::new("/dangling")).unwrap();
+
+        assert_eq!(fs.read(Path::new("/docs/latest")).unwrap(), b"cv");
+        assert_eq!(fs.read_dir(Path::new("/docs")).unwrap(), vec!["cv.txt", "latest"]);
+        assert!(fs.is_dir(Path::new("/docs")));
+        assert_eq!(
+            fs.symlink_metadata(Path::new("/docs")).unwrap().file_type,
+            FileType::Symlink
+        );
+        assert_eq!(fs.read_link(Path::new("/docs/latest")).unwrap(), PathBuf::from("cv.txt"));
+
+        let error = |result: io::Result<Vec<u8>>| result.unwrap_err().kind();
+        assert_eq!(error(fs.read(Path::new("/dangling"))), io::ErrorKind::NotFound);
+        assert!(fs.symlink(Path::new("/"), Path::new("/docs")).is_err());
+
+        // writing through a link writes the file it points to
+        fs.write(Path::new("/dangling"), b"new").unwrap();
+        assert_eq!(fs.read(Path::new("/missing")).unwrap(), b"new");
+
+        // the link is removed or moved, not what it points to
+        fs.rename(Path::new("/docs"), Path::new("/papers")).unwrap();
+        assert_eq!(fs.read(Path::new("/papers/latest")).unwrap(), b"cv");
+        fs.remove_file(Path::new("/papers")).unwrap();
+        assert!(fs.is_dir(Path::new("/home/docs")));
+    }
+}
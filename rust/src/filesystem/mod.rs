@@ -0,0 +1,190 @@
+use std::fmt::Debug;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+mod memory;
+mod native;
+mod overlay;
+
+pub use memory::MemoryFileSystem;
+pub use native::NativeFileSystem;
+pub use overlay::OverlayFileSystem;
+
+// the most symbolic links a path can go through, like Linux
+const MAX_LINKS: usize = 40;
+
+// Storage used by the shell for everything that is a file. Natively this is the real
+// filesystem, in wasm it lives in memory. Contents are bytes, so binary files survive.
+// Paths passed to a filesystem are always absolute, see `resolve`. Symbolic links in a
+// path are followed, except for the last component of the path where a method works on
+// the link itself: `symlink_metadata`, `read_link`, `create_dir`, the removes and `rename`.
+pub trait FileSystem: Debug {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    // names of the entries in a directory, sorted and without "." and ".."
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>>;
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+    // like `metadata`, but a symbolic link is described itself instead of what it points to
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata>;
+    // fails when the directory exists or its parent doesn't
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    // only an empty directory can be removed
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+    // Moves a file or directory, like rename(2) it replaces a file or an empty directory
+    // at the new path. The new path can't be inside the directory that moves.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn set_modified(&self, path: &Path, time: SystemTime) -> io::Result<()>;
+    // Creates a symbolic link at `link` to `target`, which is kept as given. A relative
+    // target is relative to the directory of the link.
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()>;
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        matches!(self.metadata(path), Ok(metadata) if metadata.file_type == FileType::Directory)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FileType {
+    File,
+    Directory,
+    Symlink,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Metadata {
+    pub file_type: FileType,
+    pub len: u64,
+    pub modified: SystemTime,
+}
+
+// Turns a path given by the user in to an absolute path without `.` and `..`
+// >> resolve("/home/user", "../foo/./bar")
+// "/home/foo/bar"
+pub fn resolve(current_dir: &str, path: &str) -> PathBuf {
+    let mut result = PathBuf::from("/");
+
+    for component in Path::new(current_dir).join(path).components() {
+        match component {
+            Component::Normal(name) => result.push(name),
+            Component::ParentDir => {
+                result.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => (),
+        }
+    }
+
+    result
+}
+
+// The path with the symbolic links in it replaced by the paths they point to, the last
+// component only when `last` is true. `link` returns the target of the path when it is a
+// link, the directories in that path are already followed.
+// >> follow_links(Path::new("/docs/cv.pdf"), true, link) with "/docs" -> "home/docs"
+// "/home/docs/cv.pdf"
+fn follow_links(
+    path: &Path,
+    last: bool,
+    link: impl Fn(&Path) -> Option<PathBuf>,
+) -> io::Result<PathBuf> {
+    let mut followed = PathBuf::from("/");
+    let mut links = 0;
+    // the components that are left, the next one last
+    let names = |path: &Path| -> Vec<PathBuf> {
+        let names = path.components().rev().filter_map(|component| match component {
+            Component::Normal(name) => Some(PathBuf::from(name)),
+            _ => None,
+        });
+        names.collect()
+    };
+    let mut rest = names(&resolve("/", &path.to_string_lossy()));
+
+    while let Some(name) = rest.pop() {
+        let next = followed.join(&name);
+
+        match link(&next) {
+            Some(target) if last || !rest.is_empty() => {
+                links += 1;
+                if links > MAX_LINKS {
+                    return Err(io::Error::other("Too many levels of symbolic links"));
+                }
+
+                // the target takes the place of the link in the path
+                let target = resolve(&followed.to_string_lossy(), &target.to_string_lossy());
+                rest.extend(names(&target));
+                followed = PathBuf::from("/");
+            }
+            _ => followed = next,
+        }
+    }
+
+    Ok(followed)
+}
+
+// The message a shell prints for a failed file operation, eg: "No such file or directory"
+pub fn describe_error(error: &io::Error) -> String {
+    match error.kind() {
+        io::ErrorKind::NotFound => String::from("No such file or directory"),
+        io::ErrorKind::PermissionDenied => String::from("Permission denied"),
+        io::ErrorKind::IsADirectory => String::from("Is a directory"),
+        io::ErrorKind::NotADirectory => String::from("Not a directory"),
+        io::ErrorKind::AlreadyExists => String::from("File exists"),
+        io::ErrorKind::DirectoryNotEmpty => String::from("Directory not empty"),
+        io::ErrorKind::InvalidInput => String::from("Invalid argument"),
+        _ => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let expected = vec![
+            ("/", "foo", "/foo"),
+            ("/home/user", "foo/bar", "/home/user/foo/bar"),
+            ("/home/user", "/etc/hosts", "/etc/hosts"),
+            ("/home/user", "../foo/./bar", "/home/foo/bar"),
+            ("/", "../..", "/"),
+            ("", "foo", "/foo"),
+        ];
+
+        for (current_dir, path, result) in expected {
+            assert_eq!(resolve(current_dir, path), PathBuf::from(result));
+        }
+    }
+
+    #[test]
+    fn test_follow_links() {
+        let link = |path: &Path| match path.to_str() {
+            Some("/docs") => Some(PathBuf::from("home/docs")),
+            Some("/home/docs/latest") => Some(PathBuf::from("../cv.pdf")),
+            Some("/loop") => Some(PathBuf::from("/loop")),
+            _ => None,
+        };
+        let follow = |path: &str, last: bool| follow_links(Path::new(path), last, link);
+
+        assert_eq!(follow("/docs/cv.pdf", true).unwrap(), PathBuf::from("/home/docs/cv.pdf"));
+        assert_eq!(follow("/docs/latest", true).unwrap(), PathBuf::from("/home/cv.pdf"));
+        assert_eq!(follow("/docs/latest", false).unwrap(), PathBuf::from("/home/docs/latest"));
+        assert_eq!(follow("/docs", false).unwrap(), PathBuf::from("/docs"));
+        assert_eq!(follow("/", true).unwrap(), PathBuf::from("/"));
+        assert_eq!(
+            follow("/loop/file", true).unwrap_err().to_string(),
+            "Too many levels of symbolic links"
+        );
+    }
+
+    #[test]
+    fn test_describe_error() {
+        let error = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(describe_error(&error), "No such file or directory");
+    }
+}
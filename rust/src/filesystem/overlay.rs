@@ -0,0 +1,442 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crate::clock::Clock;
+use crate::filesystem::{self, FileSystem, FileType, MemoryFileSystem, Metadata};
+
+// Filesystem of a base that is only read with a layer in memory on top that takes every
+// change. The browser shell ships a file tree as the base, visitors can change anything
+// in it and the original stays the same.
+// A path in the layer hides the same path in the base, a file of the base is copied to
+// the layer before it changes. A path removed from the base is kept as a whiteout, which
+// hides everything in it: a removed directory that is created again starts empty.
+#[derive(Debug)]
+pub struct OverlayFileSystem {
+    base: Rc<dyn FileSystem>,
+    layer: MemoryFileSystem,
+    whiteouts: RefCell<BTreeSet<PathBuf>>,
+    clock: Rc<dyn Clock>,
+}
+
+impl OverlayFileSystem {
+    pub fn new(base: Rc<dyn FileSystem>, clock: Rc<dyn Clock>) -> Self {
+        let layer = MemoryFileSystem::new(clock.clone());
+        // the root is always in the layer, it starts out as the root of the base
+        if let Ok(metadata) = base.metadata(Path::new("/")) {
+            let _ = layer.set_modified(Path::new("/"), metadata.modified);
+        }
+
+        OverlayFileSystem {
+            base,
+            layer,
+            whiteouts: RefCell::new(BTreeSet::new()),
+            clock,
+        }
+    }
+
+    fn hidden(&self, path: &Path) -> bool {
+        let whiteouts = self.whiteouts.borrow();
+        path.ancestors()
+            .any(|ancestor| whiteouts.contains(ancestor))
+    }
+
+    fn in_base(&self, path: &Path) -> bool {
+        !self.hidden(path) && self.base.symlink_metadata(path).is_ok()
+    }
+
+    // The filesystem the path is in, the directories in the path are already followed
+    fn find(&self, path: &Path) -> io::Result<&dyn FileSystem> {
+        match self.layer.symlink_metadata(path) {
+            Ok(_) => Ok(&self.layer),
+            Err(_) if self.in_base(path) => Ok(self.base.as_ref()),
+            Err(error) => Err(error),
+        }
+    }
+
+    // links can point from one filesystem to the other, so they are followed here
+    fn follow(&self, path: &Path, last: bool) -> io::Result<PathBuf> {
+        filesystem::follow_links(path, last, |path| {
+            self.find(path).ok()?.read_link(path).ok()
+        })
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<Metadata> {
+        self.find(path)?.symlink_metadata(path)
+    }
+
+    fn parent_is_directory(&self, path: &Path) -> io::Result<()> {
+        let parent = path.parent().ok_or(io::ErrorKind::AlreadyExists)?;
+
+        match self.stat(parent)?.file_type {
+            FileType::Directory => Ok(()),
+            _ => Err(io::ErrorKind::NotADirectory.into()),
+        }
+    }
+
+    // Copies the path and the directories it is in from the base to the layer, so it can
+    // change. Copies keep the modification time of the base.
+    fn copy_up(&self, path: &Path) -> io::Result<()> {
+        if self.layer.symlink_metadata(path).is_ok() {
+            return Ok(());
+        }
+        if self.hidden(path) {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        let parent = path.parent().ok_or(io::ErrorKind::NotFound)?;
+        self.copy_up(parent)?;
+        // a copy isn't a change of the directory it is in
+        let parent_modified = self.layer.metadata(parent)?.modified;
+
+        let metadata = self.base.symlink_metadata(path)?;
+        match metadata.file_type {
+            FileType::Directory => self.layer.create_dir(path)?,
+            FileType::File => self.layer.write(path, &self.base.read(path)?)?,
+            FileType::Symlink => self.layer.symlink(&self.base.read_link(path)?, path)?,
+        }
+        if metadata.file_type != FileType::Symlink {
+            self.layer.set_modified(path, metadata.modified)?;
+        }
+
+        self.layer.set_modified(parent, parent_modified)
+    }
+
+    // Removes the path from the layer and hides it in the base
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        let parent = path.parent().ok_or(io::ErrorKind::PermissionDenied)?;
+        self.copy_up(parent)?;
+
+        match self.layer.symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type == FileType::Directory => {
+                self.layer.remove_dir(path)?
+            }
+            Ok(_) => self.layer.remove_file(path)?,
+            Err(_) => self.layer.set_modified(parent, self.clock.time())?,
+        }
+
+        if self.in_base(path) {
+            self.whiteouts.borrow_mut().insert(path.to_path_buf());
+        }
+        Ok(())
+    }
+
+    // a rename that involves the base is a copy to the layer and a remove
+    fn copy_tree(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let metadata = self.stat(from)?;
+
+        match metadata.file_type {
+            FileType::Directory => {
+                self.layer.create_dir(to)?;
+                for name in self.read_dir(from)? {
+                    self.copy_tree(&from.join(&name), &to.join(&name))?;
+                }
+            }
+            FileType::File => self.layer.write(to, &self.read(from)?)?,
+            FileType::Symlink => self.layer.symlink(&self.read_link(from)?, to)?,
+        }
+
+        match metadata.file_type {
+            FileType::Symlink => Ok(()),
+            _ => self.layer.set_modified(to, metadata.modified),
+        }
+    }
+
+    fn remove_tree(&self, path: &Path) -> io::Result<()> {
+        if self.stat(path)?.file_type == FileType::Directory {
+            for name in self.read_dir(path)? {
+                self.remove_tree(&path.join(name))?;
+            }
+        }
+
+        self.remove(path)
+    }
+}
+
+impl FileSystem for OverlayFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let path = self.follow(path, true)?;
+        self.find(&path)?.read(&path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let path = self.follow(path, true)?;
+        self.parent_is_directory(&path)?;
+
+        if let Ok(Metadata {
+            file_type: FileType::Directory,
+            ..
+        }) = self.stat(&path)
+        {
+            return Err(io::ErrorKind::IsADirectory.into());
+        }
+
+        self.copy_up(path.parent().unwrap_or(&path))?;
+        self.layer.write(&path, contents)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        let path = self.follow(path, true)?;
+        match self.stat(&path)?.file_type {
+            FileType::Directory => (),
+            FileType::File => return Err(io::ErrorKind::NotADirectory.into()),
+            FileType::Symlink => return Err(io::ErrorKind::NotFound.into()),
+        }
+
+        let mut names: BTreeSet<String> = match self.layer.is_dir(&path) {
+            true => self.layer.read_dir(&path)?.into_iter().collect(),
+            false => BTreeSet::new(),
+        };
+        if self.in_base(&path) && self.base.is_dir(&path) {
+            let base_names = self.base.read_dir(&path)?.into_iter();
+            names.extend(base_names.filter(|name| !self.hidden(&path.join(name))));
+        }
+
+        Ok(names.into_iter().collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.stat(&self.follow(path, true)?)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.stat(&self.follow(path, false)?)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let path = self.follow(path, false)?;
+        if self.find(&path).is_ok() {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+        self.parent_is_directory(&path)?;
+
+        self.copy_up(path.parent().unwrap_or(&path))?;
+        self.layer.create_dir(&path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let path = self.follow(path, false)?;
+
+        match self.stat(&path)?.file_type {
+            FileType::Directory => Err(io::ErrorKind::IsADirectory.into()),
+            _ => self.remove(&path),
+        }
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        let path = self.follow(path, false)?;
+
+        match self.stat(&path)?.file_type {
+            FileType::Directory if path.parent().is_none() => {
+                Err(io::ErrorKind::PermissionDenied.into())
+            }
+            FileType::Directory if !self.read_dir(&path)?.is_empty() => {
+                Err(io::ErrorKind::DirectoryNotEmpty.into())
+            }
+            FileType::Directory => self.remove(&path),
+            _ => Err(io::ErrorKind::NotADirectory.into()),
+        }
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (self.follow(from, false)?, self.follow(to, false)?);
+        let file_type = self.stat(&from)?.file_type;
+        if from == to {
+            return Ok(());
+        }
+        if to.starts_with(&from) {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        self.parent_is_directory(&to)?;
+
+        // a link is replaced like a file, it isn't followed
+        let replaced = self.stat(&to).ok().map(|metadata| metadata.file_type);
+        match (file_type, replaced) {
+            (FileType::Directory, Some(FileType::Directory)) => self.remove_dir(&to)?,
+            (FileType::Directory, Some(_)) => return Err(io::ErrorKind::NotADirectory.into()),
+            (_, Some(FileType::Directory)) => return Err(io::ErrorKind::IsADirectory.into()),
+            (_, Some(_)) => self.remove_file(&to)?,
+            (_, None) => (),
+        }
+
+        self.copy_up(to.parent().unwrap_or(&to))?;
+        self.copy_tree(&from, &to)?;
+        self.remove_tree(&from)
+    }
+
+    fn set_modified(&self, path: &Path, time: SystemTime) -> io::Result<()> {
+        let path = self.follow(path, true)?;
+
+        self.copy_up(&path)?;
+        self.layer.set_modified(&path, time)
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let link = self.follow(link, false)?;
+        if self.find(&link).is_ok() {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+        self.parent_is_directory(&link)?;
+
+        self.copy_up(link.parent().unwrap_or(&link))?;
+        self.layer.symlink(target, &link)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = self.follow(path, false)?;
+        self.find(&path)?.read_link(&path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{ManualClock, Times};
+    use std::time::Duration;
+
+    // the base is from the epoch, changes happen a minute later
+    fn overlay() -> (Rc<MemoryFileSystem>, OverlayFileSystem) {
+        let base = Rc::new(MemoryFileSystem::default());
+        base.create_dir_all(Path::new("/home/projects"));
+        base.write(Path::new("/home/about.txt"), b"about").unwrap();
+        base.write(Path::new("/home/projects/shell.txt"), b"shell")
+            .unwrap();
+        base.symlink(Path::new("home/about.txt"), Path::new("/about"))
+            .unwrap();
+
+        let clock = Rc::new(ManualClock::default());
+        clock.advance(Times {
+            real: Duration::from_secs(60),
+            ..Times::default()
+        });
+
+        (base.clone(), OverlayFileSystem::new(base, clock))
+    }
+
+    #[test]
+    fn test_reads_the_base() {
+        let (_, fs) = overlay();
+
+        assert_eq!(fs.read(Path::new("/about")).unwrap(), b"about");
+        assert_eq!(fs.read_dir(Path::new("/")).unwrap(), vec!["about", "home"]);
+        assert_eq!(
+            fs.symlink_metadata(Path::new("/about")).unwrap().file_type,
+            FileType::Symlink
+        );
+        assert!(fs.is_dir(Path::new("/home/projects")));
+        assert!(!fs.exists(Path::new("/missing")));
+    }
+
+    #[test]
+    fn test_changes_leave_the_base() {
+        let (base, fs) = overlay();
+
+        fs.write(Path::new("/about"), b"changed").unwrap();
+        fs.write(Path::new("/home/projects/new.txt"), b"new")
+            .unwrap();
+        fs.create_dir(Path::new("/tmp")).unwrap();
+
+        assert_eq!(fs.read(Path::new("/home/about.txt")).unwrap(), b"changed");
+        assert_eq!(
+            fs.read_dir(Path::new("/home/projects")).unwrap(),
+            vec!["new.txt", "shell.txt"]
+        );
+        assert_eq!(
+            fs.read_dir(Path::new("/")).unwrap(),
+            vec!["about", "home", "tmp"]
+        );
+
+        assert_eq!(base.read(Path::new("/home/about.txt")).unwrap(), b"about");
+        assert_eq!(
+            base.read_dir(Path::new("/")).unwrap(),
+            vec!["about", "home"]
+        );
+    }
+
+    #[test]
+    fn test_removed_paths_stay_hidden() {
+        let (base, fs) = overlay();
+
+        fs.remove_file(Path::new("/home/projects/shell.txt"))
+            .unwrap();
+        fs.remove_dir(Path::new("/home/projects")).unwrap();
+        assert!(!fs.exists(Path::new("/home/projects")));
+
+        fs.create_dir(Path::new("/home/projects")).unwrap();
+        assert_eq!(
+            fs.read_dir(Path::new("/home/projects")).unwrap(),
+            Vec::<String>::new()
+        );
+
+        let error = |result: io::Result<()>| result.unwrap_err().kind();
+        assert_eq!(
+            error(fs.remove_dir(Path::new("/home"))),
+            io::ErrorKind::DirectoryNotEmpty
+        );
+        assert_eq!(
+            error(fs.remove_file(Path::new("/home"))),
+            io::ErrorKind::IsADirectory
+        );
+        assert_eq!(
+            error(fs.remove_dir(Path::new("/"))),
+            io::ErrorKind::PermissionDenied
+        );
+
+        assert!(base.exists(Path::new("/home/projects/shell.txt")));
+    }
+
+    #[test]
+    fn test_rename() {
+        let (base, fs) = overlay();
+
+        fs.rename(Path::new("/home"), Path::new("/house")).unwrap();
+        fs.rename(Path::new("/about"), Path::new("/house/link"))
+            .unwrap();
+
+        assert_eq!(fs.read_dir(Path::new("/")).unwrap(), vec!["house"]);
+        assert_eq!(
+            fs.read_dir(Path::new("/house")).unwrap(),
+            vec!["about.txt", "link", "projects"]
+        );
+        assert_eq!(
+            fs.read(Path::new("/house/projects/shell.txt")).unwrap(),
+            b"shell"
+        );
+        // the link keeps its relative target, which is gone now
+        assert_eq!(
+            fs.read(Path::new("/house/link")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        fs.create_dir(Path::new("/home")).unwrap();
+        assert_eq!(
+            fs.read_dir(Path::new("/home")).unwrap(),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            base.read_dir(Path::new("/home")).unwrap(),
+            vec!["about.txt", "projects"]
+        );
+    }
+
+    #[test]
+    fn test_modified() {
+        let (_, fs) = overlay();
+        let modified = |path: &str| fs.metadata(Path::new(path)).unwrap().modified;
+        let minute = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+
+        fs.set_modified(Path::new("/home/projects/shell.txt"), minute)
+            .unwrap();
+        assert_eq!(modified("/home/projects/shell.txt"), minute);
+        // copying the file to the layer doesn't modify the directories it is in
+        assert_eq!(modified("/home/projects"), SystemTime::UNIX_EPOCH);
+
+        fs.rename(Path::new("/home/projects"), Path::new("/projects"))
+            .unwrap();
+        assert_eq!(modified("/projects"), SystemTime::UNIX_EPOCH);
+        assert_eq!(modified("/home"), minute);
+    }
+}
@@ -1,14 +1,26 @@
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
+use crate::clock::{Clock, Times};
+use crate::executor::resume::{self, Input};
+use crate::filesystem::{FileSystem, MemoryFileSystem, OverlayFileSystem};
 use crate::shell_state::ShellState;
+use crate::sink::{OutputSink, Stream};
 
-mod executor;
-mod shell_state;
-mod terminal;
+pub mod executor;
+pub mod shell_state;
+pub mod terminal;
 mod command;
 mod builtin;
 mod parser;
 mod utils;
-mod exception;
+pub mod exception;
+pub mod sink;
+pub mod filesystem;
+pub mod exit_status;
+mod pattern;
+pub mod options;
+pub mod clock;
+pub mod variable;
 
 #[wasm_bindgen]
 extern {
@@ -16,11 +28,204 @@ extern {
 
     #[wasm_bindgen(js_namespace = console)]
     pub fn log(s: String);
+
+    // milliseconds since the epoch
+    #[wasm_bindgen(js_namespace = Date)]
+    fn now() -> f64;
+
+    // any js function of the shape `(stream: "stdout" | "stderr", text: string) => void`
+    pub type OutputCallback;
+
+    #[wasm_bindgen(method, js_name = call)]
+    fn call(this: &OutputCallback, this_arg: &JsValue, stream: &str, text: &str);
+}
+
+struct JsSink {
+    callback: OutputCallback,
+}
+
+impl OutputSink for JsSink {
+    fn write(&mut self, stream: Stream, bytes: &[u8]) {
+        let stream = match stream {
+            Stream::Stdout => "stdout",
+            Stream::Stderr => "stderr",
+        };
+
+        // js strings are text, this is the only place where output is converted
+        self.callback
+            .call(&JsValue::NULL, stream, &String::from_utf8_lossy(bytes));
+    }
+}
+
+// The browser only tells the real time, user and sys times stay zero
+#[derive(Debug)]
+struct JsClock;
+
+impl Clock for JsClock {
+    fn now(&self) -> Times {
+        Times {
+            real: std::time::Duration::from_secs_f64(now().max(0.0) / 1000.0),
+            ..Times::default()
+        }
+    }
+
+    fn time(&self) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs_f64(now().max(0.0) / 1000.0)
+    }
 }
 
 #[wasm_bindgen]
-pub fn run_command(name: &str) {
-    let mut shell_state = ShellState::default();
+pub struct Shell {
+    state: ShellState,
+    sink: JsSink,
+    // the files the site ships, the shell only changes the overlay on top of them
+    image: Rc<MemoryFileSystem>,
+}
+
+#[wasm_bindgen]
+impl Shell {
+    // `rc` becomes ~/.shellrc of the in memory filesystem, it runs before the first line
+    #[wasm_bindgen(constructor)]
+    pub fn new(on_output: OutputCallback, rc: Option<String>) -> Shell {
+        // files in memory get their modification time from the same clock
+        let clock = Rc::new(JsClock);
+        let image = Rc::new(MemoryFileSystem::new(clock.clone()));
+        let fs = OverlayFileSystem::new(image.clone(), clock.clone());
+        let mut state = ShellState::new(Rc::new(fs), String::from("/"));
+        state.set_variable("HOME", String::from("/"));
+        state.clock = clock;
+        state.interactive = true;
+        state.define_default_aliases();
+
+        let mut shell = Shell {
+            state,
+            sink: JsSink { callback: on_output },
+            image,
+        };
+
+        if let Some(rc) = rc {
+            let path = std::path::Path::new("/.shellrc");
+            if let Err(error) = shell.state.fs.write(path, rc.as_bytes()) {
+                log(format!("the rc file can't be written: {}", error));
+            }
+        }
+
+        if let Err(exception) = executor::run_rc_file(&mut shell.state) {
+            shell
+                .sink
+                .write(Stream::Stderr, format!("shell: {}\n", exception).as_bytes());
+        }
+        sink::flush(&mut shell.state.output, &mut shell.sink);
+
+        shell
+    }
+
+    // Adds a file to the tree the site ships, the directories it is in are created. The
+    // visitor sees it unless it was changed or removed in the shell already.
+    #[wasm_bindgen(js_name = addFile)]
+    pub fn add_file(&mut self, path: &str, contents: &[u8]) {
+        let path = filesystem::resolve("/", path);
+        if let Some(parent) = path.parent() {
+            self.image.create_dir_all(parent);
+        }
+
+        if let Err(error) = self.image.write(&path, contents) {
+            log(format!("{} can't be added: {}", path.display(), error));
+        }
+    }
+
+    // Returns whether the line waits for input of `read`, the host passes it to `provideInput`
+    #[wasm_bindgen(js_name = run)]
+    pub fn run(&mut self, line: &str) -> bool {
+        executor::history::record(line, &mut self.state);
+        let result = executor::run(line, &mut self.state);
+        self.finish(result)
+    }
+
+    // The input the line waits for, without its line break. No line ends the input, eg: Ctrl-D.
+    // Returns whether the line waits for input again.
+    #[wasm_bindgen(js_name = provideInput)]
+    pub fn provide_input(&mut self, line: Option<String>) -> bool {
+        let input = line.map_or(Input::End, Input::Line);
+        let result = resume::provide_input(input, &mut self.state);
+        self.finish(result)
+    }
+
+    // no input came within the timeout of `read -t`
+    #[wasm_bindgen(js_name = inputTimedOut)]
+    pub fn input_timed_out(&mut self) -> bool {
+        let result = resume::provide_input(Input::TimedOut, &mut self.state);
+        self.finish(result)
+    }
+
+    // the input of `read -s` isn't shown while it's typed
+    #[wasm_bindgen(js_name = isInputSilent)]
+    pub fn is_input_silent(&self) -> bool {
+        self.state
+            .input_request()
+            .is_some_and(|request| request.silent)
+    }
+
+    // milliseconds the host waits for input of `read -t` before it times out
+    #[wasm_bindgen(js_name = inputTimeout)]
+    pub fn input_timeout(&self) -> Option<f64> {
+        let timeout = self.state.input_request()?.timeout?;
+        Some(timeout.as_secs_f64() * 1000.0)
+    }
+
+    // the host cancels the line being run or read, eg: Ctrl-C, the INT trap runs
+    #[wasm_bindgen(js_name = interrupt)]
+    pub fn interrupt(&mut self) {
+        executor::trap::interrupt(&mut self.state);
+        sink::flush(&mut self.state.output, &mut self.sink);
+    }
+
+    // the session ends, eg: the page is closed, the EXIT trap runs
+    #[wasm_bindgen(js_name = exit)]
+    pub fn exit(&mut self) {
+        if self.state.exit.is_some() {
+            return;
+        }
+
+        executor::end_of_input(&mut self.state);
+        executor::trap::run_exit_trap(&mut self.state);
+        self.state.exit = Some(self.state.output.status);
+        sink::flush(&mut self.state.output, &mut self.sink);
+    }
+
+    // the lines passed to `run` are the body of a here-document, a host can show another prompt
+    #[wasm_bindgen(js_name = isReadingHereDoc)]
+    pub fn is_reading_here_doc(&self) -> bool {
+        self.state.is_reading_here_doc()
+    }
+
+    // the lines passed to `run` go on with a command that isn't closed yet, eg: `case $1 in`
+    #[wasm_bindgen(js_name = isContinuingCommand)]
+    pub fn is_continuing_command(&self) -> bool {
+        self.state.is_continuing_command()
+    }
+
+    // the shell doesn't run lines anymore, eg: a command failed with errexit
+    #[wasm_bindgen(js_name = hasExited)]
+    pub fn has_exited(&self) -> bool {
+        self.state.exit.is_some()
+    }
+
+    // exit status of the last command, `$?` in the shell
+    #[wasm_bindgen(js_name = status)]
+    pub fn status(&self) -> u8 {
+        self.state.output.status.code()
+    }
+}
+
+impl Shell {
+    fn finish(&mut self, result: Result<(), exception::Exception>) -> bool {
+        if let Err(exception) = result {
+            self.sink
+                .write(Stream::Stderr, format!("shell: {}\n", exception).as_bytes());
+        }
 
-    executor::run(&String::from(name), &mut shell_state);
+        sink::flush(&mut self.state.output, &mut self.sink);
+        self.state.input_request().is_some()
+    }
 }
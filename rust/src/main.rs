@@ -1,25 +1,131 @@
-use terminal::ReadResult;
-use crate::exception::Exception;
-use crate::shell_state::ShellState;
-
-mod executor;
-mod shell_state;
-mod terminal;
-mod command;
-mod builtin;
-mod parser;
-mod utils;
-mod exception;
+use std::env;
+use std::process;
+use std::rc::Rc;
+
+use learning_rust::clock::SystemClock;
+use learning_rust::exception::Exception;
+use learning_rust::executor::{self, resume};
+use learning_rust::exit_status::ExitStatus;
+use learning_rust::filesystem::{self, NativeFileSystem};
+use learning_rust::shell_state::ShellState;
+use learning_rust::sink::{self, OutputSink, Stream};
+use learning_rust::terminal::{self, ReadResult, TerminalSink};
 
 fn main() -> Result<(), Exception> {
-    let mut shell_state = ShellState::default();
+    let current_dir = env::current_dir().expect("Current directory should be readable");
+    let mut shell_state = ShellState::new(
+        Rc::new(NativeFileSystem),
+        current_dir.to_string_lossy().to_string(),
+    );
+    // the environment of the process becomes the variables of the shell, eg: HOME for `cd ~`
+    for (name, value) in env::vars() {
+        shell_state.set_variable(&name, value);
+    }
+    shell_state.clock = Rc::new(SystemClock::default());
+    shell_state.define_default_aliases();
+    let mut sink = TerminalSink::default();
+    terminal::catch_interrupts();
+
+    // `shell script.sh` runs the script instead of reading commands from the terminal
+    if let Some(path) = env::args().nth(1) {
+        let status = run_script_file(&path, &mut shell_state, &mut sink);
+        process::exit(i32::from(status));
+    }
+
+    // like bash the rc file is only read by interactive shells
+    shell_state.interactive = true;
+    if let Err(exception) = executor::run_rc_file(&mut shell_state) {
+        sink.write(Stream::Stderr, format!("shell: {}\n", exception).as_bytes());
+    }
+    sink::flush(&mut shell_state.output, &mut sink);
+    provide_input(&mut shell_state, &mut sink);
 
     loop {
-        match terminal::read_line() {
-            ReadResult::Empty => continue,
-            ReadResult::Ok(line) => {
-                executor::run(&line, &mut shell_state)?
+        let prompt = match shell_state.is_reading_here_doc() {
+            true => "heredoc>",
+            false if shell_state.is_continuing_command() => ">",
+            false => "%",
+        };
+        // like zsh, output that didn't end its line doesn't share it with the prompt
+        if !sink.at_line_start {
+            sink.write(Stream::Stdout, b"\n");
+        }
+
+        let line = match terminal::read_line(prompt) {
+            ReadResult::Eof => {
+                executor::end_of_input(&mut shell_state);
+                executor::trap::run_exit_trap(&mut shell_state);
+                sink::flush(&mut shell_state.output, &mut sink);
+                return Ok(());
+            }
+            ReadResult::Interrupted => {
+                sink.at_line_start = true;
+                executor::trap::interrupt(&mut shell_state);
+                sink::flush(&mut shell_state.output, &mut sink);
+                continue;
+            }
+            ReadResult::Ok(line) if line.trim().is_empty() && !shell_state.is_reading_here_doc() => {
+                continue
+            }
+            ReadResult::Ok(line) => line,
+        };
+        sink.at_line_start = true;
+
+        executor::history::record(&line, &mut shell_state);
+        if let Err(exception) = executor::run(&line, &mut shell_state) {
+            sink.write(Stream::Stderr, format!("shell: {}\n", exception).as_bytes());
+        }
+
+        sink::flush(&mut shell_state.output, &mut sink);
+        provide_input(&mut shell_state, &mut sink);
+
+        // eg: a command failed with errexit
+        if let Some(status) = shell_state.exit {
+            process::exit(i32::from(status.code()));
+        }
+    }
+}
+
+// exits with the status of the last command of the script, or 2 for a syntax error
+fn run_script_file(path: &str, shell_state: &mut ShellState, sink: &mut TerminalSink) -> u8 {
+    let script = match shell_state.fs.read(&shell_state.resolve_path(path)) {
+        Ok(script) => script,
+        Err(error) => {
+            let message = format!("shell: {}: {}\n", filesystem::describe_error(&error), path);
+            sink.write(Stream::Stderr, message.as_bytes());
+            return 127;
+        }
+    };
+
+    if let Err(exception) = executor::run_script(&String::from_utf8_lossy(&script), shell_state) {
+        shell_state.output.write_stderr(format!("shell: {}\n", exception));
+        // like bash, a line that can't be parsed ends the script with 2
+        shell_state.output.status = ExitStatus::USAGE;
+    }
+    sink::flush(&mut shell_state.output, sink);
+    provide_input(shell_state, sink);
+    executor::trap::run_exit_trap(shell_state);
+
+    sink::flush(&mut shell_state.output, sink);
+    shell_state.output.status.code()
+}
+
+// A line that waits for input of `read` gets it from the terminal, the line can wait again.
+// Ctrl-C while it waits interrupts it like it does at the prompt.
+fn provide_input(shell_state: &mut ShellState, sink: &mut TerminalSink) {
+    while let Some(request) = shell_state.input_request().cloned() {
+        let input = terminal::read_input(&request);
+        // the typed line ended with Enter
+        sink.at_line_start = true;
+        match input {
+            Some(input) => {
+                if let Err(exception) = resume::provide_input(input, shell_state) {
+                    sink.write(Stream::Stderr, format!("shell: {}\n", exception).as_bytes());
+                }
             }
+            // Ctrl-C drops the line that waits
+            None => executor::trap::interrupt(shell_state),
         }
+        sink::flush(&mut shell_state.output, sink);
     }
 }
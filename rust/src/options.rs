@@ -0,0 +1,174 @@
+// Options of the shell that are toggled with `shopt -s NAME` and `shopt -u NAME`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ShoptOptions {
+    // globs match files starting with a "."
+    pub dotglob: bool,
+    // a glob without matches is an error
+    pub failglob: bool,
+    // "**" matches any amount of directories
+    pub globstar: bool,
+    // a glob without matches expands to nothing instead of itself
+    pub nullglob: bool,
+    // `echo` expands backslash escapes without -e
+    pub xpg_echo: bool,
+}
+
+impl ShoptOptions {
+    pub const NAMES: [&'static str; 5] =
+        ["dotglob", "failglob", "globstar", "nullglob", "xpg_echo"];
+
+    pub fn get(&self, name: &str) -> Option<bool> {
+        match name {
+            "dotglob" => Some(self.dotglob),
+            "failglob" => Some(self.failglob),
+            "globstar" => Some(self.globstar),
+            "nullglob" => Some(self.nullglob),
+            "xpg_echo" => Some(self.xpg_echo),
+            _ => None,
+        }
+    }
+
+    // returns None when there is no option with that name
+    pub fn set(&mut self, name: &str, value: bool) -> Option<()> {
+        let option = match name {
+            "dotglob" => &mut self.dotglob,
+            "failglob" => &mut self.failglob,
+            "globstar" => &mut self.globstar,
+            "nullglob" => &mut self.nullglob,
+            "xpg_echo" => &mut self.xpg_echo,
+            _ => return None,
+        };
+
+        *option = value;
+        Some(())
+    }
+}
+
+// Options of the shell that are toggled with `set -o NAME` and `set +o NAME`,
+// or with their letter, eg: `set -e`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SetOptions {
+    // the shell exits when a command fails and its status isn't tested
+    pub errexit: bool,
+    // `>` doesn't overwrite files, `>|` still does
+    pub noclobber: bool,
+    // words are not expanded to pathnames
+    pub noglob: bool,
+    // expanding a parameter that is not set is an error
+    pub nounset: bool,
+    // the status of a pipeline is the one of its last failing command
+    pub pipefail: bool,
+    // commands are written to stderr after PS4 before they run
+    pub xtrace: bool,
+}
+
+impl SetOptions {
+    pub const NAMES: [&'static str; 6] = [
+        "errexit",
+        "noclobber",
+        "noglob",
+        "nounset",
+        "pipefail",
+        "xtrace",
+    ];
+
+    // letters of the options that have one, in the order `$-` lists them
+    const FLAGS: [(char, &'static str); 5] = [
+        ('e', "errexit"),
+        ('f', "noglob"),
+        ('u', "nounset"),
+        ('x', "xtrace"),
+        ('C', "noclobber"),
+    ];
+
+    pub fn get(&self, name: &str) -> Option<bool> {
+        match name {
+            "errexit" => Some(self.errexit),
+            "noclobber" => Some(self.noclobber),
+            "noglob" => Some(self.noglob),
+            "nounset" => Some(self.nounset),
+            "pipefail" => Some(self.pipefail),
+            "xtrace" => Some(self.xtrace),
+            _ => None,
+        }
+    }
+
+    // returns None when there is no option with that name
+    pub fn set(&mut self, name: &str, value: bool) -> Option<()> {
+        let option = match name {
+            "errexit" => &mut self.errexit,
+            "noclobber" => &mut self.noclobber,
+            "noglob" => &mut self.noglob,
+            "nounset" => &mut self.nounset,
+            "pipefail" => &mut self.pipefail,
+            "xtrace" => &mut self.xtrace,
+            _ => return None,
+        };
+
+        *option = value;
+        Some(())
+    }
+
+    // >> SetOptions::name_of_flag('e')
+    // Some("errexit")
+    pub fn name_of_flag(flag: char) -> Option<&'static str> {
+        Self::FLAGS
+            .iter()
+            .find(|(letter, _)| *letter == flag)
+            .map(|(_, name)| *name)
+    }
+
+    // `$-`, the letters of the options that are on
+    pub fn flags(&self) -> String {
+        Self::FLAGS
+            .iter()
+            .filter(|(_, name)| self.get(name).unwrap_or_default())
+            .map(|(letter, _)| letter)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_and_set() {
+        let mut options = ShoptOptions::default();
+
+        for name in ShoptOptions::NAMES {
+            assert_eq!(options.get(name), Some(false));
+            assert_eq!(options.set(name, true), Some(()));
+            assert_eq!(options.get(name), Some(true));
+        }
+
+        assert_eq!(options.get("unknown"), None);
+        assert_eq!(options.set("unknown", true), None);
+    }
+
+    #[test]
+    fn test_set_options() {
+        let mut options = SetOptions::default();
+
+        for name in SetOptions::NAMES {
+            assert_eq!(options.get(name), Some(false));
+            assert_eq!(options.set(name, true), Some(()));
+            assert_eq!(options.get(name), Some(true));
+        }
+
+        assert_eq!(options.set("unknown", true), None);
+    }
+
+    #[test]
+    fn test_flags() {
+        let mut options = SetOptions::default();
+        assert_eq!(options.flags(), "");
+
+        options.set(SetOptions::name_of_flag('x').unwrap(), true);
+        options.set(SetOptions::name_of_flag('C').unwrap(), true);
+        options.pipefail = true;
+
+        assert_eq!(options.flags(), "xC");
+        assert_eq!(SetOptions::name_of_flag('z'), None);
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+
+use crate::parser::token::{tokenize_raw_line, Token, PIPELINE_PREFIXES};
+use crate::parser::word;
+
+// Replaces the first word of every simple command that is an alias with the value of the
+// alias, before the tokens are parsed. The value is tokenized again, so it can hold
+// operators like `;` or `|`. An alias is not expanded again inside its own value, and
+// a value that ends with a blank makes the word after it an alias too.
+// >> alias ll='ls -la'; ll /
+// ls -la /
+pub fn expand_aliases(tokens: Vec<Token>, aliases: &BTreeMap<String, String>) -> Vec<Token> {
+    match aliases.is_empty() {
+        true => tokens,
+        false => expand_tokens(tokens, aliases, &[]),
+    }
+}
+
+fn expand_tokens(
+    tokens: Vec<Token>,
+    aliases: &BTreeMap<String, String>,
+    active: &[&str],
+) -> Vec<Token> {
+    let mut result = Vec::new();
+    // the words after a group are its redirections, not a command
+    let mut is_command_start = true;
+
+    for token in tokens {
+        let is_operator = matches!(
+            token,
+            Token::Semicolon | Token::And | Token::Or | Token::Pipeline | Token::Async
+        );
+
+        match token {
+            Token::Raw(text) if is_command_start => {
+                result.extend(expand_command(&text, aliases, active))
+            }
+            Token::Subshell(list) => result.push(Token::Subshell(expand_list(&list, aliases, active))),
+            Token::BraceGroup(list) => {
+                result.push(Token::BraceGroup(expand_list(&list, aliases, active)))
+            }
+            token => result.push(token),
+        }
+
+        is_command_start = is_operator;
+    }
+
+    result
+}
+
+fn expand_list(list: &str, aliases: &BTreeMap<String, String>, active: &[&str]) -> String {
+    let tokens = expand_tokens(tokenize_raw_line(list), aliases, active);
+    let words: Vec<String> = tokens.iter().map(|token| token.to_string()).collect();
+
+    format!(" {} ", words.join(" "))
+}
+
+// The alias can follow a pipeline prefix and assignments, eg: `! A=1 ll`
+fn expand_command(text: &str, aliases: &BTreeMap<String, String>, active: &[&str]) -> Vec<Token> {
+    let words = word::split_words(text);
+    let command_start = words
+        .iter()
+        .take_while(|word| {
+            PIPELINE_PREFIXES.contains(&word.as_str()) || word::split_assignment(word).is_some()
+        })
+        .count();
+
+    let (name, value) = match alias(words.get(command_start), aliases, active) {
+        Some(alias) => alias,
+        None => return vec![Token::Raw(String::from(text))],
+    };
+
+    let rest = &words[command_start + 1..];
+    let rest = match value.ends_with(is_blank) {
+        true => expand_next_word(rest, aliases, active),
+        false => rest.join(" "),
+    };
+    let text = format!("{} {} {}", words[..command_start].join(" "), value, rest);
+
+    let mut active = active.to_vec();
+    active.push(name);
+    expand_tokens(tokenize_raw_line(&text), aliases, &active)
+}
+
+// the words after an alias that ends with a blank, the first of them can be an alias too
+fn expand_next_word(words: &[String], aliases: &BTreeMap<String, String>, active: &[&str]) -> String {
+    match alias(words.first(), aliases, active) {
+        Some((_, value)) if value.ends_with(is_blank) => {
+            format!("{} {}", value, expand_next_word(&words[1..], aliases, active))
+        }
+        Some((_, value)) => format!("{} {}", value, words[1..].join(" ")),
+        None => words.join(" "),
+    }
+}
+
+fn alias<'a>(
+    word: Option<&String>,
+    aliases: &'a BTreeMap<String, String>,
+    active: &[&str],
+) -> Option<(&'a str, &'a str)> {
+    let (name, value) = aliases.get_key_value(word?)?;
+
+    match active.contains(&name.as_str()) {
+        true => None,
+        false => Some((name, value)),
+    }
+}
+
+fn is_blank(char: char) -> bool {
+    char == ' ' || char == '\t'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::{parse_to_ast, AstItem};
+
+    fn aliases(definitions: &[(&str, &str)]) -> BTreeMap<String, String> {
+        definitions
+            .iter()
+            .map(|(name, value)| (String::from(*name), String::from(*value)))
+            .collect()
+    }
+
+    fn parse(line: &str) -> AstItem {
+        parse_to_ast(&tokenize_raw_line(line)).unwrap()
+    }
+
+    fn expand_line(line: &str, aliases: &BTreeMap<String, String>) -> AstItem {
+        parse_to_ast(&expand_aliases(tokenize_raw_line(line), aliases)).unwrap()
+    }
+
+    #[test]
+    fn test_first_words_are_expanded() {
+        let aliases = aliases(&[("ll", "ls -la"), ("g", "grep -i")]);
+
+        let expected = vec![
+            ("ll /", "ls -la /"),
+            (
+                "ll | g a && (ll; { ll; }) || A=1 ll",
+                "ls -la | grep -i a && (ls -la; { ls -la; }) || A=1 ls -la",
+            ),
+            ("echo ll; 'll'", "echo ll; 'll'"),
+            ("! ll > out; time -p ll", "! ls -la > out; time -p ls -la"),
+        ];
+
+        for (line, result) in expected {
+            assert_eq!(expand_line(line, &aliases), parse(result), "expanding {}", line);
+        }
+    }
+
+    #[test]
+    fn test_values_with_operators() {
+        let aliases = aliases(&[("both", "echo a; echo b |"), ("up", "cd ..")]);
+
+        assert_eq!(
+            expand_line("both cat && up", &aliases),
+            parse("echo a; echo b | cat && cd ..")
+        );
+    }
+
+    #[test]
+    fn test_recursion() {
+        let aliases = aliases(&[("ls", "ls -F"), ("l", "ls -a"), ("a", "b"), ("b", "a")]);
+
+        assert_eq!(expand_line("l", &aliases), parse("ls -F -a"));
+        assert_eq!(expand_line("a", &aliases), parse("a"));
+        assert_eq!(expand_line("b 1", &aliases), parse("b 1"));
+    }
+
+    #[test]
+    fn test_trailing_blank() {
+        let aliases = aliases(&[("e", "echo "), ("s", "sudo\t"), ("ll", "ls -la")]);
+
+        assert_eq!(expand_line("e ll", &aliases), parse("echo ls -la"));
+        assert_eq!(expand_line("s e ll x", &aliases), parse("sudo echo ls -la x"));
+        assert_eq!(expand_line("e e hi", &aliases), parse("echo echo hi"));
+        assert_eq!(expand_line("ll ll", &aliases), parse("ls -la ll"));
+    }
+}
@@ -1,9 +1,12 @@
+mod case;
 mod conversions;
 
+use crate::parser::condition::Condition;
+use crate::parser::redirection::Redirection;
 use crate::parser::token::Token;
 use std::convert::TryFrom;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum LogicalExpressionOp {
     Or,
     And,
@@ -20,7 +23,7 @@ impl TryFrom<&Token> for LogicalExpressionOp {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum AstItem {
     Command {
         keyword: String,
@@ -33,6 +36,91 @@ pub enum AstItem {
         right: Box<AstItem>,
     },
     Pipeline(Vec<AstItem>),
+    // `(( expression ))`, succeeds when the expression is not zero
+    Arithmetic(String),
+    // `[[ condition ]]`
+    Conditional(Condition),
+    // `case word in pattern) list;; ... esac`
+    Case {
+        word: String,
+        items: Vec<CaseItem>,
+    },
+    // `( list )`, runs in a copy of the shell state
+    Subshell(Box<AstItem>),
+    // `{ list; }`
+    Group(Box<AstItem>),
+    // `! pipeline`, succeeds when the pipeline fails and the other way around
+    Negated(Box<AstItem>),
+    // `time pipeline`, writes how long the pipeline took to stderr.
+    // `time -p` uses the posix format instead of TIMEFORMAT.
+    Timed {
+        item: Box<AstItem>,
+        posix: bool,
+    },
+    // an item with its redirections, eg: `cat <<EOF`
+    Redirected {
+        item: Box<AstItem>,
+        redirections: Vec<Redirection>,
+    },
+}
+
+impl AstItem {
+    // the here-documents in the order they are written, their bodies follow in this order
+    pub fn here_docs(&mut self) -> Vec<&mut Redirection> {
+        let mut here_docs = Vec::new();
+        self.collect_here_docs(&mut here_docs);
+        here_docs
+    }
+
+    fn collect_here_docs<'a>(&'a mut self, here_docs: &mut Vec<&'a mut Redirection>) {
+        match self {
+            AstItem::Script(items) | AstItem::Pipeline(items) => {
+                for item in items {
+                    item.collect_here_docs(here_docs);
+                }
+            }
+            AstItem::LogicalExpression { left, right, .. } => {
+                left.collect_here_docs(here_docs);
+                right.collect_here_docs(here_docs);
+            }
+            AstItem::Subshell(item)
+            | AstItem::Group(item)
+            | AstItem::Negated(item)
+            | AstItem::Timed { item, .. } => item.collect_here_docs(here_docs),
+            AstItem::Case { items, .. } => {
+                for body in items.iter_mut().filter_map(|item| item.body.as_mut()) {
+                    body.collect_here_docs(here_docs);
+                }
+            }
+            AstItem::Redirected { item, redirections } => {
+                item.collect_here_docs(here_docs);
+                here_docs.extend(
+                    redirections
+                        .iter_mut()
+                        .filter(|redirection| matches!(redirection, Redirection::HereDoc { .. })),
+                );
+            }
+            AstItem::Command { .. } | AstItem::Arithmetic(_) | AstItem::Conditional(_) => (),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CaseItem {
+    pub patterns: Vec<String>,
+    pub body: Option<AstItem>,
+    pub terminator: CaseTerminator,
+}
+
+// What happens after the body of a matching item ran
+#[derive(Debug, PartialEq, Clone)]
+pub enum CaseTerminator {
+    // `;;` ends the case
+    Break,
+    // `;&` runs the next body without testing its patterns
+    FallThrough,
+    // `;;&` goes on testing the next patterns
+    Continue,
 }
 
 impl TryFrom<&Token> for AstItem {
@@ -40,17 +128,31 @@ impl TryFrom<&Token> for AstItem {
     fn try_from(token: &Token) -> Result<Self, Self::Error> {
         match token {
             Token::Raw(token) => conversions::convert_token_to_command(token),
+            Token::Subshell(list) => Ok(AstItem::Subshell(Box::new(parse_list(list, ')')?))),
+            Token::BraceGroup(list) => Ok(AstItem::Group(Box::new(parse_list(list, '}')?))),
             token => Err(Exception::ConversionNotImplemented(format!("{:?}", token))),
         }
     }
 }
 
 use crate::exception::Exception;
-use AstItem::{Command, LogicalExpression, Pipeline, Script};
+use crate::parser::token::tokenize_raw_line;
+use AstItem::{LogicalExpression, Pipeline, Script};
+
+// the list inside a group is parsed like a line of its own
+fn parse_list(list: &str, closing_char: char) -> Result<AstItem, Exception> {
+    match tokenize_raw_line(list).as_slice() {
+        [] => Err(Exception::TokensCannotBeParsed(format!(
+            "parse error near `{}'",
+            closing_char
+        ))),
+        tokens => parse_to_ast(tokens),
+    }
+}
 
-fn split_last_by_logical_expr(
-    tokens: &[Token],
-) -> Result<Option<(LogicalExpressionOp, &[Token], &[Token])>, Exception> {
+type LogicalSplit<'a> = (LogicalExpressionOp, &'a [Token], &'a [Token]);
+
+fn split_last_by_logical_expr(tokens: &[Token]) -> Result<Option<LogicalSplit<'_>>, Exception> {
     let maybe_index = tokens
         .iter()
         .rposition(|token| token == &Token::Or || token == &Token::And);
@@ -58,7 +160,7 @@ fn split_last_by_logical_expr(
     match maybe_index {
         Some(i) => {
             let (left, right_with_token) = tokens.split_at(i);
-            let logical_op = LogicalExpressionOp::try_from(right_with_token.get(0).unwrap())?;
+            let logical_op = LogicalExpressionOp::try_from(right_with_token.first().unwrap())?;
 
             Ok(Some((logical_op, left, &right_with_token[1..])))
         }
@@ -79,6 +181,32 @@ fn group_by_pipeline(tokens: &[Token]) -> Result<Option<Vec<&[Token]>>, Exceptio
     }
 }
 
+// Splits `!`, `time` or `time -p` from the start of a pipeline
+// >> split_pipeline_prefix(&[Raw("! a"), Pipeline, Raw("b")])
+// Some(("!", vec![Raw("a"), Pipeline, Raw("b")]))
+fn split_pipeline_prefix(tokens: &[Token]) -> Option<(&'static str, Vec<Token>)> {
+    let first = match tokens.first() {
+        Some(Token::Raw(first)) => first,
+        _ => return None,
+    };
+
+    let (prefix, rest) = ["!", "time -p", "time"].iter().find_map(|prefix| {
+        let rest = first.strip_prefix(prefix)?;
+        match rest.is_empty() || rest.starts_with(char::is_whitespace) {
+            true => Some((*prefix, rest.trim_start())),
+            false => None,
+        }
+    })?;
+
+    let mut rest_tokens = Vec::new();
+    if !rest.is_empty() {
+        rest_tokens.push(Token::Raw(String::from(rest)));
+    }
+    rest_tokens.extend_from_slice(&tokens[1..]);
+
+    Some((prefix, rest_tokens))
+}
+
 // ast prioritizes tokens to be evaluated earlier to represent the tree as how it should be executed
 // tree will be executed depth-first
 
@@ -87,7 +215,7 @@ pub fn parse_to_ast(tokens: &[Token]) -> Result<AstItem, Exception> {
         let tokens: Result<Vec<_>, _> = tokens
             .split(|t| t == &Token::Semicolon)
             .filter(|slice| !slice.is_empty())
-            .map(|token_slice| parse_to_ast(token_slice))
+            .map(parse_to_ast)
             .collect();
 
         return Ok(Script(tokens?));
@@ -105,10 +233,23 @@ pub fn parse_to_ast(tokens: &[Token]) -> Result<AstItem, Exception> {
         });
     }
 
+    if let Some((prefix, tokens)) = split_pipeline_prefix(tokens) {
+        let item = match tokens.is_empty() {
+            true => return Err(Exception::TokensCannotBeParsed(format!("parse error near `{}'", prefix))),
+            false => Box::new(parse_to_ast(&tokens)?),
+        };
+
+        return Ok(match prefix {
+            "!" => AstItem::Negated(item),
+            "time -p" => AstItem::Timed { item, posix: true },
+            _ => AstItem::Timed { item, posix: false },
+        });
+    }
+
     if let Some(groups) = group_by_pipeline(tokens)? {
         let tokens: Result<Vec<_>, _> = groups
             .into_iter()
-            .map(|token_slice| parse_to_ast(token_slice))
+            .map(parse_to_ast)
             .collect();
 
         return Ok(Pipeline(tokens?));
@@ -118,8 +259,13 @@ pub fn parse_to_ast(tokens: &[Token]) -> Result<AstItem, Exception> {
         return Err(Exception::AsyncIsUnSupported);
     }
 
-    match &tokens {
-        &[token] => AstItem::try_from(token),
+    match tokens {
+        [token] => AstItem::try_from(token),
+        // the words after a group are its redirections, eg: `{ a; b; } > file`
+        [group @ Token::Subshell(_), Token::Raw(words)]
+        | [group @ Token::BraceGroup(_), Token::Raw(words)] => {
+            conversions::redirect(AstItem::try_from(group)?, words)
+        }
         tokens => Err(Exception::Unexpected(format!("Ast should have processed all non raw tokens: {:?}", tokens))),
     }
 }
@@ -127,6 +273,7 @@ pub fn parse_to_ast(tokens: &[Token]) -> Result<AstItem, Exception> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use AstItem::Command;
 
     #[test]
     fn test_logical_expression() {
@@ -381,4 +528,38 @@ mod tests {
     fn create_cmd(token: &str) -> AstItem {
         AstItem::try_from(&Token::Raw(String::from(token))).unwrap()
     }
+
+    #[test]
+    fn test_groups() {
+        let tokens = tokenize_raw_line("(a; b) | { c; } 2>> err");
+        let command = |keyword: &str| Command {
+            keyword: String::from(keyword),
+            args: Vec::new(),
+        };
+
+        assert_eq!(
+            parse_to_ast(&tokens),
+            Ok(Pipeline(vec![
+                AstItem::Subshell(Box::new(Script(vec![command("a"), command("b")]))),
+                AstItem::Redirected {
+                    item: Box::new(AstItem::Group(Box::new(Script(vec![command("c")])))),
+                    redirections: vec![Redirection::Output {
+                        stream: crate::sink::Stream::Stderr,
+                        target: String::from("err"),
+                        append: true,
+                        clobber: false,
+                    }],
+                },
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_group_errors() {
+        let errors = vec!["( )", "{ a; } b", "(a", "{ a"];
+
+        for line in errors {
+            assert!(parse_to_ast(&tokenize_raw_line(line)).is_err(), "parsing {}", line);
+        }
+    }
 }
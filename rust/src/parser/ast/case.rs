@@ -0,0 +1,283 @@
+use crate::exception::Exception;
+use crate::parser::ast::{parse_to_ast, AstItem, CaseItem, CaseTerminator};
+use crate::parser::token::tokenize_raw_line;
+use crate::parser::word;
+
+// Parses a whole `case WORD in PATTERN|PATTERN) LIST;; ... esac` token.
+// Bodies are parsed as command lists of their own.
+// >> parse_case("case $1 in -h|--help) usage;; *) run;; esac")
+// Case { word: "$1", items: [CaseItem { patterns: ["-h", "--help"], .. }, ..] }
+pub fn parse_case(raw: &str) -> Result<AstItem, Exception> {
+    let chars: Vec<char> = raw.chars().collect();
+    let mut scanner = Scanner { chars, position: 0 };
+
+    scanner.expect_word("case")?;
+    let word = scanner
+        .word()
+        .ok_or_else(|| syntax_error("expected a word after `case'"))?;
+    scanner.expect_word("in")?;
+
+    let mut items = Vec::new();
+
+    loop {
+        scanner.skip_whitespace();
+
+        if scanner.at_word("esac") {
+            scanner.position += 4;
+            break;
+        }
+
+        if scanner.is_end() {
+            return Err(syntax_error("expected `esac'"));
+        }
+
+        let patterns = scanner.patterns()?;
+        let (body, terminator) = scanner.body();
+        let body = match body.trim() {
+            "" => None,
+            body => Some(parse_to_ast(&tokenize_raw_line(body))?),
+        };
+
+        items.push(CaseItem {
+            patterns,
+            body,
+            terminator,
+        });
+    }
+
+    scanner.skip_whitespace();
+    if !scanner.is_end() {
+        return Err(syntax_error("unexpected text after `esac'"));
+    }
+
+    Ok(AstItem::Case { word, items })
+}
+
+fn syntax_error(message: &str) -> Exception {
+    Exception::TokensCannotBeParsed(format!("case: {}", message))
+}
+
+struct Scanner {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Scanner {
+    fn is_end(&self) -> bool {
+        self.position >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn rest(&self) -> String {
+        self.chars[self.position..].iter().collect()
+    }
+
+    // comments between the items are skipped too
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    while self.peek().is_some_and(|char| char != '\n') {
+                        self.position += 1;
+                    }
+                }
+                Some(char) if char.is_whitespace() => self.position += 1,
+                _ => return,
+            }
+        }
+    }
+
+    // a keyword at the current position that is followed by a separator or the end
+    fn at_word(&self, keyword: &str) -> bool {
+        let rest = self.rest();
+        rest.starts_with(keyword)
+            && rest[keyword.len()..]
+                .chars()
+                .next()
+                .is_none_or(|char| char.is_whitespace() || char == ';' || char == ')')
+    }
+
+    fn expect_word(&mut self, keyword: &str) -> Result<(), Exception> {
+        self.skip_whitespace();
+
+        match self.at_word(keyword) {
+            true => {
+                self.position += keyword.len();
+                Ok(())
+            }
+            false => Err(syntax_error(&format!("expected `{}'", keyword))),
+        }
+    }
+
+    // the next word, quotes are kept and whitespace in quotes doesn't end it
+    fn word(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let words = word::split_words(&self.rest());
+        let first = words.into_iter().next()?;
+        self.position += first.chars().count();
+        Some(first)
+    }
+
+    // `(a|b)` or `a|b)`, returns the raw patterns
+    fn patterns(&mut self) -> Result<Vec<String>, Exception> {
+        if self.peek() == Some('(') {
+            self.position += 1;
+        }
+
+        let mut patterns = Vec::new();
+        let mut pattern = String::new();
+        let mut quote = None;
+
+        while let Some(char) = self.peek() {
+            self.position += 1;
+
+            match (char, quote) {
+                ('\\', Some('\'')) => (),
+                ('\\', _) => {
+                    pattern.push(char);
+                    pattern.extend(self.peek());
+                    self.position += 1;
+                    continue;
+                }
+                (c, Some(q)) if c == q => quote = None,
+                ('\'', None) | ('"', None) => quote = Some(char),
+                ('|', None) | (')', None) => {
+                    patterns.push(String::from(pattern.trim()));
+                    pattern = String::new();
+
+                    if char == ')' {
+                        return Ok(patterns);
+                    }
+                    continue;
+                }
+                _ => (),
+            }
+
+            pattern.push(char);
+        }
+
+        Err(syntax_error("expected `)' after the patterns"))
+    }
+
+    // the list up to `;;`, `;&`, `;;&` or the `esac` that ends the case
+    fn body(&mut self) -> (String, CaseTerminator) {
+        let start = self.position;
+        let mut quote = None;
+        let mut depth = 0;
+
+        while let Some(char) = self.peek() {
+            let previous = match self.position {
+                0 => None,
+                i => self.chars.get(i - 1).copied(),
+            };
+            let at_keyword_start =
+                previous.is_none_or(|c| c.is_whitespace() || c == ';' || c == '(' || c == ')');
+
+            match (char, quote) {
+                ('\\', Some('\'')) => (),
+                ('\\', _) => self.position += 1,
+                (c, Some(q)) if c == q => quote = None,
+                (_, Some(_)) => (),
+                ('\'', None) | ('"', None) => quote = Some(char),
+                ('c', None) if at_keyword_start && self.at_word("case") => depth += 1,
+                ('e', None) if at_keyword_start && self.at_word("esac") => {
+                    if depth == 0 {
+                        let body = self.chars[start..self.position].iter().collect();
+                        return (body, CaseTerminator::Break);
+                    }
+                    depth -= 1;
+                }
+                (';', None) if depth == 0 => {
+                    let body = self.chars[start..self.position].iter().collect();
+                    let rest = self.rest();
+
+                    let (terminator, length) = match rest.as_str() {
+                        rest if rest.starts_with(";;&") => (CaseTerminator::Continue, 3),
+                        rest if rest.starts_with(";;") => (CaseTerminator::Break, 2),
+                        rest if rest.starts_with(";&") => (CaseTerminator::FallThrough, 2),
+                        _ => {
+                            self.position += 1;
+                            continue;
+                        }
+                    };
+
+                    self.position += length;
+                    return (body, terminator);
+                }
+                _ => (),
+            }
+
+            self.position += 1;
+        }
+
+        let body = self.chars[start..].iter().collect();
+        (body, CaseTerminator::Break)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(keyword: &str) -> AstItem {
+        AstItem::Command {
+            keyword: String::from(keyword),
+            args: Vec::new(),
+        }
+    }
+
+    fn item(patterns: &[&str], body: Option<AstItem>, terminator: CaseTerminator) -> CaseItem {
+        CaseItem {
+            patterns: patterns.iter().map(|pattern| String::from(*pattern)).collect(),
+            body,
+            terminator,
+        }
+    }
+
+    #[test]
+    fn test_parse_case() {
+        let expected = AstItem::Case {
+            word: String::from("\"$1\""),
+            items: vec![
+                item(&["-h", "--help"], Some(command("usage")), CaseTerminator::Break),
+                item(&["'a|b'"], Some(command("x")), CaseTerminator::FallThrough),
+                item(&["x"], None, CaseTerminator::Continue),
+                item(&["*"], Some(command("run")), CaseTerminator::Break),
+            ],
+        };
+
+        assert_eq!(
+            parse_case("case \"$1\" in -h|--help) usage;; ('a|b') x;& x) ;;& *) run esac"),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn test_nested_case() {
+        let inner = String::from("case b in b) y;; esac");
+        let parsed = parse_case(&format!("case a in a) {};; esac", inner)).unwrap();
+
+        assert_eq!(
+            parsed,
+            AstItem::Case {
+                word: String::from("a"),
+                items: vec![item(
+                    &["a"],
+                    Some(parse_case(&inner).unwrap()),
+                    CaseTerminator::Break
+                )],
+            }
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(parse_case("case a").is_err());
+        assert!(parse_case("case a in a) x;;").is_err());
+        assert!(parse_case("case a in a x;; esac").is_err());
+        assert!(parse_case("case a in esac").is_ok());
+    }
+}
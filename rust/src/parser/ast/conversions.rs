@@ -1,15 +1,78 @@
 use crate::exception::Exception;
-use crate::parser::ast::AstItem;
+use crate::parser::ast::{case, AstItem};
+use crate::parser::condition;
+use crate::parser::redirection::{self, Redirection};
+use crate::parser::word;
 
-pub fn convert_token_to_command(token: &String) -> Result<AstItem, Exception> {
-    let mut command_split = token.split_whitespace().map(|s| s.to_string());
+// Words keep their quotes, they are removed when the command is expanded before it runs
+pub fn convert_token_to_command(token: &str) -> Result<AstItem, Exception> {
+    if let Some(expression) = token
+        .strip_prefix("((")
+        .and_then(|rest| rest.strip_suffix("))"))
+    {
+        return Ok(AstItem::Arithmetic(String::from(expression)));
+    }
+
+    // a group that is closed became a token of its own
+    if token.starts_with('(') || token.starts_with("{ ") {
+        let closing_char = if token.starts_with('(') { ')' } else { '}' };
+        return Err(Exception::TokensCannotBeParsed(format!(
+            "unexpected end of line, expected `{}'",
+            closing_char
+        )));
+    }
+
+    let words = word::split_words(token);
+
+    if words.first().map(|word| word.as_str()) == Some("case") {
+        return case::parse_case(token);
+    }
+
+    if words.first().map(|word| word.as_str()) == Some("[[") {
+        return match words.last().map(|word| word.as_str()) {
+            Some("]]") if words.len() > 1 => Ok(AstItem::Conditional(
+                condition::parse_condition(&words[1..words.len() - 1])?,
+            )),
+            _ => Err(Exception::TokensCannotBeParsed(String::from(
+                "unexpected end of line, expected `]]'",
+            ))),
+        };
+    }
 
-    Ok(AstItem::Command {
+    let (words, redirections) = redirection::split_redirections(words)?;
+    let mut command_split = words.into_iter();
+    let command = AstItem::Command {
         keyword: command_split
             .next()
             .ok_or(Exception::CommandHasNoCharacters)?,
         args: command_split.collect(),
-    })
+    };
+
+    Ok(with_redirections(command, redirections))
+}
+
+// Redirects an item that is followed by words, every word has to be a redirection
+// >> redirect(Group(..), "> file")
+pub fn redirect(item: AstItem, words: &str) -> Result<AstItem, Exception> {
+    let (words, redirections) = redirection::split_redirections(word::split_words(words))?;
+
+    match words.first() {
+        Some(word) => Err(Exception::TokensCannotBeParsed(format!(
+            "parse error near `{}'",
+            word
+        ))),
+        None => Ok(with_redirections(item, redirections)),
+    }
+}
+
+fn with_redirections(item: AstItem, redirections: Vec<Redirection>) -> AstItem {
+    match redirections.is_empty() {
+        true => item,
+        false => AstItem::Redirected {
+            item: Box::new(item),
+            redirections,
+        },
+    }
 }
 
 #[cfg(test)]
@@ -42,9 +105,56 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_arithmetic_command() {
+        assert_eq!(
+            convert_token_to_command("(( a = 1 + 2 ))"),
+            Ok(AstItem::Arithmetic(String::from(" a = 1 + 2 ")))
+        );
+    }
+
+    #[test]
+    fn test_redirected_command() {
+        assert_eq!(
+            convert_token_to_command("cat <<< 'a b' -n"),
+            Ok(AstItem::Redirected {
+                item: Box::new(AstItem::Command {
+                    keyword: String::from("cat"),
+                    args: vec![String::from("-n")]
+                }),
+                redirections: vec![Redirection::HereString(String::from("'a b'"))]
+            })
+        );
+    }
+
+    #[test]
+    fn test_conditional_command() {
+        assert_eq!(
+            convert_token_to_command("[[ -n \"a b\" ]]"),
+            Ok(AstItem::Conditional(condition::Condition::Unary {
+                op: String::from("-n"),
+                operand: String::from("\"a b\""),
+            }))
+        );
+        assert!(convert_token_to_command("[[ -n a").is_err());
+    }
+
+    #[test]
+    fn test_command_keeps_quoted_words_together() {
+        let cmd = convert_token_to_command("cat 'my file.md' \"*.md\"");
+
+        assert_eq!(
+            cmd,
+            Ok(AstItem::Command {
+                keyword: String::from("cat"),
+                args: vec![String::from("'my file.md'"), String::from("\"*.md\"")]
+            })
+        )
+    }
+
     #[test]
     fn test_command_fails() {
-        let cmd = convert_token_to_command(&String::from(""));
+        let cmd = convert_token_to_command("");
         assert_eq!(cmd, Err(Exception::CommandHasNoCharacters));
     }
 }
@@ -0,0 +1,215 @@
+use crate::exception::Exception;
+use crate::parser::ast::LogicalExpressionOp;
+
+// The expression of a `[[ ... ]]` command. Words are kept raw, they are expanded when the
+// condition is evaluated, without word splitting and pathname expansion.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Condition {
+    // true when the word is not empty
+    Word(String),
+    Unary {
+        op: String,
+        operand: String,
+    },
+    Binary {
+        left: String,
+        op: String,
+        right: String,
+    },
+    Not(Box<Condition>),
+    Logical {
+        op: LogicalExpressionOp,
+        left: Box<Condition>,
+        right: Box<Condition>,
+    },
+}
+
+pub fn is_unary_operator(word: &str) -> bool {
+    matches!(
+        word,
+        "-z" | "-n" | "-e" | "-a" | "-f" | "-d" | "-h" | "-L" | "-r" | "-w" | "-x" | "-s" | "-v"
+    )
+}
+
+pub fn is_binary_operator(word: &str) -> bool {
+    matches!(
+        word,
+        "=" | "==" | "!=" | "<" | ">" | "=~" | "-eq" | "-ne" | "-lt" | "-le" | "-gt" | "-ge"
+    )
+}
+
+// Parses the words between `[[` and `]]`, `||` binds weaker than `&&` and `!`
+// >> parse_condition(["-f", "$A", "&&", "!", "$B", "==", "*.md"])
+// Logical { op: And, left: Unary { .. }, right: Not(Binary { .. }) }
+pub fn parse_condition(words: &[String]) -> Result<Condition, Exception> {
+    let mut parser = Parser { words, position: 0 };
+    let condition = parser.or()?;
+
+    match parser.peek() {
+        None => Ok(condition),
+        Some(word) => Err(syntax_error(word)),
+    }
+}
+
+fn syntax_error(near: &str) -> Exception {
+    Exception::TokensCannotBeParsed(format!(
+        "syntax error in conditional expression near `{}'",
+        near
+    ))
+}
+
+struct Parser<'a> {
+    words: &'a [String],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.words.get(self.position).map(|word| word.as_str())
+    }
+
+    fn next(&mut self) -> Result<&'a str, Exception> {
+        let word = self.peek().ok_or_else(|| syntax_error("]]"))?;
+        self.position += 1;
+        Ok(word)
+    }
+
+    fn or(&mut self) -> Result<Condition, Exception> {
+        let mut left = self.and()?;
+
+        while self.peek() == Some("||") {
+            self.position += 1;
+            left = Condition::Logical {
+                op: LogicalExpressionOp::Or,
+                left: Box::new(left),
+                right: Box::new(self.and()?),
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Condition, Exception> {
+        let mut left = self.not()?;
+
+        while self.peek() == Some("&&") {
+            self.position += 1;
+            left = Condition::Logical {
+                op: LogicalExpressionOp::And,
+                left: Box::new(left),
+                right: Box::new(self.not()?),
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Condition, Exception> {
+        match self.peek() {
+            Some("!") => {
+                self.position += 1;
+                Ok(Condition::Not(Box::new(self.not()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Condition, Exception> {
+        let word = self.next()?;
+
+        if word == "(" {
+            let condition = self.or()?;
+            return match self.next()? {
+                ")" => Ok(condition),
+                word => Err(syntax_error(word)),
+            };
+        }
+
+        if matches!(word, "&&" | "||" | ")") {
+            return Err(syntax_error(word));
+        }
+
+        if is_unary_operator(word) && !self.is_end() {
+            let operand = self.next()?;
+            return Ok(Condition::Unary {
+                op: String::from(word),
+                operand: String::from(operand),
+            });
+        }
+
+        match self.peek() {
+            Some(op) if is_binary_operator(op) => {
+                self.position += 1;
+                let right = self.next()?;
+                Ok(Condition::Binary {
+                    left: String::from(word),
+                    op: String::from(op),
+                    right: String::from(right),
+                })
+            }
+            _ => Ok(Condition::Word(String::from(word))),
+        }
+    }
+
+    // no operand follows, eg: the `-n` in `[[ -n ]]` is just a word
+    fn is_end(&self) -> bool {
+        matches!(self.peek(), None | Some("&&") | Some("||") | Some(")"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Condition, Exception> {
+        let words: Vec<String> = line.split(' ').map(String::from).collect();
+        parse_condition(&words)
+    }
+
+    fn word(text: &str) -> Condition {
+        Condition::Word(String::from(text))
+    }
+
+    #[test]
+    fn test_precedence() {
+        let expected = Condition::Logical {
+            op: LogicalExpressionOp::Or,
+            left: Box::new(word("a")),
+            right: Box::new(Condition::Logical {
+                op: LogicalExpressionOp::And,
+                left: Box::new(Condition::Not(Box::new(word("b")))),
+                right: Box::new(word("c")),
+            }),
+        };
+
+        assert_eq!(parse("a || ! b && c"), Ok(expected));
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_eq!(
+            parse("-f $A"),
+            Ok(Condition::Unary {
+                op: String::from("-f"),
+                operand: String::from("$A"),
+            })
+        );
+        assert_eq!(
+            parse("( $A == *.md )"),
+            Ok(Condition::Binary {
+                left: String::from("$A"),
+                op: String::from("=="),
+                right: String::from("*.md"),
+            })
+        );
+        assert_eq!(parse("-n"), Ok(word("-n")));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(parse("a &&").is_err());
+        assert!(parse("( a").is_err());
+        assert!(parse("a b").is_err());
+        assert!(parse("a == ").is_ok());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::parser::ast::AstItem;
+use crate::parser::redirection::Redirection;
+
+// A parsed line whose here-documents wait for their bodies. The lines read after it
+// belong to the first unfinished here-document, up to the line with its delimiter.
+// >> cat <<A; cat <<B
+// >> first
+// >> A
+// >> second
+// >> B
+#[derive(Debug, Clone)]
+pub struct PendingHereDocs {
+    ast: AstItem,
+    lines: Vec<String>,
+}
+
+impl PendingHereDocs {
+    pub fn new(ast: AstItem) -> Self {
+        PendingHereDocs {
+            ast,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn is_complete(&mut self) -> bool {
+        self.next_here_doc().is_none()
+    }
+
+    // the delimiter that ends the body that is read now
+    pub fn delimiter(&mut self) -> Option<String> {
+        match self.next_here_doc() {
+            Some(Redirection::HereDoc { delimiter, .. }) => Some(delimiter.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn push_line(&mut self, line: &str) {
+        let (delimiter, strip_tabs) = match self.next_here_doc() {
+            Some(Redirection::HereDoc {
+                delimiter,
+                strip_tabs,
+                ..
+            }) => (delimiter.clone(), *strip_tabs),
+            _ => return,
+        };
+
+        let line = match strip_tabs {
+            true => line.trim_start_matches('\t'),
+            false => line,
+        };
+
+        match line == delimiter {
+            true => self.finish_body(),
+            false => self.lines.push(String::from(line)),
+        }
+    }
+
+    // At the end of the input the unfinished bodies end with the lines read so far
+    pub fn into_ast(mut self) -> AstItem {
+        while !self.is_complete() {
+            self.finish_body();
+        }
+
+        self.ast
+    }
+
+    fn finish_body(&mut self) {
+        let text: String = self.lines.drain(..).map(|line| line + "\n").collect();
+
+        if let Some(Redirection::HereDoc { body, .. }) = self.next_here_doc() {
+            *body = Some(text);
+        }
+    }
+
+    fn next_here_doc(&mut self) -> Option<&mut Redirection> {
+        self.ast.here_docs().into_iter().find(|here_doc| {
+            matches!(here_doc, Redirection::HereDoc { body: None, .. })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::parse_to_ast;
+    use crate::parser::token::tokenize_raw_line;
+
+    fn pending(line: &str) -> PendingHereDocs {
+        PendingHereDocs::new(parse_to_ast(&tokenize_raw_line(line)).unwrap())
+    }
+
+    fn bodies(ast: &mut AstItem) -> Vec<Option<String>> {
+        ast.here_docs()
+            .into_iter()
+            .map(|here_doc| match here_doc {
+                Redirection::HereDoc { body, .. } => body.clone(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bodies_are_read_in_order() {
+        let mut pending = pending("cat <<A && cat <<-B | cat <<<x");
+        assert!(!pending.is_complete());
+        assert_eq!(pending.delimiter(), Some(String::from("A")));
+
+        for line in ["  first", "", "A", "\t\tsecond", "\tB"] {
+            pending.push_line(line);
+        }
+
+        assert!(pending.is_complete());
+        assert_eq!(
+            bodies(&mut pending.into_ast()),
+            vec![
+                Some(String::from("  first\n\n")),
+                Some(String::from("second\n"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delimiter_must_match_the_whole_line() {
+        let mut pending = pending("cat <<EOF");
+
+        for line in ["EOF ", " EOF", "\tEOF"] {
+            pending.push_line(line);
+            assert!(!pending.is_complete(), "{:?} ended the body", line);
+        }
+    }
+
+    #[test]
+    fn test_end_of_input() {
+        let mut pending = pending("cat <<A; cat <<B");
+        pending.push_line("text");
+
+        assert_eq!(
+            bodies(&mut pending.into_ast()),
+            vec![Some(String::from("text\n")), Some(String::new())]
+        );
+    }
+
+    #[test]
+    fn test_line_without_here_docs() {
+        assert!(pending("cat <<<x; echo '<<A'").is_complete());
+    }
+}
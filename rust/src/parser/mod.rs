@@ -1,3 +1,8 @@
 pub mod token;
 pub mod ast;
+pub mod condition;
+pub mod word;
+pub mod redirection;
+pub mod here_doc;
 
+pub mod alias;
@@ -0,0 +1,309 @@
+use crate::exception::Exception;
+use crate::parser::word::{self, WordPart};
+use crate::sink::Stream;
+
+// A redirection of a command, it's taken out of the words of the command while parsing
+#[derive(Debug, PartialEq, Clone)]
+pub enum Redirection {
+    // `<<EOF`, the body is read from the lines after the command and is None until then.
+    // `<<-EOF` strips leading tabs, a quoted delimiter like `<<'EOF'` turns off expansions.
+    HereDoc {
+        delimiter: String,
+        strip_tabs: bool,
+        expand: bool,
+        body: Option<String>,
+    },
+    // `<<<word`, the expanded word followed by a newline
+    HereString(String),
+    // `< file`, the file becomes the stdin
+    Input(String),
+    // `> file` or `2> file` replaces the file with the output of the stream,
+    // `>> file` and `2>> file` append to it. `>| file` replaces it even with noclobber.
+    Output {
+        stream: Stream,
+        target: String,
+        append: bool,
+        clobber: bool,
+    },
+    // `2>&1` writes the stream to where the target stream goes at this point,
+    // `>&2` writes stdout there
+    Duplicate {
+        stream: Stream,
+        target: Stream,
+    },
+}
+
+// longer operators first, so `<<` isn't read as `<`
+const OPERATORS: [&str; 8] = ["<<<", "<<-", "<<", ">>", ">|", ">&", ">", "<"];
+
+// Splits the redirections from the words of a command, the operator and its word
+// can be written together or apart. A number right before the operator is the file
+// descriptor it redirects, other text before it is a word of the command.
+// >> split_redirections(vec!["cat", "<<", "EOF", "-n"])
+// (vec!["cat", "-n"], vec![HereDoc { delimiter: "EOF", .. }])
+pub fn split_redirections(
+    words: Vec<String>,
+) -> Result<(Vec<String>, Vec<Redirection>), Exception> {
+    let mut command_words = Vec::new();
+    let mut redirections = Vec::new();
+    let mut words = words.into_iter();
+
+    while let Some(word) = words.next() {
+        let (prefix, rest) = match operator_start(&word) {
+            Some(start) => word.split_at(start),
+            None => {
+                command_words.push(word);
+                continue;
+            }
+        };
+
+        let descriptor = match prefix {
+            "" => None,
+            prefix if prefix.chars().all(|c| c.is_ascii_digit()) => Some(prefix),
+            prefix => {
+                command_words.push(String::from(prefix));
+                None
+            }
+        };
+
+        let operator = *OPERATORS
+            .iter()
+            .find(|operator| rest.starts_with(*operator))
+            .expect("A redirection should start with an operator");
+        let target = &rest[operator.len()..];
+
+        let target = match target {
+            "" => words.next().ok_or_else(|| {
+                Exception::TokensCannotBeParsed(String::from("parse error near `\\n'"))
+            })?,
+            target => String::from(target),
+        };
+
+        // only stdin is read from
+        if operator.starts_with('<') && descriptor.is_some_and(|descriptor| descriptor != "0") {
+            return Err(bad_descriptor(descriptor.unwrap_or_default()));
+        }
+
+        redirections.push(match operator {
+            "<<<" => Redirection::HereString(target),
+            "<" => Redirection::Input(target),
+            ">&" => Redirection::Duplicate {
+                stream: output_stream(descriptor)?,
+                target: output_stream(Some(&target))?,
+            },
+            ">" | ">>" | ">|" => Redirection::Output {
+                stream: output_stream(descriptor)?,
+                target,
+                append: operator == ">>",
+                clobber: operator == ">|",
+            },
+            operator => Redirection::HereDoc {
+                delimiter: remove_quotes(&target),
+                strip_tabs: operator == "<<-",
+                expand: !target.contains(&['\'', '"', '\\'][..]),
+                body: None,
+            },
+        });
+    }
+
+    Ok((command_words, redirections))
+}
+
+// Index of the first redirection operator of a word, operators that are quoted or inside
+// of an expansion don't count
+// >> operator_start("a2>out")
+// Some(2)
+fn operator_start(word: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut expansion_depth = 0;
+    let mut previous = None;
+
+    for (i, char) in word.char_indices() {
+        match (char, quote) {
+            _ if escaped => escaped = false,
+            ('\\', Some('\'')) => (),
+            ('\\', _) => escaped = true,
+            (c, Some(q)) if c == q => quote = None,
+            ('\'' | '"', None) => quote = Some(char),
+            (_, Some('\'')) => (),
+            ('(' | '{', _) if expansion_depth > 0 || previous == Some('$') => expansion_depth += 1,
+            (')' | '}', _) if expansion_depth > 0 => expansion_depth -= 1,
+            ('<' | '>', None) if expansion_depth == 0 => return Some(i),
+            _ => (),
+        }
+        previous = Some(char);
+    }
+
+    None
+}
+
+// Only stdout and stderr can be redirected, the descriptor is 1 when it's left out
+fn output_stream(descriptor: Option<&str>) -> Result<Stream, Exception> {
+    match descriptor {
+        None | Some("1") => Ok(Stream::Stdout),
+        Some("2") => Ok(Stream::Stderr),
+        Some(descriptor) => Err(bad_descriptor(descriptor)),
+    }
+}
+
+fn bad_descriptor(descriptor: &str) -> Exception {
+    Exception::TokensCannotBeParsed(format!("{}: bad file descriptor", descriptor))
+}
+
+// The delimiter of a here-document is only unquoted, it's never expanded
+fn remove_quotes(raw: &str) -> String {
+    word::parse_word(raw)
+        .into_iter()
+        .map(|part| match part {
+            WordPart::Literal(text) | WordPart::Quoted(text) => text,
+            WordPart::Parameter { expression, .. } => format!("${}", expression),
+            WordPart::Tilde(user) => format!("~{}", user),
+            WordPart::Arithmetic(expression) => format!("$(({}))", expression),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(raw: &str) -> Vec<String> {
+        word::split_words(raw)
+    }
+
+    fn here_doc(delimiter: &str, strip_tabs: bool, expand: bool) -> Redirection {
+        Redirection::HereDoc {
+            delimiter: String::from(delimiter),
+            strip_tabs,
+            expand,
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_here_docs() {
+        assert_eq!(
+            split_redirections(words("cat <<EOF -n")),
+            Ok((words("cat -n"), vec![here_doc("EOF", false, true)]))
+        );
+        assert_eq!(
+            split_redirections(words("cat << END <<-'A B'")),
+            Ok((words("cat"), vec![here_doc("END", false, true), here_doc("A B", true, false)]))
+        );
+        assert_eq!(
+            split_redirections(words("cat <<\\EOF")),
+            Ok((words("cat"), vec![here_doc("EOF", false, false)]))
+        );
+    }
+
+    #[test]
+    fn test_here_strings() {
+        assert_eq!(
+            split_redirections(words("cat <<<$A")),
+            Ok((
+                words("cat"),
+                vec![Redirection::HereString(String::from("$A"))]
+            ))
+        );
+        assert_eq!(
+            split_redirections(words("cat <<< 'a b'")),
+            Ok((
+                words("cat"),
+                vec![Redirection::HereString(String::from("'a b'"))]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_files() {
+        let output = |stream: Stream, target: &str, append: bool| Redirection::Output {
+            stream,
+            target: String::from(target),
+            append,
+            clobber: false,
+        };
+        let clobbered = |stream: Stream, target: &str| Redirection::Output {
+            stream,
+            target: String::from(target),
+            append: false,
+            clobber: true,
+        };
+
+        assert_eq!(
+            split_redirections(words("echo a >out 2>> err <in b")),
+            Ok((
+                words("echo a b"),
+                vec![
+                    output(Stream::Stdout, "out", false),
+                    output(Stream::Stderr, "err", true),
+                    Redirection::Input(String::from("in"))
+                ]
+            ))
+        );
+        assert_eq!(
+            split_redirections(words("cat >> '$A b'")),
+            Ok((words("cat"), vec![output(Stream::Stdout, "'$A b'", true)]))
+        );
+        assert_eq!(
+            split_redirections(words("cat >|out 2>| err")),
+            Ok((
+                words("cat"),
+                vec![
+                    clobbered(Stream::Stdout, "out"),
+                    clobbered(Stream::Stderr, "err")
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_descriptors() {
+        let output = |stream: Stream, target: &str, append: bool| Redirection::Output {
+            stream,
+            target: String::from(target),
+            append,
+            clobber: false,
+        };
+        let duplicate = |stream: Stream, target: Stream| Redirection::Duplicate { stream, target };
+
+        assert_eq!(
+            split_redirections(words("echo z 1>o 2>>e a>f 0<in")),
+            Ok((
+                words("echo z a"),
+                vec![
+                    output(Stream::Stdout, "o", false),
+                    output(Stream::Stderr, "e", true),
+                    output(Stream::Stdout, "f", false),
+                    Redirection::Input(String::from("in"))
+                ]
+            ))
+        );
+        assert_eq!(
+            split_redirections(words("cat 2>&1 >&2 1>& 2")),
+            Ok((
+                words("cat"),
+                vec![
+                    duplicate(Stream::Stderr, Stream::Stdout),
+                    duplicate(Stream::Stdout, Stream::Stderr),
+                    duplicate(Stream::Stdout, Stream::Stderr)
+                ]
+            ))
+        );
+        assert_eq!(
+            split_redirections(words("echo '1>a' \\> $((1>2)) ${A:->} \">\"")),
+            Ok((words("echo '1>a' \\> $((1>2)) ${A:->} \">\""), vec![]))
+        );
+
+        assert!(split_redirections(words("cat 3>out")).is_err());
+        assert!(split_redirections(words("cat 2<in")).is_err());
+        assert!(split_redirections(words("cat >&file")).is_err());
+    }
+
+    #[test]
+    fn test_missing_word() {
+        assert!(split_redirections(words("cat <<")).is_err());
+        assert!(split_redirections(words("cat <<<")).is_err());
+        assert!(split_redirections(words("cat >")).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt::{Display, Formatter};
+use std::mem;
 use crate::parser::ast::LogicalExpressionOp;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -9,6 +10,8 @@ pub enum Token {
     Async,     // &
     Pipeline,  // |
     Raw(String),
+    Subshell(String),   // ( list ), the list between the parentheses
+    BraceGroup(String), // { list; }, the list between the braces
 }
 
 impl From<&LogicalExpressionOp> for Token {
@@ -29,21 +32,51 @@ impl Display for Token {
             Token::Async => write!(f, "&"),
             Token::Pipeline => write!(f, "|"),
             Token::Raw(string) => write!(f, "{}", string),
+            Token::Subshell(list) => write!(f, "({})", list),
+            Token::BraceGroup(list) => write!(f, "{{{}}}", list),
         }
     }
 }
 
-// transform raw lines in to tokens
+// words in front of a pipeline that change how it runs, eg: `! a | b` or `time -p a`
+pub const PIPELINE_PREFIXES: [&str; 3] = ["!", "time", "-p"];
+
+// transform raw lines in to tokens, a line break ends a command like `;`
 // >> tokenize_raw_line("a; b | c && d")
 // vec!["a", ";", "|", "&&"]
-pub fn tokenize_raw_line(line: &String) -> Vec<Token> {
+pub fn tokenize_raw_line(line: &str) -> Vec<Token> {
+    tokenize(line).0
+}
+
+// A `case`, `( list )` or `{ list; }` that isn't closed yet goes on in the lines after it
+// >> is_open("case $1 in")
+// true
+pub fn is_open(line: &str) -> bool {
+    tokenize(line).1
+}
+
+// the tokens of the lines and whether a compound command in them is still open
+fn tokenize(line: &str) -> (Vec<Token>, bool) {
     let mut result = Vec::new();
     let mut token = String::new();
     let mut has_backslash = false;
     let mut escaper: Option<char> = None; // example: " ' `
     let mut skip_next = false;
+    let mut paren_depth = 0;
+    let mut in_conditional = false;
+    let mut case_depth = 0;
+    let mut in_comment = false;
+    // the closing chars of the groups that are open, eg: `)` for `(`
+    let mut groups: Vec<char> = Vec::new();
+    let chars: Vec<char> = line.chars().collect();
 
     for (i, char) in line.chars().enumerate() {
+        let is_word_start = token.chars().last().is_none_or(char::is_whitespace);
+        let is_keyword_start = token.chars().last().is_none_or(is_separator);
+        let previous_char = token.trim_end_matches([' ', '\t']).chars().last();
+        let is_command_start = is_command_start(&token);
+        let follows_dollar = token.ends_with('$');
+
         let mut push_token_to_result = || {
             let result_of_token = token.trim();
             if !result_of_token.is_empty() {
@@ -68,13 +101,133 @@ pub fn tokenize_raw_line(line: &String) -> Vec<Token> {
             continue;
         }
 
+        // inside double quotes `\"` doesn't close the quote, eg: `"a\"b"`
+        if escaper == Some('"') && char == '\\' {
+            has_backslash = true;
+            token.push(char);
+            continue;
+        }
+
         if escaper == Some(char) {
             escaper = None;
+            token.push(char);
+            continue;
+        }
+
+        // a comment starts at a word, eg: `${#A}` and `a#b` are no comments. It runs to the
+        // end of the line, in a group or case it stays part of the text that is parsed later.
+        let in_compound = case_depth > 0 || !groups.is_empty();
+        let starts_comment = escaper.is_none() && paren_depth == 0 && !in_conditional;
+        if in_comment || (starts_comment && char == '#' && is_word_start) {
+            in_comment = char != '\n';
+            if in_comment {
+                if in_compound {
+                    token.push(char);
+                }
+                continue;
+            }
+        }
+
+        // everything from `case` to `esac` is one token, the `|` and `;;` belong to the case
+        if escaper.is_none() && paren_depth == 0 && !in_conditional && is_keyword_start {
+            match keyword_at(&chars, i).as_str() {
+                "case" if case_depth > 0 || is_command_start => case_depth += 1,
+                "esac" if case_depth > 0 => case_depth -= 1,
+                _ => (),
+            }
+        }
+
+        // `( list )` and `{ list; }` become one token, the list is parsed when the token is.
+        // A `}` only closes a group where a command could start, eg: `{ echo }; }`.
+        if escaper.is_none() && paren_depth == 0 && !in_conditional && case_depth == 0 {
+            let next_char = chars.get(i + 1).copied();
+            let closes_group = match (char, groups.last()) {
+                (')', Some(')')) => true,
+                ('}', Some('}')) => previous_char.is_some_and(|c| ";&})\n".contains(c)),
+                _ => false,
+            };
+            let opens_group = is_command_start
+                && match char {
+                    '(' => next_char != Some('('),
+                    '{' => next_char.is_none_or(char::is_whitespace),
+                    _ => false,
+                };
+
+            if closes_group {
+                groups.pop();
+
+                if groups.is_empty() {
+                    let list = mem::take(&mut token);
+                    result.push(match char {
+                        ')' => Token::Subshell(list),
+                        _ => Token::BraceGroup(list),
+                    });
+                    continue;
+                }
+            }
+
+            if opens_group {
+                groups.push(if char == '(' { ')' } else { '}' });
+
+                if groups.len() == 1 {
+                    push_token_to_result();
+                    token.clear();
+                    continue;
+                }
+            }
+
+            if closes_group || opens_group {
+                token.push(char);
+                continue;
+            }
+        }
+
+        // operators inside `$((...))` and `((...))` are part of the arithmetic expression
+        let starts_arithmetic = follows_dollar || (is_word_start && is_next_char_same());
+        if escaper.is_none() && paren_depth == 0 && char == '(' && starts_arithmetic {
+            paren_depth = 1;
+            token.push(char);
+            continue;
+        }
+
+        // `&&` and `||` between `[[` and `]]` are part of the condition, a `[[` only starts
+        // one where a command starts, eg: `echo [[; x` is two commands
+        let is_double_bracket = is_word_start && is_next_char_same();
+        let opens_conditional = char == '[' && is_command_start;
+        let closes_conditional = char == ']' && in_conditional;
+        if escaper.is_none() && is_double_bracket && (opens_conditional || closes_conditional) {
+            in_conditional = opens_conditional;
+            token.push(char);
+            continue;
+        }
+
+        let is_raw = paren_depth > 0 || in_conditional || case_depth > 0 || !groups.is_empty();
+        if escaper.is_none() && is_raw {
+            match char {
+                '(' => paren_depth += 1,
+                ')' if paren_depth > 0 => paren_depth -= 1,
+                '\\' => has_backslash = true,
+                char if is_escaper(char) => escaper = Some(char),
+                _ => (),
+            }
+
+            token.push(char);
+            continue;
         }
 
         if escaper.is_none() {
-            if char == '#' {
-                break;
+            // after an operator the command goes on in the next line
+            if char == '\n' {
+                push_token_to_result();
+
+                let ends_command = matches!(
+                    result.last(),
+                    Some(Token::Raw(_) | Token::Subshell(_) | Token::BraceGroup(_))
+                );
+                if ends_command {
+                    result.push(Token::Semicolon);
+                }
+                continue;
             }
 
             if char == '\\' {
@@ -88,6 +241,12 @@ pub fn tokenize_raw_line(line: &String) -> Vec<Token> {
                 continue;
             }
 
+            // `>&` is a redirection, eg: `2>&1`
+            if char == '&' && i > 0 && line.chars().nth(i - 1) == Some('>') {
+                token.push(char);
+                continue;
+            }
+
             if char == '&' {
                 push_token_to_result();
 
@@ -101,6 +260,12 @@ pub fn tokenize_raw_line(line: &String) -> Vec<Token> {
                 continue;
             }
 
+            // `>|` is a redirection, not a pipe
+            if char == '|' && i > 0 && line.chars().nth(i - 1) == Some('>') {
+                token.push(char);
+                continue;
+            }
+
             if char == '|' {
                 push_token_to_result();
 
@@ -122,22 +287,49 @@ pub fn tokenize_raw_line(line: &String) -> Vec<Token> {
         token.push(char);
     }
 
+    // a group that isn't closed stays raw, with its opening char
+    if let Some(closing_char) = groups.first() {
+        let opening_char = if *closing_char == ')' { '(' } else { '{' };
+        token.insert(0, opening_char);
+    }
+
     let result_of_token = token.trim();
     if !result_of_token.is_empty() {
         result.push(Token::Raw(String::from(result_of_token)))
     }
 
-    return result;
+    (result, case_depth > 0 || !groups.is_empty())
+}
+
+// A command starts after an operator, the start of a group or a pipeline prefix like `!`
+fn is_command_start(token: &str) -> bool {
+    let start = token.rfind(|c| ";&|({\n".contains(c)).map_or(0, |i| i + 1);
+
+    token[start..]
+        .split_whitespace()
+        .all(|word| PIPELINE_PREFIXES.contains(&word))
+}
+
+fn is_separator(char: char) -> bool {
+    char.is_whitespace() || ";|&()".contains(char)
+}
+
+// the word that starts at the index, up to a separator
+fn keyword_at(chars: &[char], start: usize) -> String {
+    chars[start..]
+        .iter()
+        .take_while(|char| !is_separator(**char))
+        .collect()
 }
 
 fn is_escaper(char: char) -> bool {
-    return char == '\'' || char == '"' || char == '`';
+    char == '\'' || char == '"' || char == '`'
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use Token::{And, Async, Or, Pipeline, Raw, Semicolon};
+    use Token::{And, Async, BraceGroup, Or, Pipeline, Raw, Semicolon, Subshell};
 
     #[test]
     fn test_tokenize_command() {
@@ -153,6 +345,14 @@ mod tests {
                 "ls | wc",
                 vec![Raw(String::from("ls")), Pipeline, Raw(String::from("wc"))],
             ),
+            (
+                "ls >| out | wc",
+                vec![Raw(String::from("ls >| out")), Pipeline, Raw(String::from("wc"))],
+            ),
+            (
+                "ls 2>&1 >&2 | wc",
+                vec![Raw(String::from("ls 2>&1 >&2")), Pipeline, Raw(String::from("wc"))],
+            ),
             ("echo #foo; echo bar", vec![Raw(String::from("echo"))]),
             (
                 "echo foo; echo bar",
@@ -211,12 +411,28 @@ mod tests {
                 ],
             ),
             ("echo \"\\\"\"", vec![Raw(String::from("echo \"\\\"\""))]),
+            (
+                "echo \"a\\\"b; c\"; echo next",
+                vec![
+                    Raw(String::from("echo \"a\\\"b; c\"")),
+                    Semicolon,
+                    Raw(String::from("echo next")),
+                ],
+            ),
+            (
+                "echo \"a\\\\\" | cat",
+                vec![Raw(String::from("echo \"a\\\\\"")), Pipeline, Raw(String::from("cat"))],
+            ),
             (
                 "man awk| awk -F \"[ ,.\\\"]+\" 'foo' |sort -k2nr|head",
                 vec![
                     Raw(String::from("man awk")),
                     Pipeline,
-                    Raw(String::from("awk -F \"[ ,.\\\"]+\" 'foo' |sort -k2nr|head")),
+                    Raw(String::from("awk -F \"[ ,.\\\"]+\" 'foo'")),
+                    Pipeline,
+                    Raw(String::from("sort -k2nr")),
+                    Pipeline,
+                    Raw(String::from("head")),
                 ],
             ),
             (";", vec![Semicolon]),
@@ -224,6 +440,96 @@ mod tests {
             ("&&", vec![And]),
             ("ls foo\\#bar", vec![Raw(String::from("ls foo\\#bar"))]),
             ("ls \\|\\|foo", vec![Raw(String::from("ls \\|\\|foo"))]),
+            (
+                "(( a || b )); echo $((1|2&3))",
+                vec![
+                    Raw(String::from("(( a || b ))")),
+                    Semicolon,
+                    Raw(String::from("echo $((1|2&3))")),
+                ],
+            ),
+            (
+                "[[ a && (b || c) ]] && d",
+                vec![Raw(String::from("[[ a && (b || c) ]]")), And, Raw(String::from("d"))],
+            ),
+            (
+                "echo [[; type [[ case; ! [[ a ]] || b",
+                vec![
+                    Raw(String::from("echo [[")),
+                    Semicolon,
+                    Raw(String::from("type [[ case")),
+                    Semicolon,
+                    Raw(String::from("! [[ a ]]")),
+                    Or,
+                    Raw(String::from("b")),
+                ],
+            ),
+            (
+                "case $a in (a|b) x;; *) y && z;; esac | cat",
+                vec![
+                    Raw(String::from("case $a in (a|b) x;; *) y && z;; esac")),
+                    Pipeline,
+                    Raw(String::from("cat")),
+                ],
+            ),
+            (
+                "echo case; case a in a) case b in b) x;;esac;; esac",
+                vec![
+                    Raw(String::from("echo case")),
+                    Semicolon,
+                    Raw(String::from("case a in a) case b in b) x;;esac;; esac")),
+                ],
+            ),
+            (
+                "echo ${#A} a#b # comment",
+                vec![Raw(String::from("echo ${#A} a#b"))],
+            ),
+            (
+                "echo 'a;b';c",
+                vec![Raw(String::from("echo 'a;b'")), Semicolon, Raw(String::from("c"))],
+            ),
+            (
+                "(cd /tmp && ls) | { cat; echo }; } > out; x",
+                vec![
+                    Subshell(String::from("cd /tmp && ls")),
+                    Pipeline,
+                    BraceGroup(String::from(" cat; echo }; ")),
+                    Raw(String::from("> out")),
+                    Semicolon,
+                    Raw(String::from("x")),
+                ],
+            ),
+            (
+                "( (a) ; { b;}; echo ')' $((1)) ${A} ) || c",
+                vec![
+                    Subshell(String::from(" (a) ; { b;}; echo ')' $((1)) ${A} ")),
+                    Or,
+                    Raw(String::from("c")),
+                ],
+            ),
+            (
+                "(case a in a) x;; esac)",
+                vec![Subshell(String::from("case a in a) x;; esac"))],
+            ),
+            (
+                "echo {a,b} { a; (b",
+                vec![
+                    Raw(String::from("echo {a,b} { a")),
+                    Semicolon,
+                    Raw(String::from("(b")),
+                ],
+            ),
+            ("{ a; (b", vec![Raw(String::from("{ a; (b"))]),
+            (
+                "! (a) && time -p { b; }",
+                vec![
+                    Raw(String::from("!")),
+                    Subshell(String::from("a")),
+                    And,
+                    Raw(String::from("time -p")),
+                    BraceGroup(String::from(" b; ")),
+                ],
+            ),
         ];
 
         for (line, result) in expected_a_b {
@@ -233,6 +539,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_line_breaks() {
+        let expected = vec![
+            (
+                "a\n\nb # c; d\n",
+                vec![Raw(String::from("a")), Semicolon, Raw(String::from("b")), Semicolon],
+            ),
+            (
+                "a &&\n b |\n c",
+                vec![Raw(String::from("a")), And, Raw(String::from("b")), Pipeline, Raw(String::from("c"))],
+            ),
+            (
+                "{ # it's a group\n  a\n}\nb",
+                vec![
+                    BraceGroup(String::from(" # it's a group\n  a\n")),
+                    Semicolon,
+                    Raw(String::from("b")),
+                ],
+            ),
+            (
+                "case $a in\n  a) x\n  ;;\nesac",
+                vec![Raw(String::from("case $a in\n  a) x\n  ;;\nesac"))],
+            ),
+        ];
+
+        for (line, result) in expected {
+            assert_vec_token_equals(tokenize_raw_line(line), result);
+        }
+    }
+
+    #[test]
+    fn test_is_open() {
+        for line in ["case $a in", "{ a", "{\n case a in a) x;; esac", "(a\n", "{ # }"] {
+            assert!(is_open(line), "{}", line);
+        }
+        for line in ["a", "{ a; }", "{\n a\n}", "case a in a) x;; esac", "echo '{' # (", "echo {a,b}"] {
+            assert!(!is_open(line), "{}", line);
+        }
+    }
+
     fn assert_vec_token_equals(a: Vec<Token>, b: Vec<Token>) {
         assert_eq!(
             a.len(),
@@ -0,0 +1,544 @@
+// A word of a command split in to the parts that are expanded differently,
+// quotes are removed during parsing but are remembered per part.
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum WordPart {
+    // unquoted text, can be a glob pattern
+    Literal(String),
+    // text in single quotes, double quotes or escaped by a backslash
+    Quoted(String),
+    // `$NAME` or `${...}`, expression is the part after the `$` without braces
+    Parameter { expression: String, quoted: bool },
+    // `~` or `~user` at the start of a word, up to the first "/"
+    Tilde(String),
+    // `$((...))`, the expression between the parentheses
+    Arithmetic(String),
+}
+
+// Splits a raw command on whitespace that is not quoted or escaped, quotes stay part of the words
+// >> split_words("echo 'a b' c\ d")
+// vec!["echo", "'a b'", "c\ d"]
+pub fn split_words(raw: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut chars = raw.chars().peekable();
+    let mut quote: Option<char> = None;
+    let mut brace_depth = 0;
+    let mut paren_depth = 0;
+
+    while let Some(char) = chars.next() {
+        match (char, quote) {
+            ('\\', Some('\'')) => (),
+            ('\\', _) => {
+                word.push(char);
+                word.extend(chars.next());
+                continue;
+            }
+            (c, Some(q)) if c == q => quote = None,
+            ('\'', None) | ('"', None) => quote = Some(char),
+            ('$', None) | ('$', Some('"')) if chars.peek() == Some(&'{') => {
+                word.push(char);
+                word.extend(chars.next());
+                brace_depth += 1;
+                continue;
+            }
+            ('}', None) | ('}', Some('"')) if brace_depth > 0 => brace_depth -= 1,
+            // `$((1 + 2))`, `((a = 1))` and `a=(1 2)` are one word
+            ('(', None) | ('(', Some('"'))
+                if paren_depth > 0 || word.ends_with('$') || word.ends_with('=') || word == "(" =>
+            {
+                paren_depth += 1
+            }
+            (')', None) | (')', Some('"')) if paren_depth > 0 => paren_depth -= 1,
+            (c, None) if c.is_whitespace() && brace_depth == 0 && paren_depth == 0 => {
+                if !word.is_empty() {
+                    words.push(word);
+                    word = String::new();
+                }
+                continue;
+            }
+            _ => (),
+        }
+
+        word.push(char);
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+// A word of the form `NAME=value` is an assignment, the parts of it are unexpanded.
+// An element of an array is set with `NAME[SUBSCRIPT]=value` and `+=` appends to the value.
+#[derive(Debug, PartialEq)]
+pub struct Assignment<'a> {
+    pub name: &'a str,
+    pub subscript: Option<&'a str>,
+    pub append: bool,
+    pub value: &'a str,
+}
+
+impl<'a> Assignment<'a> {
+    // the words between the parentheses of `NAME=(a b c)`
+    pub fn elements(&self) -> Option<Vec<String>> {
+        let list = self.value.strip_prefix('(')?.strip_suffix(')')?;
+        Some(split_words(list))
+    }
+}
+
+// >> split_assignment("PATH+=:~/bin")
+// Some(Assignment { name: "PATH", subscript: None, append: true, value: ":~/bin" })
+pub fn split_assignment(raw: &str) -> Option<Assignment<'_>> {
+    let name_end = raw
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(raw.len());
+    let (name, rest) = raw.split_at(name_end);
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return None;
+    }
+
+    let (subscript, rest) = match rest.strip_prefix('[') {
+        Some(rest) => {
+            let end = subscript_length(rest)?;
+            (Some(&rest[..end]), &rest[end + 1..])
+        }
+        None => (None, rest),
+    };
+
+    let (append, value) = match rest.strip_prefix("+=") {
+        Some(value) => (true, value),
+        None => (false, rest.strip_prefix('=')?),
+    };
+
+    Some(Assignment {
+        name,
+        subscript,
+        append,
+        value,
+    })
+}
+
+// An element of `NAME=(...)` with a subscript, eg: `[key]=value`
+// >> split_keyed_element("[1+1]=b")
+// Some(("1+1", "b"))
+pub fn split_keyed_element(raw: &str) -> Option<(&str, &str)> {
+    let rest = raw.strip_prefix('[')?;
+    let end = subscript_length(rest)?;
+    Some((&rest[..end], rest[end + 1..].strip_prefix('=')?))
+}
+
+// length of a subscript up to its closing `]`, subscripts can contain brackets too
+fn subscript_length(text: &str) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, char) in text.char_indices() {
+        match char {
+            '[' => depth += 1,
+            ']' if depth == 0 => return Some(i),
+            ']' => depth -= 1,
+            _ => (),
+        }
+    }
+
+    None
+}
+
+// Quotes a text so the shell reads it back as one word, plain words stay as they are
+// >> quote("it's")
+// "'it'\\''s'"
+pub fn quote(text: &str) -> String {
+    let is_plain = |char: char| char.is_ascii_alphanumeric() || "_-./=:,+@%^".contains(char);
+
+    match !text.is_empty() && text.chars().all(is_plain) {
+        true => String::from(text),
+        false => format!("'{}'", text.replace('\'', "'\\''")),
+    }
+}
+
+pub fn parse_word(raw: &str) -> Vec<WordPart> {
+    let chars: Vec<char> = raw.chars().collect();
+    let mut parts = Vec::new();
+    let mut i = 0;
+
+    if let Some((user, end)) = parse_tilde(&chars) {
+        parts.push(WordPart::Tilde(user));
+        i = end;
+    }
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                push_quoted(&mut parts, &chars[i + 1].to_string());
+                i += 2;
+            }
+            '\'' => {
+                let end = find(&chars, i + 1, '\'');
+                let text: String = chars[i + 1..end].iter().collect();
+                push_quoted(&mut parts, &text);
+                i = end + 1;
+            }
+            '"' => i = parse_double_quoted(&chars, i + 1, &mut parts),
+            '$' => match parse_expansion(&chars, i, false) {
+                Some((part, end)) => {
+                    parts.push(part);
+                    i = end;
+                }
+                None => {
+                    push_literal(&mut parts, "$");
+                    i += 1;
+                }
+            },
+            char => {
+                push_literal(&mut parts, &char.to_string());
+                i += 1;
+            }
+        }
+    }
+
+    parts
+}
+
+// A tilde prefix is only expanded when none of its characters are quoted,
+// returns the user name and the index after it
+fn parse_tilde(chars: &[char]) -> Option<(String, usize)> {
+    if chars.first() != Some(&'~') {
+        return None;
+    }
+
+    let end = find(chars, 1, '/');
+    let user: String = chars[1..end].iter().collect();
+
+    match user.chars().all(|c| c.is_alphanumeric() || "_-.+".contains(c)) {
+        true => Some((user, end)),
+        false => None,
+    }
+}
+
+// parses the inside of double quotes starting after the opening quote,
+// returns the index after the closing quote
+fn parse_double_quoted(chars: &[char], start: usize, parts: &mut Vec<WordPart>) -> usize {
+    let mut i = start;
+
+    // an empty pair of quotes still makes a (empty) word
+    push_quoted(parts, "");
+
+    while i < chars.len() {
+        match chars[i] {
+            '"' => return i + 1,
+            '\\' if matches!(chars.get(i + 1), Some('$' | '`' | '"' | '\\')) => {
+                push_quoted(parts, &chars[i + 1].to_string());
+                i += 2;
+            }
+            '$' => match parse_expansion(chars, i, true) {
+                Some((part, end)) => {
+                    parts.push(part);
+                    i = end;
+                }
+                None => {
+                    push_quoted(parts, "$");
+                    i += 1;
+                }
+            },
+            char => {
+                push_quoted(parts, &char.to_string());
+                i += 1;
+            }
+        }
+    }
+
+    i
+}
+
+// The body of a here-document is expanded like the inside of double quotes, but quotes
+// are ordinary characters and a backslash before a newline joins two lines
+// >> parse_here_doc("\"$A\" \\$B\n")
+// vec![Quoted("\""), Parameter { expression: "A", quoted: true }, Quoted("\" $B\n")]
+pub fn parse_here_doc(body: &str) -> Vec<WordPart> {
+    let chars: Vec<char> = body.chars().collect();
+    let mut parts = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if chars.get(i + 1) == Some(&'\n') => i += 2,
+            '\\' if matches!(chars.get(i + 1), Some('$' | '`' | '\\')) => {
+                push_quoted(&mut parts, &chars[i + 1].to_string());
+                i += 2;
+            }
+            '$' => match parse_expansion(&chars, i, true) {
+                Some((part, end)) => {
+                    parts.push(part);
+                    i = end;
+                }
+                None => {
+                    push_quoted(&mut parts, "$");
+                    i += 1;
+                }
+            },
+            char => {
+                push_quoted(&mut parts, &char.to_string());
+                i += 1;
+            }
+        }
+    }
+
+    parts
+}
+
+// parses the `$((...))` or parameter starting at the `$`, returns it and the index after it
+fn parse_expansion(chars: &[char], start: usize, quoted: bool) -> Option<(WordPart, usize)> {
+    if is_arithmetic_start(chars, start) {
+        let end = find_closing_arithmetic(chars, start + 3);
+        let expression = chars[start + 3..end].iter().collect();
+        return Some((WordPart::Arithmetic(expression), end + 2));
+    }
+
+    let (expression, end) = parse_parameter(chars, start + 1)?;
+    Some((WordPart::Parameter { expression, quoted }, end))
+}
+
+// parses the parameter after a `$`, returns the expression and the index after it
+fn parse_parameter(chars: &[char], start: usize) -> Option<(String, usize)> {
+    match chars.get(start)? {
+        '{' => {
+            let end = find_closing_brace(chars, start + 1);
+            let expression = chars[start + 1..end].iter().collect();
+            Some((expression, end + 1))
+        }
+        '?' | '$' | '#' | '!' | '@' | '*' | '-' | '0'..='9' => {
+            Some((chars[start].to_string(), start + 1))
+        }
+        char if char.is_ascii_alphabetic() || *char == '_' => {
+            let end = (start..chars.len())
+                .find(|i| !(chars[*i].is_ascii_alphanumeric() || chars[*i] == '_'))
+                .unwrap_or(chars.len());
+            Some((chars[start..end].iter().collect(), end))
+        }
+        _ => None,
+    }
+}
+
+// a `$((` with a closing `))`
+fn is_arithmetic_start(chars: &[char], start: usize) -> bool {
+    chars.get(start + 1) == Some(&'(')
+        && chars.get(start + 2) == Some(&'(')
+        && find_closing_arithmetic(chars, start + 3) < chars.len()
+}
+
+// index of the "))" that closes a "$((", nested parentheses are skipped
+fn find_closing_arithmetic(chars: &[char], start: usize) -> usize {
+    let mut depth = 0;
+
+    for i in start..chars.len() {
+        match chars[i] {
+            '(' => depth += 1,
+            ')' if depth == 0 && chars.get(i + 1) == Some(&')') => return i,
+            ')' => depth -= 1,
+            _ => (),
+        }
+    }
+
+    chars.len()
+}
+
+// index of the "}" that closes a "${", nested braces and quotes are skipped
+fn find_closing_brace(chars: &[char], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '\'' => i = find(chars, i + 1, '\''),
+            '{' => depth += 1,
+            '}' if depth == 0 => return i,
+            '}' => depth -= 1,
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    chars.len()
+}
+
+// index of the char, or the end when it's missing
+fn find(chars: &[char], start: usize, char: char) -> usize {
+    (start..chars.len())
+        .find(|i| chars[*i] == char)
+        .unwrap_or(chars.len())
+}
+
+fn push_literal(parts: &mut Vec<WordPart>, text: &str) {
+    match parts.last_mut() {
+        Some(WordPart::Literal(literal)) => literal.push_str(text),
+        _ => parts.push(WordPart::Literal(String::from(text))),
+    }
+}
+
+fn push_quoted(parts: &mut Vec<WordPart>, text: &str) {
+    match parts.last_mut() {
+        Some(WordPart::Quoted(quoted)) => quoted.push_str(text),
+        _ => parts.push(WordPart::Quoted(String::from(text))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use WordPart::{Arithmetic, Literal, Parameter, Quoted, Tilde};
+
+    fn literal(text: &str) -> WordPart {
+        Literal(String::from(text))
+    }
+
+    fn quoted(text: &str) -> WordPart {
+        Quoted(String::from(text))
+    }
+
+    fn parameter(expression: &str, quoted: bool) -> WordPart {
+        Parameter {
+            expression: String::from(expression),
+            quoted,
+        }
+    }
+
+    #[test]
+    fn test_split_words() {
+        let expected = vec![
+            ("echo", vec!["echo"]),
+            ("echo  foo\tbar", vec!["echo", "foo", "bar"]),
+            ("echo 'a b' \"c d\"", vec!["echo", "'a b'", "\"c d\""]),
+            ("echo a\\ b", vec!["echo", "a\\ b"]),
+            ("echo 'a\\nb' c", vec!["echo", "'a\\nb'", "c"]),
+            ("echo 'it'\"'\"'s'", vec!["echo", "'it'\"'\"'s'"]),
+            ("echo ${A:-a b}", vec!["echo", "${A:-a b}"]),
+            ("echo \"${A:-}\" b", vec!["echo", "\"${A:-}\"", "b"]),
+            ("echo $(( 1 + (2) )) b", vec!["echo", "$(( 1 + (2) ))", "b"]),
+            ("(( a = 1 ))", vec!["(( a = 1 ))"]),
+            ("a=(1 '2 3') b+=( c )", vec!["a=(1 '2 3')", "b+=( c )"]),
+            ("", vec![]),
+        ];
+
+        for (raw, words) in expected {
+            assert_eq!(split_words(raw), words, "splitting {}", raw);
+        }
+    }
+
+    #[test]
+    fn test_split_assignment() {
+        let assignment = |name, subscript, append, value| {
+            Some(Assignment {
+                name,
+                subscript,
+                append,
+                value,
+            })
+        };
+
+        assert_eq!(split_assignment("A=1"), assignment("A", None, false, "1"));
+        assert_eq!(
+            split_assignment("_a1='x=y'"),
+            assignment("_a1", None, false, "'x=y'")
+        );
+        assert_eq!(split_assignment("A="), assignment("A", None, false, ""));
+        assert_eq!(split_assignment("A+=b"), assignment("A", None, true, "b"));
+        assert_eq!(
+            split_assignment("a[i+1]+=x"),
+            assignment("a", Some("i+1"), true, "x")
+        );
+        assert_eq!(
+            split_assignment("a[b[0]]=x"),
+            assignment("a", Some("b[0]"), false, "x")
+        );
+        assert_eq!(split_assignment("1A=1"), None);
+        assert_eq!(split_assignment("'A'=1"), None);
+        assert_eq!(split_assignment("=1"), None);
+        assert_eq!(split_assignment("A+1=1"), None);
+        assert_eq!(split_assignment("a[0=1"), None);
+        assert_eq!(split_assignment("echo"), None);
+    }
+
+    #[test]
+    fn test_compound_assignments() {
+        let elements = |raw| split_assignment(raw).and_then(|a| a.elements());
+
+        assert_eq!(
+            elements("a=(x 'y z' [3]=w)"),
+            Some(vec![
+                String::from("x"),
+                String::from("'y z'"),
+                String::from("[3]=w")
+            ])
+        );
+        assert_eq!(elements("a=()"), Some(Vec::new()));
+        assert_eq!(split_keyed_element("[a b]='c'"), Some(("a b", "'c'")));
+        assert_eq!(split_keyed_element("[a]"), None);
+        assert_eq!(elements("a=x"), None);
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("a/b.c=1"), "a/b.c=1");
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("a b"), "'a b'");
+        assert_eq!(quote("it's $A"), "'it'\\''s $A'");
+    }
+
+    #[test]
+    fn test_parse_word() {
+        let expected = vec![
+            ("foo", vec![literal("foo")]),
+            ("*.md", vec![literal("*.md")]),
+            ("'*.md'", vec![quoted("*.md")]),
+            ("\\*.md", vec![quoted("*"), literal(".md")]),
+            (
+                "\"a $B c\"",
+                vec![quoted("a "), parameter("B", true), quoted(" c")],
+            ),
+            ("\"\"", vec![quoted("")]),
+            ("a$B", vec![literal("a"), parameter("B", false)]),
+            ("${B}c", vec![parameter("B", false), literal("c")]),
+            ("$?", vec![parameter("?", false)]),
+            ("$1x", vec![parameter("1", false), literal("x")]),
+            ("a$", vec![literal("a$")]),
+            ("\"\\$a\\b\"", vec![quoted("$a\\b")]),
+            ("'\\'", vec![quoted("\\")]),
+            ("$((1 + $A))x", vec![Arithmetic(String::from("1 + $A")), literal("x")]),
+            ("\"$(( (1) ))\"", vec![quoted(""), Arithmetic(String::from(" (1) "))]),
+            ("$((1)", vec![literal("$((1)")]),
+            ("~", vec![Tilde(String::new())]),
+            ("~/a", vec![Tilde(String::new()), literal("/a")]),
+            ("~bob/a~", vec![Tilde(String::from("bob")), literal("/a~")]),
+            ("~'bob'", vec![literal("~"), quoted("bob")]),
+            ("'~'", vec![quoted("~")]),
+            ("a~", vec![literal("a~")]),
+        ];
+
+        for (raw, parts) in expected {
+            assert_eq!(parse_word(raw), parts, "parsing {}", raw);
+        }
+    }
+
+    #[test]
+    fn test_parse_here_doc() {
+        let parameter = |name: &str| Parameter {
+            expression: String::from(name),
+            quoted: true,
+        };
+
+        let expected = vec![
+            ("'a' \"b\"\n", vec![quoted("'a' \"b\"\n")]),
+            ("$A-$((1))", vec![parameter("A"), quoted("-"), Arithmetic(String::from("1"))]),
+            ("\\$A \\\\ \\n", vec![quoted("$A \\ \\n")]),
+            ("a\\\nb", vec![quoted("ab")]),
+            ("~ $", vec![quoted("~ $")]),
+        ];
+
+        for (body, parts) in expected {
+            assert_eq!(parse_here_doc(body), parts, "parsing {:?}", body);
+        }
+    }
+}
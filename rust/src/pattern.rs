@@ -0,0 +1,284 @@
+// Shell pattern matching as used by globbing, eg: "*.md", "file?.txt", "[a-c]*"
+// A backslash makes the next character literal.
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Char(char),
+    AnyChar,
+    AnyString,
+    Class {
+        negated: bool,
+        items: Vec<ClassItem>,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum ClassItem {
+    Char(char),
+    Range(char, char),
+    Named(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Self {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '*' => {
+                    if tokens.last() != Some(&Token::AnyString) {
+                        tokens.push(Token::AnyString);
+                    }
+                }
+                '?' => tokens.push(Token::AnyChar),
+                '\\' if i + 1 < chars.len() => {
+                    i += 1;
+                    tokens.push(Token::Char(chars[i]));
+                }
+                '[' => match parse_class(&chars[i..]) {
+                    Some((token, length)) => {
+                        tokens.push(token);
+                        i += length - 1;
+                    }
+                    None => tokens.push(Token::Char('[')),
+                },
+                char => tokens.push(Token::Char(char)),
+            }
+
+            i += 1;
+        }
+
+        Pattern { tokens }
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let tokens = &self.tokens;
+
+        // backtracking only has to return to the last star, since a star can match anything
+        let (mut t, mut p) = (0, 0);
+        let mut last_star: Option<(usize, usize)> = None;
+
+        while t < text.len() {
+            match tokens.get(p) {
+                Some(Token::AnyString) => {
+                    last_star = Some((p, t));
+                    p += 1;
+                }
+                Some(token) if token_matches(token, text[t]) => {
+                    t += 1;
+                    p += 1;
+                }
+                _ => match last_star {
+                    Some((star_p, star_t)) => {
+                        last_star = Some((star_p, star_t + 1));
+                        p = star_p + 1;
+                        t = star_t + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        tokens[p..].iter().all(|token| token == &Token::AnyString)
+    }
+
+    // true when the pattern starts with a literal ".", only those match hidden files
+    pub fn starts_with_dot(&self) -> bool {
+        self.tokens.first() == Some(&Token::Char('.'))
+    }
+}
+
+// True when the pattern contains unescaped special characters, a pattern
+// without any is just a string
+pub fn has_special_chars(pattern: &str) -> bool {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '*' | '?' => return true,
+            '[' if parse_class(&chars[i..]).is_some() => return true,
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    false
+}
+
+// Makes every character of the text match literally
+// >> escape("*.md")
+// "\*.md"
+pub fn escape(text: &str) -> String {
+    let mut result = String::new();
+
+    for char in text.chars() {
+        if matches!(char, '*' | '?' | '[' | ']' | '\\') {
+            result.push('\\');
+        }
+
+        result.push(char);
+    }
+
+    result
+}
+
+// Removes the escaping backslashes, the opposite of `escape`
+pub fn unescape(pattern: &str) -> String {
+    let mut result = String::new();
+    let mut chars = pattern.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => result.extend(chars.next()),
+            char => result.push(char),
+        }
+    }
+
+    result
+}
+
+fn token_matches(token: &Token, char: char) -> bool {
+    match token {
+        Token::Char(c) => *c == char,
+        Token::AnyChar => true,
+        Token::AnyString => true,
+        Token::Class { negated, items } => {
+            items.iter().any(|item| item_matches(item, char)) != *negated
+        }
+    }
+}
+
+fn item_matches(item: &ClassItem, char: char) -> bool {
+    match item {
+        ClassItem::Char(c) => *c == char,
+        ClassItem::Range(from, to) => (*from..=*to).contains(&char),
+        ClassItem::Named(name) => match name.as_str() {
+            "alpha" => char.is_alphabetic(),
+            "digit" => char.is_ascii_digit(),
+            "alnum" => char.is_alphanumeric(),
+            "upper" => char.is_uppercase(),
+            "lower" => char.is_lowercase(),
+            "space" => char.is_whitespace(),
+            "blank" => char == ' ' || char == '\t',
+            "punct" => char.is_ascii_punctuation(),
+            "xdigit" => char.is_ascii_hexdigit(),
+            "cntrl" => char.is_control(),
+            "print" => !char.is_control(),
+            "graph" => !char.is_control() && !char.is_whitespace(),
+            _ => false,
+        },
+    }
+}
+
+// Parses a bracket expression starting at "[", returns the token and the amount of chars used.
+// A "]" directly after "[" or "[!" is part of the class. Without a closing "]" it's not a class.
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let mut i = 1;
+    let negated = matches!(chars.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut items = Vec::new();
+    let start = i;
+
+    loop {
+        let char = *chars.get(i)?;
+
+        match char {
+            ']' if i > start => return Some((Token::Class { negated, items }, i + 1)),
+            '[' if chars.get(i + 1) == Some(&':') => {
+                let rest: String = chars[i + 2..].iter().collect();
+                let end = rest.find(":]")?;
+                items.push(ClassItem::Named(String::from(&rest[..end])));
+                i += end + 4;
+                continue;
+            }
+            '\\' => {
+                i += 1;
+                items.push(ClassItem::Char(*chars.get(i)?));
+            }
+            char if chars.get(i + 1) == Some(&'-')
+                && chars.get(i + 2).is_some_and(|c| *c != ']') =>
+            {
+                items.push(ClassItem::Range(char, chars[i + 2]));
+                i += 2;
+            }
+            char => items.push(ClassItem::Char(char)),
+        }
+
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let expected = vec![
+            ("*", "", true),
+            ("*", "anything", true),
+            ("*.md", "README.md", true),
+            ("*.md", "README.txt", false),
+            ("a*b*c", "aXXbYYc", true),
+            ("a*b*c", "aXXbYY", false),
+            ("?", "a", true),
+            ("?", "ab", false),
+            ("file?.txt", "file1.txt", true),
+            ("[abc]", "b", true),
+            ("[abc]", "d", false),
+            ("[!abc]", "d", true),
+            ("[^abc]", "a", false),
+            ("[a-c]x", "bx", true),
+            ("[]]", "]", true),
+            ("[a-]", "-", true),
+            ("[[:digit:]]*", "1abc", true),
+            ("[[:upper:]]", "a", false),
+            ("\\*", "*", true),
+            ("\\*", "a", false),
+            ("[", "[", true),
+            ("a[", "a[", true),
+            ("*a", "aaa", true),
+        ];
+
+        for (pattern, text, result) in expected {
+            assert_eq!(
+                Pattern::new(pattern).matches(text),
+                result,
+                "pattern {} on {}",
+                pattern,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_has_special_chars() {
+        assert!(has_special_chars("*.md"));
+        assert!(has_special_chars("file?"));
+        assert!(has_special_chars("[ab]"));
+        assert!(!has_special_chars("README.md"));
+        assert!(!has_special_chars("\\*.md"));
+        assert!(!has_special_chars("["));
+    }
+
+    #[test]
+    fn test_escape() {
+        let text = "*?[]\\a";
+        assert!(Pattern::new(&escape(text)).matches(text));
+        assert_eq!(unescape(&escape(text)), text);
+    }
+}
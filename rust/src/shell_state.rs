@@ -1,38 +1,248 @@
-#[derive(Default, Debug)]
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::clock::{Clock, ManualClock};
+use crate::executor::resume::{Input, InputRequest, Suspension};
+use crate::exit_status::ExitStatus;
+use crate::filesystem::{self, FileSystem, MemoryFileSystem};
+use crate::options::{SetOptions, ShoptOptions};
+use crate::parser::here_doc::PendingHereDocs;
+use crate::sink::Stream;
+use crate::variable::{Subscript, Value};
+
+#[derive(Debug, Clone)]
 pub struct ShellState {
     pub current_dir: String,
     pub output: ShellOutput,
+    pub fs: Rc<dyn FileSystem>,
+    // statuses of every command in the last pipeline, a single command is a pipeline of one
+    pub pipe_status: Vec<ExitStatus>,
+    pub shopt: ShoptOptions,
+    pub options: SetOptions,
+    // above 0 while the status of the running commands is tested, eg: on the left of `&&`,
+    // errexit doesn't apply to them
+    pub status_tested: usize,
+    // actions of `trap` by signal name, eg: "EXIT", an empty action ignores the signal
+    pub traps: BTreeMap<String, String>,
+    // an action of a trap is running, `$BASH_COMMAND` stays the command that raised it
+    pub running_trap: bool,
+    // set once the shell exits, eg: because of errexit, nothing runs after that
+    pub exit: Option<ExitStatus>,
+    // an error dropped the rest of the running line, eg: `echo $((1/0)); echo skipped`
+    pub aborted: bool,
+    // the host reads the lines from a user, a script exits on errors the user could fix,
+    // eg: an unbound variable
+    pub interactive: bool,
+    pub variables: BTreeMap<String, Value>,
+    // `$1`, `$2`... eg: the arguments of `source`
+    pub positional_parameters: Vec<String>,
+    // `getopts` reads bundled flags one at a time: the `$OPTIND` it's reading
+    // and the position of the next flag in that argument
+    pub getopts_position: (usize, usize),
+    // `alias` definitions, they replace the first word of commands before parsing
+    pub aliases: BTreeMap<String, String>,
+    // a line that waits for the bodies of its here-documents, see `executor::run`
+    pub pending_here_docs: Option<PendingHereDocs>,
+    // the lines of a compound command that isn't closed yet, eg: `case $1 in`
+    pub open_command: Option<String>,
+    // a line that waits for input of `read`, see `executor::resume`
+    pub suspension: Option<Suspension>,
+    // the input the host gave to the line that waited, `read` takes it
+    pub input: Option<Input>,
+    // above 0 while the running commands can't wait for input, eg: in a pipeline or `eval`
+    pub nested: usize,
+    // used by `time`, the clock only moves when it's advanced unless the host sets another
+    pub clock: Rc<dyn Clock>,
+}
+
+// aliases every host defines when the shell starts
+pub const DEFAULT_ALIASES: [(&str, &str); 3] = [("ll", "ls -la"), ("la", "ls -A"), ("l", "ls -CF")];
+
+// by default the shell starts in the root of an empty in memory filesystem
+impl Default for ShellState {
+    fn default() -> Self {
+        ShellState::new(Rc::new(MemoryFileSystem::default()), String::from("/"))
+    }
 }
-#[derive(Default, Debug)]
+
+impl ShellState {
+    pub fn new(fs: Rc<dyn FileSystem>, current_dir: String) -> Self {
+        let mut state = ShellState {
+            current_dir,
+            output: ShellOutput::default(),
+            fs,
+            pipe_status: vec![ExitStatus::SUCCESS],
+            shopt: ShoptOptions::default(),
+            options: SetOptions::default(),
+            status_tested: 0,
+            traps: BTreeMap::new(),
+            running_trap: false,
+            exit: None,
+            aborted: false,
+            interactive: false,
+            variables: BTreeMap::new(),
+            positional_parameters: Vec::new(),
+            getopts_position: (1, 1),
+            aliases: BTreeMap::new(),
+            pending_here_docs: None,
+            open_command: None,
+            suspension: None,
+            input: None,
+            nested: 0,
+            clock: Rc::new(ManualClock::default()),
+        };
+
+        // like POSIX, so `shift $((OPTIND - 1))` works before `getopts` runs
+        state.set_variable("OPTIND", String::from("1"));
+        state
+    }
+
+    // Value of a shell parameter, eg: "?" for the status of the last command.
+    // An array parameter used without subscript is its first element.
+    pub fn parameter(&self, name: &str) -> Option<String> {
+        match name {
+            "?" => Some(self.output.status.to_string()),
+            "PWD" => Some(self.current_dir.clone()),
+            "-" => Some(self.options.flags()),
+            "#" => Some(self.positional_parameters.len().to_string()),
+            "@" | "*" => Some(self.positional_parameters.join(" ")),
+            name if name.starts_with(|c: char| c.is_ascii_digit()) => {
+                let index: usize = name.parse().ok()?;
+                self.positional_parameters.get(index.checked_sub(1)?).cloned()
+            }
+            "PIPESTATUS" => self.pipe_status.first().map(|status| status.to_string()),
+            name => self.variables.get(name)?.scalar().cloned(),
+        }
+    }
+
+    // `NAME=value`, for an array it sets element 0
+    pub fn set_variable(&mut self, name: &str, value: String) {
+        match self.variables.get_mut(name) {
+            Some(Value::String(old_value)) => *old_value = value,
+            Some(array) => {
+                array.set(Subscript::Index(0), value);
+            }
+            None => {
+                self.variables
+                    .insert(String::from(name), Value::String(value));
+            }
+        }
+    }
+
+    pub fn define_default_aliases(&mut self) {
+        for (name, value) in DEFAULT_ALIASES {
+            self.aliases.insert(String::from(name), String::from(value));
+        }
+    }
+
+    // the lines passed to the shell are the body of a here-document
+    pub fn is_reading_here_doc(&self) -> bool {
+        self.pending_here_docs.is_some()
+    }
+
+    // the lines passed to the shell go on with a compound command, eg: after `{`
+    pub fn is_continuing_command(&self) -> bool {
+        self.open_command.is_some()
+    }
+
+    // how the host should get the input the running line waits for, if it waits
+    pub fn input_request(&self) -> Option<&InputRequest> {
+        self.suspension.as_ref().map(|suspension| &suspension.request)
+    }
+
+    // the array a parameter is, eg: `${PIPESTATUS[@]}`, a string is an array of one element
+    pub fn array_parameter(&self, name: &str) -> Option<Value> {
+        match name {
+            "PIPESTATUS" => Some(Value::indexed(
+                self.pipe_status.iter().map(|s| s.to_string()).collect(),
+            )),
+            "@" | "*" => Some(Value::indexed(self.positional_parameters.clone())),
+            name => self.variables.get(name).cloned(),
+        }
+    }
+
+    // absolute path of a path given by the user
+    pub fn resolve_path(&self, path: &str) -> PathBuf {
+        filesystem::resolve(&self.current_dir, path)
+    }
+}
+
+// Data is kept as bytes everywhere so binary content can flow through pipelines,
+// it's only converted to text when it's shown to the user.
+#[derive(Debug, PartialEq, Clone)]
+pub struct OutputChunk {
+    pub stream: Stream,
+    pub bytes: Vec<u8>,
+}
+
+// Output is append-only: commands write chunks in the order they produce them
+// and the host drains them whenever it wants to show them.
+// The exit status of the last command is tracked separately from the chunks.
+#[derive(Default, Debug, Clone)]
 pub struct ShellOutput {
-    pub code: Option<u32>,
-    pub stdout: Option<String>,
-    pub stderr: Option<String>,
-    pub stdin: Option<String>,
+    pub status: ExitStatus,
+    pub stdin: Option<Vec<u8>>,
+    chunks: VecDeque<OutputChunk>,
 }
 
 impl ShellOutput {
     pub fn is_ok(&self) -> bool {
-        self.code == Some(0)
+        self.status.is_success()
     }
 
-    pub fn set_stdout(&mut self, stdout: String) {
-        self.code = Some(0);
-        self.stdout = Some(stdout.clone());
-        self.stderr = None;
+    pub fn write_stdout<T: AsRef<[u8]>>(&mut self, bytes: T) {
+        self.write(Stream::Stdout, bytes.as_ref());
     }
 
-    pub fn set_stderr(&mut self, code: u32, stdout: String) {
-        self.code = Some(code);
-        self.stderr = Some(stdout.clone());
-        self.stdout = None;
+    pub fn write_stderr<T: AsRef<[u8]>>(&mut self, bytes: T) {
+        self.write(Stream::Stderr, bytes.as_ref());
+    }
+
+    // consecutive writes to the same stream are merged in to one chunk
+    pub fn write(&mut self, stream: Stream, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+
+        match self.chunks.back_mut() {
+            Some(chunk) if chunk.stream == stream => chunk.bytes.extend_from_slice(bytes),
+            _ => self.chunks.push_back(OutputChunk {
+                stream,
+                bytes: bytes.to_vec(),
+            }),
+        }
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &OutputChunk> {
+        self.chunks.iter()
+    }
+
+    // removes and returns everything written so far, the exit status is kept
+    pub fn drain(&mut self) -> impl Iterator<Item = OutputChunk> + '_ {
+        self.chunks.drain(..)
+    }
+
+    pub fn stdout(&self) -> Vec<u8> {
+        self.collect(Stream::Stdout)
+    }
+
+    pub fn stderr(&self) -> Vec<u8> {
+        self.collect(Stream::Stderr)
+    }
+
+    fn collect(&self, stream: Stream) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.stream == stream)
+            .flat_map(|chunk| chunk.bytes.iter().copied())
+            .collect()
     }
 
     pub fn clear(&mut self) {
-        self.code = None;
-        self.stdout = None;
-        self.stderr = None;
+        self.status = ExitStatus::SUCCESS;
         self.stdin = None;
+        self.chunks.clear();
     }
 }
 
@@ -41,9 +251,50 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_shell_output_set_stdout() {
+    fn test_shell_output_write_stdout() {
+        let mut shell_output = ShellOutput::default();
+        shell_output.write_stdout("testing");
+        assert_eq!(shell_output.stdout(), b"testing")
+    }
+
+    #[test]
+    fn test_shell_output_appends_instead_of_replacing() {
         let mut shell_output = ShellOutput::default();
-        shell_output.set_stdout(String::from("testing"));
-        assert_eq!(shell_output.stdout, Some(String::from("testing")))
+        shell_output.write_stderr("warning\n");
+        shell_output.write_stdout("a\n");
+        shell_output.write_stdout("b\n");
+
+        assert_eq!(shell_output.stdout(), b"a\nb\n");
+        assert_eq!(shell_output.stderr(), b"warning\n");
+    }
+
+    #[test]
+    fn test_shell_output_keeps_order_between_streams() {
+        let mut shell_output = ShellOutput::default();
+        shell_output.write_stdout("a");
+        shell_output.write_stderr("b");
+        shell_output.write_stdout("c");
+
+        let streams: Vec<Stream> = shell_output.chunks().map(|chunk| chunk.stream).collect();
+        assert_eq!(streams, vec![Stream::Stdout, Stream::Stderr, Stream::Stdout]);
+    }
+
+    #[test]
+    fn test_shell_output_is_binary_safe() {
+        let mut shell_output = ShellOutput::default();
+        shell_output.write_stdout([0xff, 0x00]);
+        shell_output.write_stdout(vec![0xfe]);
+        assert_eq!(shell_output.stdout(), vec![0xff, 0x00, 0xfe])
+    }
+
+    #[test]
+    fn test_shell_output_drain_keeps_status() {
+        let mut shell_output = ShellOutput::default();
+        shell_output.write_stdout("a");
+        shell_output.status = ExitStatus::FAILURE;
+
+        assert_eq!(shell_output.drain().count(), 1);
+        assert_eq!(shell_output.chunks().count(), 0);
+        assert_eq!(shell_output.status, ExitStatus::FAILURE);
     }
 }
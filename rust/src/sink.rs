@@ -0,0 +1,110 @@
+use crate::shell_state::ShellOutput;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+// A sink receives the output of commands in the order it was produced.
+// Natively this is the terminal, in wasm it is a callback provided by the host.
+// Output is passed as bytes, a sink that can only show text has to convert it.
+pub trait OutputSink {
+    fn write(&mut self, stream: Stream, bytes: &[u8]);
+}
+
+// Collects everything written to it, used by tests and by hosts that want to inspect output
+#[derive(Debug, Default)]
+pub struct BufferSink {
+    pub writes: Vec<(Stream, Vec<u8>)>,
+}
+
+impl BufferSink {
+    pub fn stdout(&self) -> Vec<u8> {
+        self.collect(Stream::Stdout)
+    }
+
+    pub fn stderr(&self) -> Vec<u8> {
+        self.collect(Stream::Stderr)
+    }
+
+    fn collect(&self, stream: Stream) -> Vec<u8> {
+        self.writes
+            .iter()
+            .filter(|(s, _)| s == &stream)
+            .flat_map(|(_, bytes)| bytes.iter().copied())
+            .collect()
+    }
+}
+
+impl OutputSink for BufferSink {
+    fn write(&mut self, stream: Stream, bytes: &[u8]) {
+        self.writes.push((stream, bytes.to_vec()));
+    }
+}
+
+// Moves everything the commands wrote in to the sink, in the order it was written.
+// Like a real shell output is not altered, eg: `printf abc` doesn't end with a newline.
+pub fn flush(output: &mut ShellOutput, sink: &mut dyn OutputSink) {
+    for chunk in output.drain() {
+        sink.write(chunk.stream, &chunk.bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flush_writes_chunks_in_order() {
+        let mut output = ShellOutput::default();
+        let mut sink = BufferSink::default();
+
+        output.write_stdout("a\n");
+        output.write_stderr("b\n");
+        output.write_stdout("c\n");
+        flush(&mut output, &mut sink);
+
+        assert_eq!(
+            sink.writes,
+            vec![
+                (Stream::Stdout, b"a\n".to_vec()),
+                (Stream::Stderr, b"b\n".to_vec()),
+                (Stream::Stdout, b"c\n".to_vec()),
+            ]
+        );
+        assert_eq!(output.chunks().count(), 0);
+    }
+
+    #[test]
+    fn test_flush_keeps_the_last_line_unterminated() {
+        let mut output = ShellOutput::default();
+        let mut sink = BufferSink::default();
+
+        output.write_stdout("foo");
+        flush(&mut output, &mut sink);
+
+        assert_eq!(sink.stdout(), b"foo");
+    }
+
+    #[test]
+    fn test_flush_does_not_add_a_second_newline() {
+        let mut output = ShellOutput::default();
+        let mut sink = BufferSink::default();
+
+        output.write_stderr("error\n");
+        flush(&mut output, &mut sink);
+
+        assert_eq!(sink.writes, vec![(Stream::Stderr, b"error\n".to_vec())]);
+    }
+
+    #[test]
+    fn test_flush_keeps_empty_output_empty() {
+        let mut output = ShellOutput::default();
+        let mut sink = BufferSink::default();
+
+        flush(&mut output, &mut sink);
+
+        assert_eq!(sink.writes, vec![]);
+    }
+}
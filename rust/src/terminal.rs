@@ -1,47 +1,216 @@
 
-use std::io::{self, Write};
+use std::cell::RefCell;
+use std::io::{self, IsTerminal, Write};
+use std::process::{self, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::executor::resume::{Input, InputRequest};
+use crate::sink::{OutputSink, Stream};
 
 pub enum ReadResult {
     Ok(String),
-    Empty,
+    Eof,
+    // Ctrl-C while the line was typed
+    Interrupted,
 }
 
-pub fn read_line() -> ReadResult {
-    print_prompt();
+// why a read stopped waiting for its line
+enum Stopped {
+    TimedOut,
+    Interrupted,
+}
+
+// how often a read that waits checks for Ctrl-C
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Ctrl-C sets INTERRUPTED while the shell waits for a line, see `catch_interrupts`
+static WAITING: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // a line that's still being read after `read -t` timed out, the next read gets it
+    static PENDING: RefCell<Option<Receiver<ReadResult>>> = const { RefCell::new(None) };
+}
+
+// Reads a line without its line break. Blank lines are returned too,
+// they matter in the body of a here-document.
+pub fn read_line(prompt: &str) -> ReadResult {
+    print_prompt(prompt);
+
+    match wait_for_line(None) {
+        Ok(result) => result,
+        Err(_) => {
+            println!();
+            ReadResult::Interrupted
+        }
+    }
+}
+
+// Reads the line a `read` waits for, its prompt is written already. Typed chars aren't
+// shown when it's silent. Returns None when Ctrl-C interrupts it.
+pub fn read_input(request: &InputRequest) -> Option<Input> {
+    if request.silent {
+        set_echo(false);
+    }
+
+    let input = match wait_for_line(request.timeout) {
+        Ok(ReadResult::Ok(line)) => Some(Input::Line(line)),
+        Ok(ReadResult::Eof) | Ok(ReadResult::Interrupted) => Some(Input::End),
+        Err(Stopped::TimedOut) => Some(Input::TimedOut),
+        Err(Stopped::Interrupted) => None,
+    };
+
+    if request.silent {
+        set_echo(true);
+    }
+    if input.is_none() {
+        println!();
+    }
+
+    input
+}
+
+// Stdin is read on another thread, so a timeout or Ctrl-C can stop waiting for it.
+// The line that is still being read then goes to the next read.
+fn wait_for_line(timeout: Option<Duration>) -> Result<ReadResult, Stopped> {
+    let receiver = PENDING
+        .with(|pending| pending.borrow_mut().take())
+        .unwrap_or_else(|| {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || sender.send(read_stdin()));
+            receiver
+        });
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    WAITING.store(true, Ordering::SeqCst);
+
+    let result = loop {
+        if INTERRUPTED.swap(false, Ordering::SeqCst) {
+            break Err(Stopped::Interrupted);
+        }
+
+        let wait = match deadline {
+            Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+            None => POLL_INTERVAL,
+        };
+        match receiver.recv_timeout(wait.min(POLL_INTERVAL)) {
+            Ok(result) => break Ok(result),
+            Err(RecvTimeoutError::Disconnected) => break Ok(ReadResult::Eof),
+            Err(RecvTimeoutError::Timeout) => {
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    break Err(Stopped::TimedOut);
+                }
+            }
+        }
+    };
+
+    WAITING.store(false, Ordering::SeqCst);
+    if result.is_err() {
+        PENDING.with(|pending| *pending.borrow_mut() = Some(receiver));
+    }
+
+    result
+}
 
+// Ctrl-C interrupts the line that is typed or read, the host runs the INT trap then.
+// Commands run inside of the shell and can't be stopped halfway, so while one runs
+// Ctrl-C ends the process like it did before.
+#[cfg(unix)]
+pub fn catch_interrupts() {
+    unsafe {
+        signal(SIGINT, on_interrupt as *const () as usize);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn catch_interrupts() {}
+
+#[cfg(unix)]
+const SIGINT: i32 = 2;
+#[cfg(unix)]
+const SIG_DFL: usize = 0;
+
+#[cfg(unix)]
+extern "C" {
+    fn signal(signum: i32, handler: usize) -> usize;
+    fn raise(signum: i32) -> i32;
+}
+
+// only touches atomics or calls functions that are safe in a signal handler
+#[cfg(unix)]
+extern "C" fn on_interrupt(signum: i32) {
+    if WAITING.load(Ordering::SeqCst) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        return;
+    }
+
+    unsafe {
+        signal(signum, SIG_DFL);
+        raise(signum);
+    }
+}
+
+fn read_stdin() -> ReadResult {
     let mut command = String::new();
-    io::stdin()
+    let read = io::stdin()
         .read_line(&mut command)
         .expect("Failed to read command");
 
-    let trimmed = String::from(command.trim());
-    match command.eq("") {
-        false => ReadResult::Ok(trimmed),
-        true => ReadResult::Empty,
+    if read == 0 {
+        return ReadResult::Eof;
     }
+
+    ReadResult::Ok(String::from(command.trim_end_matches(&['\n', '\r'][..])))
 }
 
-fn print_prompt() {
-    let prompt_char = "%";
+// turns showing the typed chars on or off, only a terminal shows them
+fn set_echo(on: bool) {
+    if !io::stdin().is_terminal() {
+        return;
+    }
+
+    let setting = if on { "echo" } else { "-echo" };
+    let _ = process::Command::new("stty")
+        .arg(setting)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .status();
+}
 
-    print!("{} ", prompt_char);
+fn print_prompt(prompt: &str) {
+    print!("{} ", prompt);
     io::stdout().flush().unwrap();
 }
 
-// pub fn print_result(shell_state: &ShellState) {
-//     match &shell_state.output {
-//         ShellOutput {
-//             code: Some(0),
-//             stdout: Some(text),
-//             stderr: _,
-//             stdin: _,
-//         } => println!("{}", text),
-//         ShellOutput {
-//             code: Some(_),
-//             stderr: Some(text),
-//             stdout: _,
-//             stdin: _,
-//         } => eprintln!("{}", text),
-//         _ => ()
-//     }
-// }
+// Writes stdout to the process stdout and stderr to the process stderr
+pub struct TerminalSink {
+    // the last byte written ended a line, the REPL starts its prompt on a fresh line
+    pub at_line_start: bool,
+}
+
+impl Default for TerminalSink {
+    fn default() -> Self {
+        TerminalSink {
+            at_line_start: true,
+        }
+    }
+}
+
+impl OutputSink for TerminalSink {
+    fn write(&mut self, stream: Stream, bytes: &[u8]) {
+        if let Some(last) = bytes.last() {
+            self.at_line_start = *last == b'\n';
+        }
+
+        // a terminal can handle any bytes, so output is written without conversion
+        let result = match stream {
+            Stream::Stdout => io::stdout().write_all(bytes).and_then(|_| io::stdout().flush()),
+            Stream::Stderr => io::stderr().write_all(bytes),
+        };
+
+        result.expect("Failed to write output");
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+// The value of a shell variable: a string or an array of strings
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    String(String),
+    // `arr=(a b c)`, elements can be set at any index, eg: `arr[10]=d`
+    Indexed(BTreeMap<usize, String>),
+    // `declare -A map`, elements have string keys
+    Associative(BTreeMap<String, String>),
+}
+
+// An element of an array, `arr[-1]` is the last element of an indexed array
+#[derive(Debug, PartialEq, Clone)]
+pub enum Subscript {
+    Index(i64),
+    Key(String),
+}
+
+impl Value {
+    // >> Value::indexed(vec!["a", "b"])
+    // Indexed({0: "a", 1: "b"})
+    pub fn indexed(values: Vec<String>) -> Value {
+        Value::Indexed(values.into_iter().enumerate().collect())
+    }
+
+    pub fn is_associative(&self) -> bool {
+        matches!(self, Value::Associative(_))
+    }
+
+    // A string is the same as an array with only element 0, so `$arr` is `${arr[0]}`
+    pub fn scalar(&self) -> Option<&String> {
+        match self {
+            Value::String(value) => Some(value),
+            Value::Indexed(elements) => elements.get(&0),
+            Value::Associative(elements) => elements.get("0"),
+        }
+    }
+
+    // the elements in the order of their index, `${arr[@]}`
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            Value::String(value) => vec![value.clone()],
+            Value::Indexed(elements) => elements.values().cloned().collect(),
+            Value::Associative(elements) => elements.values().cloned().collect(),
+        }
+    }
+
+    // the indexes or keys of the elements, `${!arr[@]}`
+    pub fn keys(&self) -> Vec<String> {
+        match self {
+            Value::String(_) => vec![String::from("0")],
+            Value::Indexed(elements) => elements.keys().map(|i| i.to_string()).collect(),
+            Value::Associative(elements) => elements.keys().cloned().collect(),
+        }
+    }
+
+    pub fn get(&self, subscript: &Subscript) -> Option<&String> {
+        match (self, subscript) {
+            (Value::Associative(elements), Subscript::Key(key)) => elements.get(key),
+            (Value::Associative(_), Subscript::Index(_)) => None,
+            (_, Subscript::Index(index)) => {
+                let index = self.resolve_index(*index)?;
+                match self {
+                    Value::Indexed(elements) => elements.get(&index),
+                    _ => self.scalar().filter(|_| index == 0),
+                }
+            }
+            (_, Subscript::Key(_)) => None,
+        }
+    }
+
+    // A string becomes an indexed array when an element is set.
+    // Fails for a negative index before the first element.
+    pub fn set(&mut self, subscript: Subscript, value: String) -> Option<()> {
+        let index = match (&mut *self, subscript) {
+            (Value::Associative(elements), Subscript::Key(key)) => {
+                elements.insert(key, value);
+                return Some(());
+            }
+            (Value::Associative(elements), Subscript::Index(index)) => {
+                elements.insert(index.to_string(), value);
+                return Some(());
+            }
+            (_, Subscript::Key(_)) => return None,
+            (_, Subscript::Index(index)) => self.resolve_index(index)?,
+        };
+
+        if let Value::String(scalar) = self {
+            *self = Value::indexed(vec![std::mem::take(scalar)]);
+        }
+        if let Value::Indexed(elements) = self {
+            elements.insert(index, value);
+        }
+
+        Some(())
+    }
+
+    pub fn remove(&mut self, subscript: &Subscript) -> Option<String> {
+        match (&mut *self, subscript) {
+            (Value::Associative(elements), Subscript::Key(key)) => elements.remove(key),
+            (Value::Indexed(_), Subscript::Index(index)) => {
+                let index = self.resolve_index(*index)?;
+                match self {
+                    Value::Indexed(elements) => elements.remove(&index),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // `arr+=(d e)` adds elements after the last one
+    pub fn push(&mut self, value: String) {
+        let index = self.next_index();
+        self.set(Subscript::Index(index as i64), value);
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Value::String(_) => 1,
+            Value::Indexed(elements) => elements.len(),
+            Value::Associative(elements) => elements.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The value as `declare -p` and `set` show it, so the shell can read it back
+    // >> Value::indexed(vec!["a", "b c"]).format()
+    // "([0]=\"a\" [1]=\"b c\")"
+    pub fn format(&self) -> String {
+        let elements: Vec<String> = match self {
+            Value::String(value) => return double_quote(value),
+            Value::Indexed(elements) => elements
+                .iter()
+                .map(|(index, value)| format!("[{}]={}", index, double_quote(value)))
+                .collect(),
+            Value::Associative(elements) => elements
+                .iter()
+                .map(|(key, value)| format!("[{}]={}", key, double_quote(value)))
+                .collect(),
+        };
+
+        format!("({})", elements.join(" "))
+    }
+
+    fn next_index(&self) -> usize {
+        match self {
+            Value::Indexed(elements) => elements.keys().next_back().map_or(0, |last| last + 1),
+            _ => 1,
+        }
+    }
+
+    // negative indexes count back from the end
+    fn resolve_index(&self, index: i64) -> Option<usize> {
+        match index < 0 {
+            true => (self.next_index() as i64 + index).try_into().ok(),
+            false => Some(index as usize),
+        }
+    }
+}
+
+fn double_quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for char in value.chars() {
+        if "\"\\$`".contains(char) {
+            quoted.push('\\');
+        }
+        quoted.push(char);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indexed(values: &[&str]) -> Value {
+        Value::indexed(values.iter().map(|value| String::from(*value)).collect())
+    }
+
+    #[test]
+    fn test_indexes() {
+        let mut value = indexed(&["a", "b"]);
+        value.set(Subscript::Index(5), String::from("c")).unwrap();
+        value.push(String::from("d"));
+
+        assert_eq!(value.values(), vec!["a", "b", "c", "d"]);
+        assert_eq!(value.keys(), vec!["0", "1", "5", "6"]);
+        assert_eq!(value.get(&Subscript::Index(-1)), Some(&String::from("d")));
+        assert_eq!(value.get(&Subscript::Index(-2)), Some(&String::from("c")));
+        assert_eq!(value.get(&Subscript::Index(-3)), None);
+        assert_eq!(value.get(&Subscript::Index(-8)), None);
+
+        assert_eq!(value.remove(&Subscript::Index(-1)), Some(String::from("d")));
+        assert_eq!(value.set(Subscript::Index(-7), String::new()), None);
+        assert_eq!(value.len(), 3);
+    }
+
+    #[test]
+    fn test_strings_become_arrays() {
+        let mut value = Value::String(String::from("a"));
+        assert_eq!(value.get(&Subscript::Index(0)), Some(&String::from("a")));
+        assert_eq!(value.get(&Subscript::Index(1)), None);
+
+        value.set(Subscript::Index(2), String::from("c")).unwrap();
+        assert_eq!(
+            value,
+            Value::Indexed(
+                vec![(0, String::from("a")), (2, String::from("c"))]
+                    .into_iter()
+                    .collect()
+            )
+        );
+        assert_eq!(value.scalar(), Some(&String::from("a")));
+    }
+
+    #[test]
+    fn test_associative() {
+        let mut value = Value::Associative(BTreeMap::new());
+        value
+            .set(Subscript::Key(String::from("b")), String::from("2"))
+            .unwrap();
+        value
+            .set(Subscript::Key(String::from("a")), String::from("1"))
+            .unwrap();
+
+        assert!(value.is_associative());
+        assert_eq!(value.keys(), vec!["a", "b"]);
+        assert_eq!(value.values(), vec!["1", "2"]);
+        assert_eq!(
+            value.get(&Subscript::Key(String::from("a"))),
+            Some(&String::from("1"))
+        );
+        assert_eq!(
+            value.remove(&Subscript::Key(String::from("b"))),
+            Some(String::from("2"))
+        );
+        assert_eq!(value.scalar(), None);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            Value::String(String::from("a \"b\"")).format(),
+            "\"a \\\"b\\\"\""
+        );
+        assert_eq!(indexed(&["a", "$b"]).format(), "([0]=\"a\" [1]=\"\\$b\")");
+        assert_eq!(Value::Associative(BTreeMap::new()).format(), "()");
+    }
+}
@@ -1,13 +1,23 @@
 import {Logo} from './logo'
-import init, {run_command} from '../../rust/pkg'
+import init, {Shell} from '../../rust/pkg'
 
 
 export function App() {
-    init();
+    let shell: Shell | undefined;
+
+    init().then(() => {
+        shell = new Shell((stream: string, text: string) => {
+            if (stream === "stderr") {
+                console.error(text);
+            } else {
+                console.log(text);
+            }
+        });
+    });
 
     function click() {
         const cmd = prompt("Command:") || "echo foo"
-        run_command(cmd);
+        shell?.run(cmd);
     }
 
     return (